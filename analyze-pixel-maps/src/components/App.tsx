@@ -26,8 +26,10 @@ function App(): JSX.Element {
       console.log(`Loading pixel contributions from ${url}`);
 
       PixelContributionMaps.from_reader(url).then((pixel_contrib: PixelContributionMaps): void => {
-        console.log(`Loaded pixel contributions for ${pixel_contrib.size()} maps`);
-        setPixelContrib(pixel_contrib);
+        console.log(`Loaded pixel contributions for ${pixel_contrib.size()} maps at the distances ${pixel_contrib.get_distances()}`);
+
+        // the maps at other camera distances share the angles of the fitted maps
+        setPixelContrib(pixel_contrib.filter_distance(0));
       });
     }
   }, []);
@@ -10,7 +10,7 @@ import {
 } from 'chart.js';
 import { useEffect, useState } from "react";
 import { Line } from 'react-chartjs-2';
-import { AnglePixelContribInterpolator, LinearPixelContribInterpolator, PixelContribInterpolator, QuadraticPixelContribInterpolator } from "../interpolate";
+import { AnglePixelContribInterpolator, LinearPixelContribInterpolator, PchipPixelContribInterpolator, PixelContribInterpolator, QuadraticPixelContribInterpolator } from "../interpolate";
 import { PixelContributionMaps } from "rs-analyze-pixel-maps";
 
 ChartJS.register(
@@ -143,6 +143,7 @@ export function InterpolateAngleGraph(props: InterpolateAngleGraphProps): JSX.El
         const linear_interpolation = createInterpolationDataSeries([99, 255, 132], new LinearPixelContribInterpolator(contrib_maps), contrib_maps, pixel_pos);
         const angle_interpolation = createInterpolationDataSeries([99, 132, 255], new AnglePixelContribInterpolator(contrib_maps), contrib_maps, pixel_pos);
         const quadratic_interpolation = createInterpolationDataSeries([128, 128, 128], new QuadraticPixelContribInterpolator(contrib_maps), contrib_maps, pixel_pos);
+        const pchip_interpolation = createInterpolationDataSeries([255, 160, 64], new PchipPixelContribInterpolator(contrib_maps), contrib_maps, pixel_pos);
 
         setDataSeries({
             labels,
@@ -155,7 +156,8 @@ export function InterpolateAngleGraph(props: InterpolateAngleGraphProps): JSX.El
                 },
                 linear_interpolation,
                 angle_interpolation,
-                quadratic_interpolation
+                quadratic_interpolation,
+                pchip_interpolation
             ],
         });
 
@@ -1,4 +1,4 @@
-import { LinearAngle, PixelContributionMaps, QuadraticAngle, TangentAngle } from "rs-analyze-pixel-maps";
+import { LinearAngle, PchipAngle, PixelContributionMaps, QuadraticAngle, TangentAngle } from "rs-analyze-pixel-maps";
 
 /**
  * An interpolator for pixel contributions. For a given angle and position on the pixel contribution
@@ -82,6 +82,32 @@ export class QuadraticPixelContribInterpolator implements PixelContribInterpolat
         this.interpolator = new QuadraticAngle(contrib_maps);
     }
 
+    public interpolate(angle: number, pos: [number, number]): number {
+        return this.interpolator.interpolate(angle, pos[0], pos[1]);
+    }
+}
+
+/**
+ * A monotone cubic interpolator (PCHIP) for pixel contributions that interpolates using the first,
+ * middle and last pixel contribution map based on the angle as input.
+ * In contrast to the quadratic interpolator, the result never overshoots the values of the maps
+ * and angles outside of the range of the maps are clamped.
+ */
+export class PchipPixelContribInterpolator implements PixelContribInterpolator {
+    private interpolator: PchipAngle;
+
+    public readonly name = "PCHIP";
+
+    public constructor(contrib_maps: PixelContributionMaps) {
+        const n = contrib_maps.size();
+
+        if (n <= 2) {
+            throw new Error("Not enough contribution maps given");
+        }
+
+        this.interpolator = new PchipAngle(contrib_maps);
+    }
+
     public interpolate(angle: number, pos: [number, number]): number {
         return this.interpolator.interpolate(angle, pos[0], pos[1]);
     }
@@ -3,6 +3,7 @@ mod bounding_sphere;
 mod frustum;
 mod plane;
 mod ray;
+mod sphere_fitting;
 mod utils;
 
 pub use aabb::*;
@@ -10,6 +11,7 @@ pub use bounding_sphere::*;
 pub use frustum::*;
 pub use plane::*;
 pub use ray::*;
+pub use sphere_fitting::*;
 pub use utils::*;
 
 use nalgebra_glm::{vec4_to_vec3, Mat4, Vec3, Vec4};
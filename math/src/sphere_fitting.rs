@@ -0,0 +1,469 @@
+use nalgebra_glm::{DVec3, Vec3};
+
+use crate::BoundingSphere;
+
+/// The relative tolerance for a point to be considered inside a sphere. Compensates for the
+/// rounding errors of the spheres computed from their support points.
+const CONTAINMENT_EPSILON: f64 = 1e-7;
+
+/// The directions along which the extremal points are determined by the EPOS algorithm. These
+/// are the 13 directions of the EPOS-26 variant.
+const EPOS_DIRECTIONS: [[f64; 3]; 13] = [
+    [1.0, 0.0, 0.0],
+    [0.0, 1.0, 0.0],
+    [0.0, 0.0, 1.0],
+    [1.0, 1.0, 1.0],
+    [1.0, 1.0, -1.0],
+    [1.0, -1.0, 1.0],
+    [1.0, -1.0, -1.0],
+    [1.0, 1.0, 0.0],
+    [1.0, -1.0, 0.0],
+    [1.0, 0.0, 1.0],
+    [1.0, 0.0, -1.0],
+    [0.0, 1.0, 1.0],
+    [0.0, 1.0, -1.0],
+];
+
+/// The algorithm for fitting a bounding sphere to a set of points.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BoundingSphereAlgorithm {
+    /// The center of the AABB with the distance to the farthest point as radius. Fast, but
+    /// inflates the radius for skewed point sets.
+    #[default]
+    AabbCenter,
+
+    /// Ritter's algorithm, which grows the sphere spanned by two distant points until all
+    /// points are enclosed. Usually 5-20% larger than the minimal sphere.
+    Ritter,
+
+    /// The extremal points optimal sphere (EPOS-26) by Larsson, which computes the minimal
+    /// sphere of the extremal points along 13 directions and grows it like Ritter's algorithm.
+    /// Usually within a few percent of the minimal sphere.
+    Epos,
+
+    /// Welzl's algorithm for the exact minimal enclosing sphere.
+    Welzl,
+}
+
+/// Fits a bounding sphere to the given points using the given algorithm. Returns the default
+/// sphere for an empty set of points.
+///
+/// # Arguments
+/// * `points` - The points to enclose.
+/// * `algorithm` - The algorithm for fitting the sphere.
+pub fn fit_bounding_sphere(points: &[Vec3], algorithm: BoundingSphereAlgorithm) -> BoundingSphere {
+    if points.is_empty() {
+        return BoundingSphere::default();
+    }
+
+    match algorithm {
+        BoundingSphereAlgorithm::AabbCenter => {
+            BoundingSphere::new_from_iter(points.iter().copied())
+        }
+        BoundingSphereAlgorithm::Ritter => {
+            let points: Vec<DVec3> = points.iter().map(to_f64).collect();
+            to_bounding_sphere(ritter(&points))
+        }
+        BoundingSphereAlgorithm::Epos => {
+            let points: Vec<DVec3> = points.iter().map(to_f64).collect();
+            to_bounding_sphere(epos(&points))
+        }
+        BoundingSphereAlgorithm::Welzl => {
+            let mut points: Vec<DVec3> = points.iter().map(to_f64).collect();
+            to_bounding_sphere(welzl(&mut points))
+        }
+    }
+}
+
+/// A sphere in double precision, which is used internally for robustness.
+#[derive(Debug, Clone, Copy)]
+struct Sphere {
+    /// The center of the sphere.
+    center: DVec3,
+
+    /// The radius of the sphere.
+    radius: f64,
+}
+
+impl Sphere {
+    /// Creates the sphere with radius 0 at the given point.
+    ///
+    /// # Arguments
+    /// * `p` - The center of the sphere.
+    fn from_point(p: &DVec3) -> Self {
+        Self {
+            center: *p,
+            radius: 0.0,
+        }
+    }
+
+    /// Creates the smallest sphere through the two given points.
+    ///
+    /// # Arguments
+    /// * `a` - The first point.
+    /// * `b` - The second point.
+    fn from_two(a: &DVec3, b: &DVec3) -> Self {
+        let center = (a + b) * 0.5;
+
+        Self {
+            center,
+            radius: (a - center).norm(),
+        }
+    }
+
+    /// Creates the smallest sphere through the three given points, i.e., the sphere of the
+    /// circumcircle. Falls back to the sphere of the two farthest points for collinear points.
+    ///
+    /// # Arguments
+    /// * `a` - The first point.
+    /// * `b` - The second point.
+    /// * `c` - The third point.
+    fn from_three(a: &DVec3, b: &DVec3, c: &DVec3) -> Self {
+        let ab = b - a;
+        let ac = c - a;
+        let n = ab.cross(&ac);
+        let denom = 2.0 * n.norm_squared();
+        if denom <= f64::EPSILON * ab.norm_squared() * ac.norm_squared() {
+            return Self::enclosing([*a, *b, *c].iter());
+        }
+
+        let offset = (n.cross(&ab) * ac.norm_squared() + ac.cross(&n) * ab.norm_squared()) / denom;
+        let center = a + offset;
+
+        Self {
+            center,
+            radius: offset.norm(),
+        }
+    }
+
+    /// Creates the sphere through the four given points, i.e., the circumsphere of the
+    /// tetrahedron. Falls back to the smallest sphere of the points for coplanar points.
+    ///
+    /// # Arguments
+    /// * `a` - The first point.
+    /// * `b` - The second point.
+    /// * `c` - The third point.
+    /// * `d` - The fourth point.
+    fn from_four(a: &DVec3, b: &DVec3, c: &DVec3, d: &DVec3) -> Self {
+        let ab = b - a;
+        let ac = c - a;
+        let ad = d - a;
+        let det = ab.dot(&ac.cross(&ad));
+        let scale = ab.norm() * ac.norm() * ad.norm();
+        if det.abs() <= 1e-12 * scale {
+            return Self::enclosing([*a, *b, *c, *d].iter());
+        }
+
+        let offset = (ac.cross(&ad) * ab.norm_squared()
+            + ad.cross(&ab) * ac.norm_squared()
+            + ab.cross(&ac) * ad.norm_squared())
+            / (2.0 * det);
+        let center = a + offset;
+
+        Self {
+            center,
+            radius: offset.norm(),
+        }
+    }
+
+    /// Returns the smallest of the spheres through pairs of the given points, which encloses
+    /// all of them. Used for degenerate configurations of support points.
+    ///
+    /// # Arguments
+    /// * `points` - The points to enclose.
+    fn enclosing<'a, I: Iterator<Item = &'a DVec3> + Clone>(points: I) -> Self {
+        let mut best: Option<Sphere> = None;
+        for (i, a) in points.clone().enumerate() {
+            for b in points.clone().skip(i + 1) {
+                let sphere = Self::from_two(a, b);
+                if points.clone().all(|p| sphere.contains(p))
+                    && best.is_none_or(|s| sphere.radius < s.radius)
+                {
+                    best = Some(sphere);
+                }
+            }
+        }
+
+        best.unwrap_or_else(|| ritter(&points.copied().collect::<Vec<_>>()))
+    }
+
+    /// Returns true if the given point is inside the sphere, up to a small relative tolerance.
+    ///
+    /// # Arguments
+    /// * `p` - The point to test.
+    #[inline]
+    fn contains(&self, p: &DVec3) -> bool {
+        (p - self.center).norm() <= self.radius * (1.0 + CONTAINMENT_EPSILON) + CONTAINMENT_EPSILON
+    }
+
+    /// Grows the sphere, s.t. it encloses the given point, by moving its center towards the
+    /// point.
+    ///
+    /// # Arguments
+    /// * `p` - The point to enclose.
+    #[inline]
+    fn grow(&mut self, p: &DVec3) {
+        let d = (p - self.center).norm();
+        if d > self.radius {
+            let radius = (self.radius + d) * 0.5;
+            self.center += (p - self.center) * ((radius - self.radius) / d);
+            self.radius = radius;
+        }
+    }
+}
+
+/// Computes the bounding sphere using Ritter's algorithm.
+///
+/// # Arguments
+/// * `points` - The non-empty set of points to enclose.
+fn ritter(points: &[DVec3]) -> Sphere {
+    let farthest_from = |q: &DVec3| {
+        points
+            .iter()
+            .max_by(|a, b| (*a - q).norm_squared().total_cmp(&(*b - q).norm_squared()))
+            .copied()
+            .unwrap()
+    };
+
+    let y = farthest_from(&points[0]);
+    let z = farthest_from(&y);
+
+    let mut sphere = Sphere::from_two(&y, &z);
+    points.iter().for_each(|p| sphere.grow(p));
+
+    sphere
+}
+
+/// Computes the bounding sphere using the EPOS-26 algorithm.
+///
+/// # Arguments
+/// * `points` - The non-empty set of points to enclose.
+fn epos(points: &[DVec3]) -> Sphere {
+    // small sets are solved exactly
+    if points.len() <= 2 * EPOS_DIRECTIONS.len() {
+        return welzl(&mut points.to_vec());
+    }
+
+    let mut extremal_points = Vec::with_capacity(2 * EPOS_DIRECTIONS.len());
+    for direction in EPOS_DIRECTIONS {
+        let direction = DVec3::from(direction);
+        let projection = |p: &&DVec3| p.dot(&direction);
+
+        let min = points
+            .iter()
+            .min_by(|a, b| projection(a).total_cmp(&projection(b)))
+            .unwrap();
+        let max = points
+            .iter()
+            .max_by(|a, b| projection(a).total_cmp(&projection(b)))
+            .unwrap();
+
+        extremal_points.push(*min);
+        extremal_points.push(*max);
+    }
+
+    let mut sphere = welzl(&mut extremal_points);
+    points.iter().for_each(|p| sphere.grow(p));
+
+    sphere
+}
+
+/// Computes the minimal enclosing sphere using Welzl's algorithm in its iterative form. The
+/// points are shuffled deterministically, which yields a linear expected running time.
+///
+/// # Arguments
+/// * `points` - The non-empty set of points to enclose. The points are reordered.
+fn welzl(points: &mut [DVec3]) -> Sphere {
+    shuffle(points);
+
+    let mut sphere = Sphere::from_point(&points[0]);
+    for i in 1..points.len() {
+        if sphere.contains(&points[i]) {
+            continue;
+        }
+
+        // the point i is on the boundary of the minimal sphere of the points 0..=i
+        sphere = Sphere::from_point(&points[i]);
+        for j in 0..i {
+            if sphere.contains(&points[j]) {
+                continue;
+            }
+
+            sphere = Sphere::from_two(&points[i], &points[j]);
+            for k in 0..j {
+                if sphere.contains(&points[k]) {
+                    continue;
+                }
+
+                sphere = Sphere::from_three(&points[i], &points[j], &points[k]);
+                for l in 0..k {
+                    if !sphere.contains(&points[l]) {
+                        sphere = Sphere::from_four(&points[i], &points[j], &points[k], &points[l]);
+                    }
+                }
+            }
+        }
+    }
+
+    sphere
+}
+
+/// Shuffles the given points with a fixed seed, s.t. the results are reproducible.
+///
+/// # Arguments
+/// * `points` - The points to shuffle.
+fn shuffle(points: &mut [DVec3]) {
+    // xorshift64
+    let mut state = 0x9e3779b97f4a7c15u64;
+    for i in (1..points.len()).rev() {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+
+        points.swap(i, (state % (i as u64 + 1)) as usize);
+    }
+}
+
+/// Converts the given single precision point to double precision.
+///
+/// # Arguments
+/// * `p` - The point to convert.
+#[inline]
+fn to_f64(p: &Vec3) -> DVec3 {
+    DVec3::new(p.x as f64, p.y as f64, p.z as f64)
+}
+
+/// Converts the given double precision sphere into a bounding sphere. The radius is enlarged
+/// by the rounding of the center and the containment tolerance, s.t. the sphere stays
+/// conservative.
+///
+/// # Arguments
+/// * `sphere` - The sphere to convert.
+fn to_bounding_sphere(sphere: Sphere) -> BoundingSphere {
+    let center = Vec3::new(
+        sphere.center.x as f32,
+        sphere.center.y as f32,
+        sphere.center.z as f32,
+    );
+
+    let center_error = (to_f64(&center) - sphere.center).norm();
+    let radius = sphere.radius * (1.0 + CONTAINMENT_EPSILON) + CONTAINMENT_EPSILON + center_error;
+
+    BoundingSphere::from((center, (radius as f32).next_up()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const ALGORITHMS: [BoundingSphereAlgorithm; 4] = [
+        BoundingSphereAlgorithm::AabbCenter,
+        BoundingSphereAlgorithm::Ritter,
+        BoundingSphereAlgorithm::Epos,
+        BoundingSphereAlgorithm::Welzl,
+    ];
+
+    /// Creates reproducible pseudo-random points of a skewed set, i.e., the corners and the
+    /// interior of a right-angled tetrahedron, whose AABB center is far from the center of its
+    /// minimal sphere.
+    fn create_skewed_points() -> Vec<Vec3> {
+        let mut state = 12345u32;
+        let mut random = move || {
+            state = state.wrapping_mul(1664525).wrapping_add(1013904223);
+            (state >> 8) as f32 / (1u32 << 24) as f32 * 10.0
+        };
+
+        let mut points = vec![
+            Vec3::zeros(),
+            Vec3::new(10.0, 0.0, 0.0),
+            Vec3::new(0.0, 10.0, 0.0),
+            Vec3::new(0.0, 0.0, 10.0),
+        ];
+        points.extend(
+            (0..4000)
+                .map(|_| Vec3::new(random(), random(), random()))
+                .filter(|p| p.x + p.y + p.z <= 10.0),
+        );
+
+        points
+    }
+
+    #[test]
+    fn test_all_points_are_enclosed() {
+        let points = create_skewed_points();
+
+        for algorithm in ALGORITHMS {
+            let sphere = fit_bounding_sphere(&points, algorithm);
+            for p in points.iter() {
+                assert!(
+                    (p - sphere.center).norm() <= sphere.radius,
+                    "{:?} does not enclose {}",
+                    algorithm,
+                    p
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_minimal_sphere_is_smallest() {
+        let points = create_skewed_points();
+        let minimal = fit_bounding_sphere(&points, BoundingSphereAlgorithm::Welzl);
+
+        for algorithm in ALGORITHMS {
+            let sphere = fit_bounding_sphere(&points, algorithm);
+            assert!(minimal.radius <= sphere.radius * 1.0001, "{:?}", algorithm);
+        }
+
+        // the aabb center inflates the radius of the skewed set
+        let aabb = fit_bounding_sphere(&points, BoundingSphereAlgorithm::AabbCenter);
+        assert!(aabb.radius > minimal.radius * 1.05);
+
+        let epos = fit_bounding_sphere(&points, BoundingSphereAlgorithm::Epos);
+        assert!(epos.radius < minimal.radius * 1.05);
+    }
+
+    #[test]
+    fn test_minimal_sphere_of_simple_sets() {
+        let assert_sphere = |points: &[Vec3], center: Vec3, radius: f32| {
+            let sphere = fit_bounding_sphere(points, BoundingSphereAlgorithm::Welzl);
+            assert!((sphere.center - center).norm() < 1e-5, "{}", sphere.center);
+            assert!((sphere.radius - radius).abs() < 1e-5, "{}", sphere.radius);
+        };
+
+        let p = Vec3::new(1.0, 2.0, 3.0);
+        assert_sphere(&[p], p, 0.0);
+        assert_sphere(&[p, -p], Vec3::zeros(), p.norm());
+
+        // an obtuse triangle is bounded by the sphere of its longest side
+        let triangle = [
+            Vec3::new(-1.0, 0.0, 0.0),
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(0.0, 0.1, 0.0),
+        ];
+        assert_sphere(&triangle, Vec3::zeros(), 1.0);
+
+        // an equilateral triangle is bounded by its circumcircle
+        let triangle = [
+            Vec3::new(1.0, 0.0, 0.0),
+            Vec3::new(-0.5, 0.75f32.sqrt(), 0.0),
+            Vec3::new(-0.5, -(0.75f32.sqrt()), 0.0),
+        ];
+        assert_sphere(&triangle, Vec3::zeros(), 1.0);
+
+        // the corners of a cube with collinear and coplanar points
+        let center = Vec3::new(0.5, -2.0, 4.0);
+        let cube: Vec<Vec3> = (0..27)
+            .map(|i| {
+                center + Vec3::new((i % 3) as f32, ((i / 3) % 3) as f32, (i / 9) as f32)
+                    - Vec3::new(1.0, 1.0, 1.0)
+            })
+            .collect();
+        assert_sphere(&cube, center, 3f32.sqrt());
+
+        assert_eq!(
+            fit_bounding_sphere(&[], BoundingSphereAlgorithm::Welzl).radius,
+            0.0
+        );
+    }
+}
@@ -1,16 +1,136 @@
 mod options;
 
-use std::{io::Write, time::Instant};
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    sync::{Arc, Mutex},
+    time::{Instant, SystemTime},
+};
 
 use anyhow::Result;
 use clap::Parser;
 use log::{error, info, LevelFilter};
+use nalgebra_glm::Vec2;
 use options::{Options, PixelContribColorMap};
 use pixel_contrib::{
-    compute_contribution_map, CameraConfig, GrayScaleColorMap, PixelContributionMapFile, PixelContributionMapImageExport, PixelContributionOptions, TurboColorMap
+    compute_contribution_maps_with_objects, compute_contribution_maps_with_variance,
+    resume_contribution_maps_with_objects, resume_contribution_maps_with_variance, CameraConfig,
+    CancellationToken, GrayScaleColorMap, MapSink, PixelContributionMapImageExport,
+    PixelContributionOptions, TerminalProgress, TurboColorMap,
+};
+use pixel_contrib_types::{
+    CameraFrustum, PixelContribColorMapDescriptor, PixelContributionMap,
+    PixelContributionMapsWriter, PixelContributionMetadata,
 };
-use pixel_contrib_types::PixelContributionMaps;
-use rasterizer::{simple_rasterizer::SimpleRasterizer, Scene, Stats, StatsNodeTrait};
+use rasterizer::{simple_rasterizer::SimpleRasterizer, Renderer, Scene, Stats, StatsNodeTrait};
+
+/// Writes each finished contribution map as image and appends it to the map files, s.t. a crash
+/// does not lose the maps finished so far.
+struct ContributionMapFileSink {
+    /// The color map used for the images of the maps.
+    color_map: PixelContribColorMap,
+
+    /// The writer of the contribution maps.
+    contrib_maps_writer: Mutex<PixelContributionMapsWriter<BufWriter<File>>>,
+
+    /// The writer of the variance maps, if they should be written.
+    variance_maps_writer: Option<Mutex<PixelContributionMapsWriter<BufWriter<File>>>>,
+
+    /// The writers of the contribution maps of each object, if they should be written.
+    object_maps_writers: Vec<Mutex<PixelContributionMapsWriter<BufWriter<File>>>>,
+}
+
+impl ContributionMapFileSink {
+    /// Writes the number of written maps to the map files and closes them.
+    fn finalize(self) -> Result<()> {
+        self.contrib_maps_writer.into_inner().unwrap().finalize()?;
+        if let Some(variance_maps_writer) = self.variance_maps_writer {
+            variance_maps_writer.into_inner().unwrap().finalize()?;
+        }
+        for object_maps_writer in self.object_maps_writers {
+            object_maps_writer.into_inner().unwrap().finalize()?;
+        }
+
+        Ok(())
+    }
+}
+
+impl MapSink for ContributionMapFileSink {
+    fn map_finished(
+        &self,
+        channel: usize,
+        contrib_map: &PixelContributionMap,
+        variance_map: &PixelContributionMap,
+    ) -> pixel_contrib::Result<()> {
+        // the maps of the objects are only written to their map files
+        if channel > 0 {
+            self.object_maps_writers[channel - 1]
+                .lock()
+                .unwrap()
+                .write_map(contrib_map)?;
+
+            return Ok(());
+        }
+
+        let image_file_name = image_file_name(&contrib_map.descriptor);
+
+        match self.color_map {
+            PixelContribColorMap::Grayscale => {
+                info!("Write contribution images Grayscale '{}'", image_file_name);
+                contrib_map.write_image(&image_file_name, GrayScaleColorMap::new())?;
+            }
+            PixelContribColorMap::Rgb => {
+                info!("Write contribution images RGB '{}'", image_file_name);
+                contrib_map.write_image(&image_file_name, TurboColorMap::new())?;
+            }
+        }
+
+        info!(
+            "Write contribution map for camera angle {}",
+            contrib_map.descriptor.camera_angle()
+        );
+        self.contrib_maps_writer
+            .lock()
+            .unwrap()
+            .write_map(contrib_map)?;
+
+        if let Some(variance_maps_writer) = &self.variance_maps_writer {
+            variance_maps_writer
+                .lock()
+                .unwrap()
+                .write_map(variance_map)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the name of the image file for the map with the given descriptor. The frustum is only
+/// part of the name, if it is not quadratic and symmetric, and the distance only, if it is not
+/// the distance fitting the frustum, s.t. the maps of all camera configs have distinct names.
+///
+/// # Arguments
+/// * `descriptor` - The descriptor of the map.
+fn image_file_name(descriptor: &PixelContribColorMapDescriptor) -> String {
+    let mut name = format!("contrib_map_angle_{}", descriptor.camera_angle());
+
+    let frustum = descriptor.frustum();
+    let offset = frustum.offset();
+    if frustum.aspect_ratio() != 1f32 || offset != Vec2::zeros() {
+        name += &format!(
+            "_aspect_{}_offset_{}_{}",
+            frustum.aspect_ratio(),
+            offset.x,
+            offset.y
+        );
+    }
+
+    if descriptor.distance() > 0f32 {
+        name += &format!("_distance_{}", descriptor.distance());
+    }
+
+    name + ".png"
+}
 
 /// Parses the program arguments and returns None, if no arguments were provided and Some otherwise.
 fn parse_args() -> Result<Options> {
@@ -73,11 +193,25 @@ fn execute_pixel_contribution_program(options: &Options, scene: &Scene) -> Resul
 
     let render_options = options.get_render_options();
 
+    anyhow::ensure!(
+        options.aspect_ratio.is_finite() && options.aspect_ratio > 0f32,
+        "The aspect ratio must be positive, but is {}",
+        options.aspect_ratio
+    );
+
     let camera_configs: Vec<CameraConfig> = options
         .camera
         .iter()
         .map(|fovy| {
-            if *fovy > 0f32 {
+            if options.aspect_ratio != 1f32 {
+                let frustum = if *fovy > 0f32 {
+                    CameraFrustum::perspective(*fovy, options.aspect_ratio)
+                } else {
+                    CameraFrustum::orthographic(options.aspect_ratio)
+                };
+
+                pixel_contrib::CameraConfig::Frustum(frustum)
+            } else if *fovy > 0f32 {
                 pixel_contrib::CameraConfig::Perspective { fovy: *fovy }
             } else {
                 pixel_contrib::CameraConfig::Orthographic
@@ -90,47 +224,131 @@ fn execute_pixel_contribution_program(options: &Options, scene: &Scene) -> Resul
         info!("  {}", camera_config.to_string());
     }
 
+    anyhow::ensure!(
+        !options.resume || options.checkpoint.is_some(),
+        "Resuming requires a checkpoint file"
+    );
+
     let mut render_stats = Default::default();
 
-    let mut contrib_maps = PixelContributionMaps::new();
-    for camera_config in camera_configs.iter() {
-        let contrib_option = PixelContributionOptions {
-            render_options: render_options.clone(),
-
-            num_threads: options.num_threads,
-            contrib_map_size: options.size_pixel_contrib,
-            camera_config: *camera_config,
-        };
-
-        let contrib_map = compute_contribution_map::<SimpleRasterizer>(
-            scene,
-            Stats::root(),
-            &contrib_option,
-            &mut render_stats,
-        );
+    let sphere_fitting = options.get_sphere_fitting()?;
+
+    let mut metadata = PixelContributionMetadata::new();
+    metadata.set_source_model(options.input_file.to_string_lossy());
+    metadata.set_bounding_sphere(&sphere_fitting.bounding_sphere(scene));
+    metadata.set_frame_size(render_options.frame_size);
+    metadata.set_renderer(SimpleRasterizer::NAME);
+    metadata.set_creation_time(SystemTime::now());
+
+    // the maps are written as soon as they are finished, s.t. a crash does not lose all results
+    let variance_maps_writer = if options.write_variance {
+        Some(Mutex::new(PixelContributionMapsWriter::create(
+            "contrib_maps_variance.bin",
+            &metadata,
+            options.get_map_encoding(),
+        )?))
+    } else {
+        None
+    };
+    let mut object_maps_writers = Vec::new();
+    if options.per_object {
+        for index in 0..scene.get_instances().len() {
+            let mut object_metadata = metadata.clone();
+            object_metadata.set_instance_index(index);
+
+            let file_name = format!("contrib_maps_object_{}.bin", index);
+            info!(
+                "Write contribution maps of object {} to '{}'",
+                index, file_name
+            );
+            object_maps_writers.push(Mutex::new(PixelContributionMapsWriter::create(
+                &file_name,
+                &object_metadata,
+                options.get_map_encoding(),
+            )?));
+        }
+    }
 
-        let image_file_name = format!("contrib_map_angle_{}.png", camera_config.angle());
+    let map_sink = Arc::new(ContributionMapFileSink {
+        color_map: options.color_map,
+        contrib_maps_writer: Mutex::new(PixelContributionMapsWriter::create(
+            "contrib_maps.bin",
+            &metadata,
+            options.get_map_encoding(),
+        )?),
+        variance_maps_writer,
+        object_maps_writers,
+    });
+
+    // all maps are computed in one pass sharing the geometry and the renderers
+    let mut contrib_option = PixelContributionOptions {
+        render_options: render_options.clone(),
+
+        num_threads: options.num_threads,
+        contrib_map_size: options.size_pixel_contrib,
+        camera_config: camera_configs[0],
+        camera_distances: options.camera_distances.clone(),
+        parameterization: options.parameterization.into(),
+        sphere_fitting,
+        sampling: options.get_direction_sampling(),
+        samples_per_texel: options.samples_per_texel,
+        checkpoint: options.get_checkpoint_options(),
+        progress: Arc::new(TerminalProgress::new()),
+        map_sink: Some(map_sink.clone()),
+        cancellation: CancellationToken::new(),
+    };
 
-        match options.color_map {
-            PixelContribColorMap::Grayscale => {
-                info!("Write contribution images Grayscale '{}'", image_file_name);
-                contrib_map.write_image(&image_file_name, GrayScaleColorMap::new())?;
-            }
-            PixelContribColorMap::Rgb => {
-                info!("Write contribution images RGB '{}'", image_file_name);
-                contrib_map.write_image(&image_file_name, TurboColorMap::new())?;
-            }
+    // the maps of the objects are computed from the same renders as the maps of the whole scene
+    match (options.resume, options.per_object) {
+        (false, false) => {
+            compute_contribution_maps_with_variance::<SimpleRasterizer>(
+                scene,
+                Stats::root(),
+                &contrib_option,
+                &camera_configs,
+                &mut render_stats,
+            )?;
+        }
+        (false, true) => {
+            compute_contribution_maps_with_objects::<SimpleRasterizer>(
+                scene,
+                Stats::root(),
+                &contrib_option,
+                &camera_configs,
+                &mut render_stats,
+            )?;
+        }
+        (true, false) => {
+            resume_contribution_maps_with_variance::<SimpleRasterizer>(
+                scene,
+                Stats::root(),
+                &contrib_option,
+                &camera_configs,
+                &mut render_stats,
+            )?;
+        }
+        (true, true) => {
+            resume_contribution_maps_with_objects::<SimpleRasterizer>(
+                scene,
+                Stats::root(),
+                &contrib_option,
+                &camera_configs,
+                &mut render_stats,
+            )?;
         }
-
-        contrib_maps.add_map(contrib_map);
     }
 
+    // release the sink held by the options, s.t. the map files can be finalized
+    contrib_option.map_sink = None;
+    Arc::into_inner(map_sink)
+        .expect("The map sink is still in use")
+        .finalize()?;
+
     let duration = start.elapsed();
     let secs = duration.as_secs_f64();
     print_stats(secs, render_options.frame_size, num_triangles);
-
-    info!("Write contribution maps");
-    contrib_maps.write_file("contrib_maps.bin")?;
+    info!("Rendered frames: {}", render_stats.num_frames);
+    info!("Saved renders: {}", render_stats.num_frames_saved);
 
     Ok(())
 }
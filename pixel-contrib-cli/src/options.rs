@@ -1,8 +1,12 @@
-use std::path::PathBuf;
+use std::{path::PathBuf, time::Duration};
+
+use anyhow::Result;
 
 use clap::{Parser, ValueEnum};
 use log::{info, LevelFilter};
-use rasterizer::RenderOptions;
+use pixel_contrib::{CheckpointOptions, DirectionSampling, SphereFitting};
+use pixel_contrib_types::{Compression, MapEncoding, SphereParameterization, ValueEncoding};
+use rasterizer::{BoundingSphere, BoundingSphereAlgorithm, RenderOptions};
 
 /// The color map for the pixel contribution.
 #[derive(ValueEnum, Clone, Copy, Debug)]
@@ -14,6 +18,110 @@ pub enum PixelContribColorMap {
     Rgb,
 }
 
+/// The encoding of the values in the resulting pixel contribution map file.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum MapValueEncoding {
+    /// 32-bit floats without loss of precision.
+    F32,
+
+    /// 16-bit half floats.
+    F16,
+
+    /// 8-bit quantized values.
+    Q8,
+
+    /// 12-bit quantized values.
+    Q12,
+
+    /// 16-bit quantized values.
+    Q16,
+}
+
+impl From<MapValueEncoding> for ValueEncoding {
+    fn from(value: MapValueEncoding) -> Self {
+        match value {
+            MapValueEncoding::F32 => ValueEncoding::F32,
+            MapValueEncoding::F16 => ValueEncoding::F16,
+            MapValueEncoding::Q8 => ValueEncoding::Quantized8,
+            MapValueEncoding::Q12 => ValueEncoding::Quantized12,
+            MapValueEncoding::Q16 => ValueEncoding::Quantized16,
+        }
+    }
+}
+
+/// The compression of the values in the resulting pixel contribution map file.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum MapCompression {
+    /// No compression.
+    None,
+
+    /// Deflate compression.
+    Deflate,
+}
+
+impl From<MapCompression> for Compression {
+    fn from(value: MapCompression) -> Self {
+        match value {
+            MapCompression::None => Compression::None,
+            MapCompression::Deflate => Compression::Deflate,
+        }
+    }
+}
+
+/// The parameterization of the camera directions onto the pixel contribution map.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum MapParameterization {
+    /// Octahedral mapping of the full sphere.
+    Octahedral,
+
+    /// Octahedral mapping of the upper hemisphere only.
+    HemiOctahedral,
+
+    /// Equal-area mapping of the full sphere.
+    EqualArea,
+
+    /// Cube map with the six faces arranged in a 3x2 grid.
+    CubeMap,
+}
+
+impl From<MapParameterization> for SphereParameterization {
+    fn from(value: MapParameterization) -> Self {
+        match value {
+            MapParameterization::Octahedral => SphereParameterization::Octahedral,
+            MapParameterization::HemiOctahedral => SphereParameterization::HemiOctahedral,
+            MapParameterization::EqualArea => SphereParameterization::EqualArea,
+            MapParameterization::CubeMap => SphereParameterization::CubeMap,
+        }
+    }
+}
+
+/// The algorithm for fitting the bounding sphere, to which the camera is fitted.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum SphereAlgorithm {
+    /// The center of the AABB with the distance to the farthest vertex as radius.
+    AabbCenter,
+
+    /// Ritter's approximate bounding sphere.
+    Ritter,
+
+    /// The extremal points optimal sphere, which is close to the minimal sphere.
+    Epos,
+
+    /// The exact minimal bounding sphere using Welzl's algorithm.
+    Welzl,
+}
+
+impl From<SphereAlgorithm> for BoundingSphereAlgorithm {
+    fn from(value: SphereAlgorithm) -> Self {
+        match value {
+            SphereAlgorithm::AabbCenter => BoundingSphereAlgorithm::AabbCenter,
+            SphereAlgorithm::Ritter => BoundingSphereAlgorithm::Ritter,
+            SphereAlgorithm::Epos => BoundingSphereAlgorithm::Epos,
+            SphereAlgorithm::Welzl => BoundingSphereAlgorithm::Welzl,
+        }
+    }
+}
+
 /// Workaround for parsing the different log level
 #[derive(ValueEnum, Clone, Copy, Debug)]
 pub enum LogLevel {
@@ -67,6 +175,75 @@ pub struct Options {
     /// The list of field of views for the camera in radians. 0 means orthographic camera.
     #[arg(short = 'a', long, value_parser, num_args = 1.., default_value = "1.5708", value_delimiter = ',')]
     pub camera: Vec<f32>,
+
+    /// The aspect ratio, i.e., width / height, of the camera frustum. Values other than 1 store
+    /// the non-square frustum with the maps.
+    #[arg(long, default_value_t = 1f32)]
+    pub aspect_ratio: f32,
+
+    /// The list of distances between the camera and the center of the bounding sphere as
+    /// multiples of its radius, for which maps are computed in addition to the distance fitting
+    /// the sphere into the frustum. Each distance must be larger than 1.
+    #[arg(long, value_parser, value_delimiter = ',')]
+    pub camera_distances: Vec<f32>,
+
+    /// If given, only a coarse grid of directions is rendered and refined where the contribution
+    /// varies by more than the tolerance. The remaining directions are interpolated.
+    #[arg(long)]
+    pub adaptive_tolerance: Option<f32>,
+
+    /// The number of jittered camera directions rendered and averaged for each texel.
+    #[arg(long, default_value_t = 1usize)]
+    pub samples_per_texel: usize,
+
+    /// If set, the variance of the jittered samples of each texel is written to
+    /// `contrib_maps_variance.bin`.
+    #[arg(long, default_value_t = false)]
+    pub write_variance: bool,
+
+    /// If set, the maps of each instance of the scene are additionally written to
+    /// `contrib_maps_object_<index>.bin`. All instances share the bounding sphere of the scene.
+    #[arg(long, default_value_t = false)]
+    pub per_object: bool,
+
+    /// If given, the computed texels are periodically written to this checkpoint file.
+    #[arg(long)]
+    pub checkpoint: Option<PathBuf>,
+
+    /// The minimal number of seconds between two written checkpoints.
+    #[arg(long, default_value_t = 300u64)]
+    pub checkpoint_interval: u64,
+
+    /// If set, the computation is resumed from the checkpoint file. Requires `--checkpoint`, and
+    /// fails if the checkpoint has been computed for a different model or different options.
+    #[arg(long, default_value_t = false)]
+    pub resume: bool,
+
+    /// The algorithm for fitting the bounding sphere, to which the camera is fitted.
+    #[arg(long, value_enum, default_value_t = SphereAlgorithm::AabbCenter)]
+    pub sphere_algorithm: SphereAlgorithm,
+
+    /// The bounding sphere given as "x,y,z,radius", to which the camera is fitted. Overrides
+    /// the sphere algorithm, e.g., to match the sphere used at runtime.
+    #[arg(
+        long,
+        value_parser,
+        value_delimiter = ',',
+        allow_negative_numbers = true
+    )]
+    pub bounding_sphere: Option<Vec<f32>>,
+
+    /// The parameterization of the camera directions onto the pixel contribution map.
+    #[arg(long, value_enum, default_value_t = MapParameterization::Octahedral)]
+    pub parameterization: MapParameterization,
+
+    /// The encoding of the values in the resulting pixel contribution map file.
+    #[arg(short, value_enum, long, default_value_t = MapValueEncoding::F32)]
+    pub encoding: MapValueEncoding,
+
+    /// The compression of the values in the resulting pixel contribution map file.
+    #[arg(long, value_enum, default_value_t = MapCompression::None)]
+    pub compression: MapCompression,
 }
 
 impl Options {
@@ -84,6 +261,49 @@ impl Options {
         }
     }
 
+    /// Returns the sampling of the camera directions.
+    pub fn get_direction_sampling(&self) -> DirectionSampling {
+        match self.adaptive_tolerance {
+            Some(tolerance) => DirectionSampling::Adaptive { tolerance },
+            None => DirectionSampling::Full,
+        }
+    }
+
+    /// Returns the bounding sphere, to which the camera is fitted. Fails for invalid spheres.
+    pub fn get_sphere_fitting(&self) -> Result<SphereFitting> {
+        match self.bounding_sphere.as_deref() {
+            Some([x, y, z, radius]) => {
+                anyhow::ensure!(
+                    [*x, *y, *z, *radius].iter().all(|v| v.is_finite()) && *radius > 0f32,
+                    "The bounding sphere must be finite with a positive radius"
+                );
+
+                Ok(SphereFitting::Sphere(BoundingSphere::from((
+                    nalgebra_glm::Vec3::new(*x, *y, *z),
+                    *radius,
+                ))))
+            }
+            Some(values) => anyhow::bail!(
+                "The bounding sphere requires 4 values, but got {}",
+                values.len()
+            ),
+            None => Ok(SphereFitting::Algorithm(self.sphere_algorithm.into())),
+        }
+    }
+
+    /// Returns the options for writing checkpoints, if a checkpoint file is given.
+    pub fn get_checkpoint_options(&self) -> Option<CheckpointOptions> {
+        self.checkpoint.as_ref().map(|path| CheckpointOptions {
+            path: path.clone(),
+            interval: Duration::from_secs(self.checkpoint_interval),
+        })
+    }
+
+    /// Returns the encoding used for writing the pixel contribution map file.
+    pub fn get_map_encoding(&self) -> MapEncoding {
+        MapEncoding::new(self.encoding.into(), self.compression.into())
+    }
+
     /// Dumps the options parameter to the log.
     pub fn dump_to_log(&self) {
         info!("Log-Level: {:?}", self.log_level);
@@ -92,5 +312,19 @@ impl Options {
 
         info!("num_threads: {}", self.num_threads);
         info!("size_buffer: {}", self.size_buffer);
+        info!("aspect_ratio: {}", self.aspect_ratio);
+        info!("camera_distances: {:?}", self.camera_distances);
+        info!("adaptive_tolerance: {:?}", self.adaptive_tolerance);
+        info!("samples_per_texel: {}", self.samples_per_texel);
+        info!("write_variance: {}", self.write_variance);
+        info!("per_object: {}", self.per_object);
+        info!("checkpoint: {:?}", self.checkpoint);
+        info!("checkpoint_interval: {}", self.checkpoint_interval);
+        info!("resume: {}", self.resume);
+        info!("sphere_algorithm: {:?}", self.sphere_algorithm);
+        info!("bounding_sphere: {:?}", self.bounding_sphere);
+        info!("parameterization: {:?}", self.parameterization);
+        info!("encoding: {:?}", self.encoding);
+        info!("compression: {:?}", self.compression);
     }
 }
@@ -0,0 +1,674 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{Read, Seek, SeekFrom, Write},
+    ops::Range,
+    path::Path,
+};
+
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+
+use crate::{Error, MapEncoding, PixelContributionMaps, Result};
+
+const PIXEL_CONTRIBUTION_ARCHIVE_VERSION: u32 = 1;
+const PIXEL_CONTRIBUTION_ARCHIVE_IDENTIFIER: [u8; 4] = *b"PCMA";
+
+/// The size of the archive header, i.e., identifier, version and offset of the table of contents.
+const HEADER_SIZE: u64 = 16;
+
+/// The size of the header of each segment of the table of contents, i.e., the offset of the
+/// previous segment and the number of entries.
+const SEGMENT_HEADER_SIZE: u64 = 12;
+
+/// The minimal size of a serialized entry of the table of contents, i.e., an entry with an empty
+/// name.
+const MIN_ENTRY_SIZE: u64 = 8 + 4 + 8 + 8;
+
+/// A single entry of the table of contents of a pixel contribution archive.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ArchiveEntry {
+    /// The unique id of the asset.
+    pub id: u64,
+
+    /// The unique name of the asset.
+    pub name: String,
+
+    /// The offset in bytes of the serialized maps within the archive.
+    offset: u64,
+
+    /// The length in bytes of the serialized maps.
+    length: u64,
+}
+
+impl ArchiveEntry {
+    /// Returns the byte range of the serialized maps within the archive. The range can be used
+    /// to create a [`crate::PixelContributionMapsView`] onto a memory-mapped archive.
+    #[inline]
+    pub fn byte_range(&self) -> Range<usize> {
+        self.offset as usize..(self.offset + self.length) as usize
+    }
+}
+
+/// An archive that packs the pixel contribution maps of many assets into a single file.
+///
+/// The archive consists of a header pointing to the last segment of the table of contents. Each
+/// segment lists the entries added with it and the offset of the previous segment, i.e., the
+/// segments form a chain back to the first one. Each asset is stored as a complete pixel
+/// contribution maps file. Appending an asset writes the new maps and a segment with only the
+/// new entry behind the existing data and patches the offset in the header afterwards, s.t. the
+/// archive is never rewritten and stays valid if the append is interrupted.
+///
+/// Opening an archive follows the whole chain, i.e., reads one segment per append. The chain can
+/// be merged into a single segment by rewriting the archive with
+/// [`PixelContributionArchive::compact_into`] or [`PixelContributionArchive::compact_file`],
+/// which also drops the data left behind by interrupted appends.
+pub struct PixelContributionArchive<S> {
+    stream: S,
+    entries: Vec<ArchiveEntry>,
+
+    /// The size in bytes of all segments of the table of contents.
+    toc_size: u64,
+}
+
+impl<S: Read + Seek> PixelContributionArchive<S> {
+    /// Opens the archive from the given stream. Only the table of contents is read, the
+    /// individual entries are loaded on demand.
+    ///
+    /// # Arguments
+    /// * `stream` - The stream from which the archive is read.
+    pub fn open(mut stream: S) -> Result<Self> {
+        stream.seek(SeekFrom::Start(0))?;
+
+        let mut identifier = [0u8; 4];
+        stream.read_exact(&mut identifier)?;
+        if identifier != PIXEL_CONTRIBUTION_ARCHIVE_IDENTIFIER {
+            return Err(Error::IO("Invalid archive identifier".to_string()));
+        }
+
+        let version = stream.read_u32::<LittleEndian>()?;
+        if version != PIXEL_CONTRIBUTION_ARCHIVE_VERSION {
+            return Err(Error::IO("Invalid archive version".to_string()));
+        }
+
+        let toc_offset = stream.read_u64::<LittleEndian>()?;
+        let (entries, toc_size) = Self::read_toc(&mut stream, toc_offset)?;
+
+        Ok(Self {
+            stream,
+            entries,
+            toc_size,
+        })
+    }
+
+    /// Returns the entries of the table of contents in the order they have been added.
+    #[inline]
+    pub fn entries(&self) -> &[ArchiveEntry] {
+        &self.entries
+    }
+
+    /// Returns the entry for the asset with the given id.
+    ///
+    /// # Arguments
+    /// * `id` - The id of the asset.
+    pub fn find_by_id(&self, id: u64) -> Option<&ArchiveEntry> {
+        self.entries.iter().find(|e| e.id == id)
+    }
+
+    /// Returns the entry for the asset with the given name.
+    ///
+    /// # Arguments
+    /// * `name` - The name of the asset.
+    pub fn find_by_name(&self, name: &str) -> Option<&ArchiveEntry> {
+        self.entries.iter().find(|e| e.name == name)
+    }
+
+    /// Loads the pixel contribution maps of the given entry.
+    ///
+    /// # Arguments
+    /// * `entry` - The entry to load.
+    pub fn load(&mut self, entry: &ArchiveEntry) -> Result<PixelContributionMaps> {
+        self.stream.seek(SeekFrom::Start(entry.offset))?;
+
+        let mut reader = (&mut self.stream).take(entry.length);
+        PixelContributionMaps::from_reader(&mut reader)
+    }
+
+    /// Loads the pixel contribution maps of the asset with the given id.
+    ///
+    /// # Arguments
+    /// * `id` - The id of the asset.
+    pub fn load_by_id(&mut self, id: u64) -> Result<PixelContributionMaps> {
+        let entry = self
+            .find_by_id(id)
+            .cloned()
+            .ok_or_else(|| Error::InvalidArgument(format!("No asset with id {}", id)))?;
+
+        self.load(&entry)
+    }
+
+    /// Loads the pixel contribution maps of the asset with the given name.
+    ///
+    /// # Arguments
+    /// * `name` - The name of the asset.
+    pub fn load_by_name(&mut self, name: &str) -> Result<PixelContributionMaps> {
+        let entry = self
+            .find_by_name(name)
+            .cloned()
+            .ok_or_else(|| Error::InvalidArgument(format!("No asset with name '{}'", name)))?;
+
+        self.load(&entry)
+    }
+
+    /// Consumes the archive and returns the underlying stream.
+    pub fn into_inner(self) -> S {
+        self.stream
+    }
+
+    /// Returns the number of bytes in the archive that are not referenced, i.e., the data left
+    /// behind by interrupted appends.
+    pub fn unused_bytes(&mut self) -> Result<u64> {
+        let file_size = self.stream.seek(SeekFrom::End(0))?;
+        let used_size =
+            HEADER_SIZE + self.toc_size + self.entries.iter().map(|e| e.length).sum::<u64>();
+
+        Ok(file_size.saturating_sub(used_size))
+    }
+
+    /// Copies all assets into a new archive in the given stream, whose table of contents consists
+    /// of a single segment. The serialized maps are copied as they are without decoding them.
+    ///
+    /// # Arguments
+    /// * `stream` - The stream into which the compacted archive is written.
+    pub fn compact_into<T: Read + Write + Seek>(
+        &mut self,
+        mut stream: T,
+    ) -> Result<PixelContributionArchive<T>> {
+        stream.seek(SeekFrom::Start(0))?;
+
+        stream.write_all(&PIXEL_CONTRIBUTION_ARCHIVE_IDENTIFIER)?;
+        stream.write_u32::<LittleEndian>(PIXEL_CONTRIBUTION_ARCHIVE_VERSION)?;
+        stream.write_u64::<LittleEndian>(0)?;
+
+        let mut entries = Vec::with_capacity(self.entries.len());
+        let mut offset = HEADER_SIZE;
+        for entry in self.entries.iter() {
+            self.stream.seek(SeekFrom::Start(entry.offset))?;
+
+            let mut data = Vec::new();
+            (&mut self.stream)
+                .take(entry.length)
+                .read_to_end(&mut data)?;
+            if data.len() as u64 != entry.length {
+                return Err(Error::IO("Unexpected end of archive".to_string()));
+            }
+
+            stream.write_all(&data)?;
+            entries.push(ArchiveEntry {
+                offset,
+                ..entry.clone()
+            });
+
+            offset += entry.length;
+        }
+
+        // Write the table of contents behind the maps and patch the header to point to it
+        PixelContributionArchive::<T>::write_toc_segment(&mut stream, 0, &entries)?;
+
+        stream.seek(SeekFrom::Start(8))?;
+        stream.write_u64::<LittleEndian>(offset)?;
+        stream.flush()?;
+
+        Ok(PixelContributionArchive {
+            stream,
+            toc_size: segment_size(&entries),
+            entries,
+        })
+    }
+
+    /// Reads the table of contents, whose last segment is at the given offset. Returns the
+    /// entries in the order they have been added and the size in bytes of all segments.
+    ///
+    /// # Arguments
+    /// * `stream` - The stream from which the table of contents is read.
+    /// * `toc_offset` - The offset of the last segment of the table of contents.
+    fn read_toc(stream: &mut S, toc_offset: u64) -> Result<(Vec<ArchiveEntry>, u64)> {
+        let stream_size = stream.seek(SeekFrom::End(0))?;
+
+        let mut segments = Vec::new();
+        let mut toc_size = 0;
+        let mut segment_offset = toc_offset;
+        loop {
+            let (previous_offset, entries) =
+                Self::read_toc_segment(stream, segment_offset, stream_size)?;
+            toc_size += segment_size(&entries);
+            segments.push(entries);
+
+            // the segments are written one after the other, s.t. the chain cannot contain cycles
+            match previous_offset {
+                0 => break,
+                offset if offset >= HEADER_SIZE && offset < segment_offset => {
+                    segment_offset = offset
+                }
+                _ => {
+                    return Err(Error::IO(
+                        "Invalid table of contents segment offset".to_string(),
+                    ))
+                }
+            }
+        }
+
+        let entries = segments.into_iter().rev().flatten().collect();
+
+        Ok((entries, toc_size))
+    }
+
+    /// Reads the segment of the table of contents at the given offset. Returns the offset of the
+    /// previous segment, which is 0 for the first segment, and the entries of the segment.
+    ///
+    /// # Arguments
+    /// * `stream` - The stream from which the segment is read.
+    /// * `segment_offset` - The offset of the segment.
+    /// * `stream_size` - The size of the stream in bytes.
+    fn read_toc_segment(
+        stream: &mut S,
+        segment_offset: u64,
+        stream_size: u64,
+    ) -> Result<(u64, Vec<ArchiveEntry>)> {
+        stream.seek(SeekFrom::Start(segment_offset))?;
+
+        let previous_offset = stream.read_u64::<LittleEndian>()?;
+        let num_entries = stream.read_u32::<LittleEndian>()? as u64;
+
+        // a corrupt number of entries must not request more memory than the stream can hold
+        let remaining = stream_size.saturating_sub(segment_offset + SEGMENT_HEADER_SIZE);
+        if num_entries > remaining / MIN_ENTRY_SIZE {
+            return Err(Error::IO("Unexpected end of archive".to_string()));
+        }
+
+        let mut entries = Vec::with_capacity(num_entries as usize);
+        for _ in 0..num_entries {
+            let id = stream.read_u64::<LittleEndian>()?;
+
+            let name_len = stream.read_u32::<LittleEndian>()? as u64;
+            let mut name = Vec::new();
+            stream.take(name_len).read_to_end(&mut name)?;
+            if name.len() as u64 != name_len {
+                return Err(Error::IO("Unexpected end of archive".to_string()));
+            }
+            let name = String::from_utf8(name)
+                .map_err(|_| Error::IO("Invalid UTF-8 in asset name".to_string()))?;
+
+            let offset = stream.read_u64::<LittleEndian>()?;
+            let length = stream.read_u64::<LittleEndian>()?;
+            if offset < HEADER_SIZE || offset.saturating_add(length) > segment_offset {
+                return Err(Error::IO(format!(
+                    "Invalid entry range for asset '{}'",
+                    name
+                )));
+            }
+
+            entries.push(ArchiveEntry {
+                id,
+                name,
+                offset,
+                length,
+            });
+        }
+
+        Ok((previous_offset, entries))
+    }
+}
+
+impl<S: Read + Write + Seek> PixelContributionArchive<S> {
+    /// Creates a new empty archive in the given stream.
+    ///
+    /// # Arguments
+    /// * `stream` - The stream into which the archive is written.
+    pub fn create(mut stream: S) -> Result<Self> {
+        stream.seek(SeekFrom::Start(0))?;
+
+        stream.write_all(&PIXEL_CONTRIBUTION_ARCHIVE_IDENTIFIER)?;
+        stream.write_u32::<LittleEndian>(PIXEL_CONTRIBUTION_ARCHIVE_VERSION)?;
+        stream.write_u64::<LittleEndian>(HEADER_SIZE)?;
+
+        // write the empty table of contents
+        Self::write_toc_segment(&mut stream, 0, &[])?;
+        stream.flush()?;
+
+        Ok(Self {
+            stream,
+            entries: Vec::new(),
+            toc_size: SEGMENT_HEADER_SIZE,
+        })
+    }
+
+    /// Appends the pixel contribution maps of a new asset to the archive.
+    ///
+    /// # Arguments
+    /// * `id` - The unique id of the asset.
+    /// * `name` - The unique name of the asset.
+    /// * `maps` - The pixel contribution maps of the asset.
+    /// * `encoding` - The encoding and compression used for storing the values.
+    pub fn append(
+        &mut self,
+        id: u64,
+        name: &str,
+        maps: &PixelContributionMaps,
+        encoding: MapEncoding,
+    ) -> Result<()> {
+        if self.find_by_id(id).is_some() {
+            return Err(Error::InvalidArgument(format!(
+                "Asset with id {} already exists",
+                id
+            )));
+        }
+
+        if self.find_by_name(name).is_some() {
+            return Err(Error::InvalidArgument(format!(
+                "Asset with name '{}' already exists",
+                name
+            )));
+        }
+
+        // Write the maps behind the current table of contents, s.t. the archive stays valid
+        // until the header is patched.
+        let mut data = Vec::new();
+        maps.write_writer_with_encoding(&mut data, encoding)?;
+
+        self.stream.seek(SeekFrom::Start(8))?;
+        let previous_offset = self.stream.read_u64::<LittleEndian>()?;
+
+        let offset = self.stream.seek(SeekFrom::End(0))?;
+        self.stream.write_all(&data)?;
+        let toc_offset = offset + data.len() as u64;
+
+        let entry = ArchiveEntry {
+            id,
+            name: name.to_string(),
+            offset,
+            length: data.len() as u64,
+        };
+
+        // Write the segment with the new entry and patch the header to point to it
+        Self::write_toc_segment(
+            &mut self.stream,
+            previous_offset,
+            std::slice::from_ref(&entry),
+        )?;
+        self.stream.flush()?;
+
+        self.stream.seek(SeekFrom::Start(8))?;
+        self.stream.write_u64::<LittleEndian>(toc_offset)?;
+        self.stream.flush()?;
+
+        self.toc_size += segment_size(std::slice::from_ref(&entry));
+        self.entries.push(entry);
+
+        Ok(())
+    }
+
+    /// Writes a segment of the table of contents with the given entries at the current position.
+    ///
+    /// # Arguments
+    /// * `stream` - The stream into which the segment is written.
+    /// * `previous_offset` - The offset of the previous segment or 0 for the first segment.
+    /// * `entries` - The entries of the segment.
+    fn write_toc_segment(
+        stream: &mut S,
+        previous_offset: u64,
+        entries: &[ArchiveEntry],
+    ) -> Result<()> {
+        stream.write_u64::<LittleEndian>(previous_offset)?;
+        stream.write_u32::<LittleEndian>(entries.len() as u32)?;
+
+        for entry in entries.iter() {
+            stream.write_u64::<LittleEndian>(entry.id)?;
+            stream.write_u32::<LittleEndian>(entry.name.len() as u32)?;
+            stream.write_all(entry.name.as_bytes())?;
+            stream.write_u64::<LittleEndian>(entry.offset)?;
+            stream.write_u64::<LittleEndian>(entry.length)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl PixelContributionArchive<File> {
+    /// Opens the archive at the given path for reading.
+    ///
+    /// # Arguments
+    /// * `path` - The path of the archive.
+    pub fn open_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::open(File::open(path)?)
+    }
+
+    /// Opens the archive at the given path for appending new assets. If the file does not exist,
+    /// a new empty archive is created.
+    ///
+    /// # Arguments
+    /// * `path` - The path of the archive.
+    pub fn append_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+
+        if file.metadata()?.len() == 0 {
+            Self::create(file)
+        } else {
+            Self::open(file)
+        }
+    }
+
+    /// Rewrites the archive at the given path with a single segment of the table of contents and
+    /// without unused data. The archive is first compacted into a temporary file next to it,
+    /// which then replaces the archive, s.t. the archive stays valid if the compaction is
+    /// interrupted.
+    ///
+    /// # Arguments
+    /// * `path` - The path of the archive.
+    pub fn compact_file<P: AsRef<Path>>(path: P) -> Result<()> {
+        let path = path.as_ref();
+
+        let mut tmp_path = path.as_os_str().to_owned();
+        tmp_path.push(".compact");
+
+        let tmp_file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&tmp_path)?;
+
+        // both files are closed before the temporary file replaces the archive
+        {
+            let mut archive = Self::open_file(path)?;
+            let compacted = archive.compact_into(tmp_file)?;
+            compacted.stream.sync_all()?;
+        }
+
+        std::fs::rename(&tmp_path, path)?;
+
+        Ok(())
+    }
+}
+
+/// Returns the size in bytes of the segment of the table of contents with the given entries.
+///
+/// # Arguments
+/// * `entries` - The entries of the segment.
+fn segment_size(entries: &[ArchiveEntry]) -> u64 {
+    SEGMENT_HEADER_SIZE
+        + entries
+            .iter()
+            .map(|e| MIN_ENTRY_SIZE + e.name.len() as u64)
+            .sum::<u64>()
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use crate::{
+        PixelContribColorMapDescriptor, PixelContributionMap, PixelContributionMapsView,
+        ValueEncoding,
+    };
+
+    use super::*;
+
+    fn create_maps(value: f32) -> PixelContributionMaps {
+        let descriptor = PixelContribColorMapDescriptor::new(8, value);
+        let mut map = PixelContributionMap::new(descriptor);
+        map.pixel_contrib.iter_mut().for_each(|p| *p = value);
+
+        PixelContributionMaps::from_maps(vec![map])
+    }
+
+    #[test]
+    fn test_archive_append_and_lookup() {
+        let mut archive = PixelContributionArchive::create(Cursor::new(Vec::new())).unwrap();
+        assert!(archive.entries().is_empty());
+
+        let encoding = MapEncoding::default();
+        archive
+            .append(1, "duck", &create_maps(0.25), encoding)
+            .unwrap();
+        archive
+            .append(7, "boxes", &create_maps(0.5), encoding)
+            .unwrap();
+
+        // ids and names must be unique
+        assert!(archive
+            .append(1, "other", &create_maps(0.1), encoding)
+            .is_err());
+        assert!(archive
+            .append(2, "duck", &create_maps(0.1), encoding)
+            .is_err());
+
+        // reopen the archive and append a further asset without rewriting the existing ones
+        let data = archive.into_inner().into_inner();
+        let mut archive = PixelContributionArchive::open(Cursor::new(data.clone())).unwrap();
+        assert_eq!(archive.entries().len(), 2);
+
+        let f16 = MapEncoding::new(ValueEncoding::F16, Default::default());
+        archive.append(3, "plane", &create_maps(0.75), f16).unwrap();
+
+        let data2 = archive.into_inner().into_inner();
+        assert_eq!(data[16..], data2[16..data.len()]);
+
+        let mut archive = PixelContributionArchive::open(Cursor::new(data2.clone())).unwrap();
+        assert_eq!(archive.entries().len(), 3);
+        assert_eq!(archive.find_by_name("boxes").unwrap().id, 7);
+        assert!(archive.find_by_id(4).is_none());
+
+        assert!(archive.load_by_id(1).unwrap() == create_maps(0.25));
+        assert!(archive.load_by_name("boxes").unwrap() == create_maps(0.5));
+        assert!(archive.load_by_name("plane").unwrap() == create_maps(0.75));
+        assert!(archive.load_by_name("unknown").is_err());
+
+        // the entries can be viewed without copying
+        let entry = archive.find_by_id(7).unwrap();
+        let view = PixelContributionMapsView::from_bytes(&data2[entry.byte_range()]).unwrap();
+        assert!(view.to_maps() == create_maps(0.5));
+    }
+
+    #[test]
+    fn test_archive_compaction() {
+        let mut archive = PixelContributionArchive::create(Cursor::new(Vec::new())).unwrap();
+        assert_eq!(archive.unused_bytes().unwrap(), 0);
+
+        let encoding = MapEncoding::default();
+        for (id, name) in ["duck", "boxes", "plane"].iter().enumerate() {
+            archive
+                .append(id as u64, name, &create_maps(id as f32 * 0.25), encoding)
+                .unwrap();
+        }
+
+        // each append only adds a segment with its own entry
+        assert_eq!(archive.unused_bytes().unwrap(), 0);
+
+        // an interrupted append leaves its data behind
+        let size = archive.stream.get_ref().len() as u64;
+        archive.stream.get_mut().extend_from_slice(&[0u8; 100]);
+        assert_eq!(archive.unused_bytes().unwrap(), 100);
+
+        // the compacted archive merges the segments into one
+        let mut compacted = archive.compact_into(Cursor::new(Vec::new())).unwrap();
+        assert_eq!(compacted.unused_bytes().unwrap(), 0);
+        assert_eq!(
+            compacted.stream.get_ref().len() as u64,
+            size - 3 * SEGMENT_HEADER_SIZE
+        );
+
+        // the compacted archive can be reopened and extended
+        let data = compacted.into_inner().into_inner();
+        let mut archive = PixelContributionArchive::open(Cursor::new(data)).unwrap();
+        assert_eq!(archive.entries().len(), 3);
+        for (id, name) in ["duck", "boxes", "plane"].iter().enumerate() {
+            assert_eq!(archive.find_by_id(id as u64).unwrap().name, *name);
+            assert!(archive.load_by_name(name).unwrap() == create_maps(id as f32 * 0.25));
+        }
+
+        archive
+            .append(3, "cone", &create_maps(1.0), encoding)
+            .unwrap();
+        assert!(archive.load_by_id(3).unwrap() == create_maps(1.0));
+    }
+
+    #[test]
+    fn test_archive_compact_file() {
+        let path =
+            std::env::temp_dir().join(format!("pixel_contrib_archive_{}.pcma", std::process::id()));
+
+        let encoding = MapEncoding::default();
+        {
+            let mut archive = PixelContributionArchive::append_file(&path).unwrap();
+            archive
+                .append(1, "duck", &create_maps(0.25), encoding)
+                .unwrap();
+            archive
+                .append(2, "boxes", &create_maps(0.5), encoding)
+                .unwrap();
+            archive.stream.seek(SeekFrom::End(0)).unwrap();
+            archive.stream.write_all(&[0u8; 10]).unwrap();
+            assert_eq!(archive.unused_bytes().unwrap(), 10);
+        }
+
+        PixelContributionArchive::compact_file(&path).unwrap();
+
+        let mut archive = PixelContributionArchive::open_file(&path).unwrap();
+        assert_eq!(archive.unused_bytes().unwrap(), 0);
+        assert!(archive.load_by_id(1).unwrap() == create_maps(0.25));
+        assert!(archive.load_by_name("boxes").unwrap() == create_maps(0.5));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_archive_invalid_data() {
+        let data = vec![0u8; 32];
+        assert!(PixelContributionArchive::open(Cursor::new(data)).is_err());
+
+        let mut archive = PixelContributionArchive::create(Cursor::new(Vec::new())).unwrap();
+        archive
+            .append(1, "duck", &create_maps(0.25), MapEncoding::default())
+            .unwrap();
+        let data = archive.into_inner().into_inner();
+        let toc_offset = u64::from_le_bytes(data[8..16].try_into().unwrap()) as usize;
+
+        // a segment pointing to itself or behind itself is rejected
+        let mut cyclic = data.clone();
+        cyclic[toc_offset..toc_offset + 8].copy_from_slice(&(toc_offset as u64).to_le_bytes());
+        assert!(PixelContributionArchive::open(Cursor::new(cyclic)).is_err());
+
+        // a huge number of entries is rejected before allocating them
+        let mut huge = data.clone();
+        huge[toc_offset + 8..toc_offset + 12].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(PixelContributionArchive::open(Cursor::new(huge)).is_err());
+
+        // the unmodified archive can be opened
+        let archive = PixelContributionArchive::open(Cursor::new(data)).unwrap();
+        assert_eq!(archive.entries().len(), 1);
+    }
+}
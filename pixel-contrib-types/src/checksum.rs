@@ -0,0 +1,73 @@
+use std::io::{Read, Result, Write};
+
+use crc32fast::Hasher;
+
+/// A reader that computes the CRC32 checksum of all bytes read through it.
+pub struct ChecksumReader<'a, R: Read> {
+    reader: &'a mut R,
+    hasher: Hasher,
+}
+
+impl<'a, R: Read> ChecksumReader<'a, R> {
+    /// Creates a new checksum reader that reads from the given reader.
+    ///
+    /// # Arguments
+    /// * `reader` - The underlying reader.
+    pub fn new(reader: &'a mut R) -> Self {
+        Self {
+            reader,
+            hasher: Hasher::new(),
+        }
+    }
+
+    /// Returns the CRC32 checksum of all bytes read so far.
+    pub fn checksum(&self) -> u32 {
+        self.hasher.clone().finalize()
+    }
+}
+
+impl<'a, R: Read> Read for ChecksumReader<'a, R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let n = self.reader.read(buf)?;
+        self.hasher.update(&buf[..n]);
+
+        Ok(n)
+    }
+}
+
+/// A writer that computes the CRC32 checksum of all bytes written through it.
+pub struct ChecksumWriter<'a, W: Write> {
+    writer: &'a mut W,
+    hasher: Hasher,
+}
+
+impl<'a, W: Write> ChecksumWriter<'a, W> {
+    /// Creates a new checksum writer that writes into the given writer.
+    ///
+    /// # Arguments
+    /// * `writer` - The underlying writer.
+    pub fn new(writer: &'a mut W) -> Self {
+        Self {
+            writer,
+            hasher: Hasher::new(),
+        }
+    }
+
+    /// Returns the CRC32 checksum of all bytes written so far.
+    pub fn checksum(&self) -> u32 {
+        self.hasher.clone().finalize()
+    }
+}
+
+impl<'a, W: Write> Write for ChecksumWriter<'a, W> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        let n = self.writer.write(buf)?;
+        self.hasher.update(&buf[..n]);
+
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.writer.flush()
+    }
+}
@@ -0,0 +1,450 @@
+use std::io::{Read, Write};
+
+use half::f16;
+
+use crate::{Error, Result};
+
+/// The encoding of the pixel contribution values when being stored in a file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum ValueEncoding {
+    /// The values are stored as 32-bit floats without any loss of precision.
+    #[default]
+    F32,
+
+    /// The values are stored as 16-bit half floats.
+    F16,
+
+    /// The values are quantized to 8 bits w.r.t the min/max range of the map.
+    Quantized8,
+
+    /// The values are quantized to 12 bits w.r.t the min/max range of the map.
+    /// Two values are packed into three bytes.
+    Quantized12,
+
+    /// The values are quantized to 16 bits w.r.t the min/max range of the map.
+    Quantized16,
+}
+
+impl ValueEncoding {
+    /// Returns the number of bits used for quantizing a value or None, if the encoding is not
+    /// quantized.
+    #[inline]
+    pub fn quantization_bits(&self) -> Option<u32> {
+        match self {
+            ValueEncoding::F32 | ValueEncoding::F16 => None,
+            ValueEncoding::Quantized8 => Some(8),
+            ValueEncoding::Quantized12 => Some(12),
+            ValueEncoding::Quantized16 => Some(16),
+        }
+    }
+
+    /// Returns the maximal absolute error when encoding and decoding values in the range
+    /// `[min, max]`. The bound is:
+    /// * `F32` - exact, i.e., 0.
+    /// * `F16` - half a unit in the last place of the largest magnitude `m = max(|min|, |max|)`,
+    ///   i.e., `2^-11 * 2^floor(log2(m))`, but at least `2^-25` due to subnormals. If `m` is a
+    ///   power of two, it is encoded exactly and the bound of the binade below applies, i.e.,
+    ///   `2^-12 * m`. For values in [0,1] this is `2^-12`.
+    /// * `QuantizedN` - half a quantization step, i.e., `(max - min) / (2 * (2^N - 1))`.
+    ///
+    /// # Arguments
+    /// * `min` - The minimal value to encode.
+    /// * `max` - The maximal value to encode.
+    pub fn error_bound(&self, min: f32, max: f32) -> f32 {
+        match self.quantization_bits() {
+            Some(bits) => (max - min) / (2f32 * ((1u32 << bits) - 1) as f32),
+            None => match self {
+                ValueEncoding::F16 => {
+                    // take the exponent from the bits, as log2 may round up to the next
+                    // integer just above a power of two
+                    let bits = min.abs().max(max.abs()).to_bits();
+                    let exponent = ((bits >> 23) & 0xff) as i32 - 127;
+                    let exponent = if bits & 0x7f_ffff == 0 {
+                        exponent - 1
+                    } else {
+                        exponent
+                    };
+
+                    // subnormal half floats have a fixed spacing of 2^-24
+                    2f32.powi(exponent - 11).max(2f32.powi(-25))
+                }
+                _ => 0f32,
+            },
+        }
+    }
+
+    /// Returns the identifier used for serializing the encoding.
+    pub(crate) fn to_id(self) -> u8 {
+        match self {
+            ValueEncoding::F32 => 0,
+            ValueEncoding::F16 => 1,
+            ValueEncoding::Quantized8 => 2,
+            ValueEncoding::Quantized12 => 3,
+            ValueEncoding::Quantized16 => 4,
+        }
+    }
+
+    /// Returns the encoding for the given serialized identifier.
+    ///
+    /// # Arguments
+    /// * `id` - The serialized identifier of the encoding.
+    pub(crate) fn from_id(id: u8) -> Result<Self> {
+        match id {
+            0 => Ok(ValueEncoding::F32),
+            1 => Ok(ValueEncoding::F16),
+            2 => Ok(ValueEncoding::Quantized8),
+            3 => Ok(ValueEncoding::Quantized12),
+            4 => Ok(ValueEncoding::Quantized16),
+            _ => Err(Error::IO(format!("Unknown value encoding {}", id))),
+        }
+    }
+
+    /// Encodes the given values into bytes. The range `[min, max]` is only used for quantized
+    /// encodings.
+    ///
+    /// # Arguments
+    /// * `values` - The values to encode.
+    /// * `min` - The minimal value.
+    /// * `max` - The maximal value.
+    pub(crate) fn encode(&self, values: &[f32], min: f32, max: f32) -> Vec<u8> {
+        match self {
+            ValueEncoding::F32 => values.iter().flat_map(|v| v.to_le_bytes()).collect(),
+            ValueEncoding::F16 => values
+                .iter()
+                .flat_map(|v| f16::from_f32(*v).to_le_bytes())
+                .collect(),
+            ValueEncoding::Quantized8 => values
+                .iter()
+                .map(|v| quantize(*v, min, max, 8) as u8)
+                .collect(),
+            ValueEncoding::Quantized12 => {
+                let mut result = Vec::with_capacity((values.len() * 3).div_ceil(2));
+                for pair in values.chunks(2) {
+                    let q0 = quantize(pair[0], min, max, 12);
+                    let q1 = pair.get(1).map(|v| quantize(*v, min, max, 12)).unwrap_or(0);
+
+                    result.push(q0 as u8);
+                    result.push(((q0 >> 8) | (q1 << 4)) as u8);
+                    if pair.len() == 2 {
+                        result.push((q1 >> 4) as u8);
+                    }
+                }
+
+                result
+            }
+            ValueEncoding::Quantized16 => values
+                .iter()
+                .flat_map(|v| (quantize(*v, min, max, 16) as u16).to_le_bytes())
+                .collect(),
+        }
+    }
+
+    /// Decodes the given bytes into the given number of values.
+    ///
+    /// # Arguments
+    /// * `data` - The encoded bytes.
+    /// * `num_values` - The number of values to decode.
+    /// * `min` - The minimal value used for the quantization.
+    /// * `max` - The maximal value used for the quantization.
+    pub(crate) fn decode(
+        &self,
+        data: &[u8],
+        num_values: usize,
+        min: f32,
+        max: f32,
+    ) -> Result<Vec<f32>> {
+        if data.len() != self.encoded_size(num_values) {
+            return Err(Error::IO(format!(
+                "Expected {} bytes of encoded values, but got {}",
+                self.encoded_size(num_values),
+                data.len()
+            )));
+        }
+
+        let values = match self {
+            ValueEncoding::F32 => data
+                .chunks_exact(4)
+                .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                .collect(),
+            ValueEncoding::F16 => data
+                .chunks_exact(2)
+                .map(|b| f16::from_le_bytes([b[0], b[1]]).to_f32())
+                .collect(),
+            ValueEncoding::Quantized8 => data
+                .iter()
+                .map(|q| dequantize(*q as u32, min, max, 8))
+                .collect(),
+            ValueEncoding::Quantized12 => {
+                let mut result = Vec::with_capacity(num_values);
+                for b in data.chunks(3) {
+                    let q0 = b[0] as u32 | ((b[1] as u32 & 0x0F) << 8);
+                    result.push(dequantize(q0, min, max, 12));
+
+                    if b.len() == 3 {
+                        let q1 = (b[1] as u32 >> 4) | ((b[2] as u32) << 4);
+                        result.push(dequantize(q1, min, max, 12));
+                    }
+                }
+
+                result
+            }
+            ValueEncoding::Quantized16 => data
+                .chunks_exact(2)
+                .map(|b| dequantize(u16::from_le_bytes([b[0], b[1]]) as u32, min, max, 16))
+                .collect(),
+        };
+
+        Ok(values)
+    }
+
+    /// Returns the number of bytes needed for encoding the given number of values.
+    ///
+    /// # Arguments
+    /// * `num_values` - The number of values to encode.
+    pub(crate) fn encoded_size(&self, num_values: usize) -> usize {
+        match self {
+            ValueEncoding::F32 => num_values * 4,
+            ValueEncoding::F16 | ValueEncoding::Quantized16 => num_values * 2,
+            ValueEncoding::Quantized8 => num_values,
+            ValueEncoding::Quantized12 => (num_values * 3).div_ceil(2),
+        }
+    }
+}
+
+/// The compression applied to the encoded pixel contribution values.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Compression {
+    /// The encoded values are stored as they are.
+    #[default]
+    None,
+
+    /// The encoded values are compressed using deflate.
+    Deflate,
+
+    /// The encoded values are compressed using zstd. Requires the `zstd` feature.
+    Zstd,
+}
+
+impl Compression {
+    /// Returns the identifier used for serializing the compression.
+    pub(crate) fn to_id(self) -> u8 {
+        match self {
+            Compression::None => 0,
+            Compression::Deflate => 1,
+            Compression::Zstd => 2,
+        }
+    }
+
+    /// Returns the compression for the given serialized identifier.
+    ///
+    /// # Arguments
+    /// * `id` - The serialized identifier of the compression.
+    pub(crate) fn from_id(id: u8) -> Result<Self> {
+        match id {
+            0 => Ok(Compression::None),
+            1 => Ok(Compression::Deflate),
+            2 => Ok(Compression::Zstd),
+            _ => Err(Error::IO(format!("Unknown compression {}", id))),
+        }
+    }
+
+    /// Compresses the given data.
+    ///
+    /// # Arguments
+    /// * `data` - The data to compress.
+    pub(crate) fn compress(&self, data: Vec<u8>) -> Result<Vec<u8>> {
+        match self {
+            Compression::None => Ok(data),
+            Compression::Deflate => {
+                let mut encoder =
+                    flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(&data)?;
+
+                Ok(encoder.finish()?)
+            }
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => Ok(zstd::encode_all(data.as_slice(), 0)?),
+            #[cfg(not(feature = "zstd"))]
+            Compression::Zstd => Err(Error::InvalidArgument(
+                "zstd compression requires the 'zstd' feature".to_string(),
+            )),
+        }
+    }
+
+    /// Decompresses the given data.
+    ///
+    /// # Arguments
+    /// * `data` - The compressed data.
+    /// * `max_size` - The maximal number of bytes to decompress.
+    pub(crate) fn decompress(&self, data: Vec<u8>, max_size: usize) -> Result<Vec<u8>> {
+        let mut result = Vec::new();
+
+        match self {
+            Compression::None => return Ok(data),
+            Compression::Deflate => {
+                flate2::read::DeflateDecoder::new(data.as_slice())
+                    .take(max_size as u64)
+                    .read_to_end(&mut result)?;
+            }
+            #[cfg(feature = "zstd")]
+            Compression::Zstd => {
+                zstd::stream::read::Decoder::new(data.as_slice())?
+                    .take(max_size as u64)
+                    .read_to_end(&mut result)?;
+            }
+            #[cfg(not(feature = "zstd"))]
+            Compression::Zstd => {
+                return Err(Error::InvalidArgument(
+                    "zstd compression requires the 'zstd' feature".to_string(),
+                ))
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+/// The encoding and compression used for storing the pixel contribution maps.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct MapEncoding {
+    /// The encoding of the individual values.
+    pub values: ValueEncoding,
+
+    /// The compression applied to the encoded values.
+    pub compression: Compression,
+}
+
+impl MapEncoding {
+    /// Creates a new map encoding.
+    ///
+    /// # Arguments
+    /// * `values` - The encoding of the individual values.
+    /// * `compression` - The compression applied to the encoded values.
+    pub fn new(values: ValueEncoding, compression: Compression) -> Self {
+        Self {
+            values,
+            compression,
+        }
+    }
+}
+
+/// Quantizes the given value w.r.t the given range and number of bits.
+///
+/// # Arguments
+/// * `v` - The value to quantize.
+/// * `min` - The minimal value of the range.
+/// * `max` - The maximal value of the range.
+/// * `bits` - The number of bits of the quantized value.
+#[inline]
+fn quantize(v: f32, min: f32, max: f32, bits: u32) -> u32 {
+    let levels = ((1u32 << bits) - 1) as f32;
+    if max <= min {
+        return 0;
+    }
+
+    let t = math::clamp((v - min) / (max - min), 0f32, 1f32);
+    (t * levels).round() as u32
+}
+
+/// Maps the given quantized value back into the given range.
+///
+/// # Arguments
+/// * `q` - The quantized value.
+/// * `min` - The minimal value of the range.
+/// * `max` - The maximal value of the range.
+/// * `bits` - The number of bits of the quantized value.
+#[inline]
+fn dequantize(q: u32, min: f32, max: f32, bits: u32) -> f32 {
+    let levels = ((1u32 << bits) - 1) as f32;
+
+    min + (q as f32 / levels) * (max - min)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn create_values(n: usize) -> Vec<f32> {
+        (0..n).map(|i| ((i * 7919) % 1000) as f32 / 999.0).collect()
+    }
+
+    #[test]
+    fn test_value_encoding_error_bound() {
+        let encodings = [
+            ValueEncoding::F32,
+            ValueEncoding::F16,
+            ValueEncoding::Quantized8,
+            ValueEncoding::Quantized12,
+            ValueEncoding::Quantized16,
+        ];
+
+        // use an odd number of values to test the 12-bit packing
+        for n in [0, 1, 2, 1001] {
+            let values = create_values(n);
+            let min = values.iter().fold(f32::MAX, |a, b| a.min(*b));
+            let max = values.iter().fold(f32::MIN, |a, b| a.max(*b));
+
+            for encoding in encodings {
+                let data = encoding.encode(&values, min, max);
+                assert_eq!(data.len(), encoding.encoded_size(n));
+
+                let decoded = encoding.decode(&data, n, min, max).unwrap();
+                assert_eq!(decoded.len(), n);
+
+                let bound = encoding.error_bound(min, max) + 1e-6;
+                for (v, d) in values.iter().zip(decoded.iter()) {
+                    assert!((v - d).abs() <= bound, "{:?}: {} vs {}", encoding, v, d);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_f16_error_bound_at_one() {
+        assert_eq!(ValueEncoding::F16.error_bound(0f32, 1f32), 2f32.powi(-12));
+        assert_eq!(ValueEncoding::F16.error_bound(0f32, 0.99), 2f32.powi(-12));
+        assert_eq!(ValueEncoding::F16.error_bound(0f32, 1.01), 2f32.powi(-11));
+        assert_eq!(ValueEncoding::F16.error_bound(-1f32, 0f32), 2f32.powi(-12));
+        assert_eq!(ValueEncoding::F16.error_bound(0f32, 0f32), 2f32.powi(-25));
+
+        // values just below 1 have the largest error in [0,1]
+        let values: Vec<f32> = (0..=4096).map(|i| 0.5 + i as f32 / 8192.0).collect();
+        let bound = ValueEncoding::F16.error_bound(0f32, 1f32);
+        let data = ValueEncoding::F16.encode(&values, 0f32, 1f32);
+        let decoded = ValueEncoding::F16
+            .decode(&data, values.len(), 0f32, 1f32)
+            .unwrap();
+        for (v, d) in values.iter().zip(decoded.iter()) {
+            assert!((v - d).abs() <= bound, "{} vs {}", v, d);
+        }
+        assert!(values
+            .iter()
+            .zip(decoded.iter())
+            .any(|(v, d)| (v - d).abs() == bound));
+    }
+
+    #[test]
+    fn test_deflate_compression() {
+        let data = ValueEncoding::F32.encode(&create_values(1000), 0f32, 1f32);
+
+        let compressed = Compression::Deflate.compress(data.clone()).unwrap();
+        let decompressed = Compression::Deflate
+            .decompress(compressed, data.len())
+            .unwrap();
+
+        assert_eq!(data, decompressed);
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_zstd_compression() {
+        let data = ValueEncoding::F16.encode(&create_values(1000), 0f32, 1f32);
+
+        let compressed = Compression::Zstd.compress(data.clone()).unwrap();
+        let decompressed = Compression::Zstd
+            .decompress(compressed, data.len())
+            .unwrap();
+
+        assert_eq!(data, decompressed);
+    }
+}
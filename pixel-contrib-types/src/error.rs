@@ -13,6 +13,39 @@ quick_error! {
         InvalidArgument(err: String) {
             display("{}", err)
         }
+        InvalidIdentifier {
+            display("Invalid identifier")
+        }
+        UnsupportedVersion(version: u32) {
+            display("Unsupported version {}", version)
+        }
+        ChecksumMismatch(map: usize) {
+            display("Checksum mismatch for map {}", map)
+        }
+        InvalidMapSize(size: usize, max_size: usize) {
+            display("Invalid map size {}, expected a size in the range [1, {}]", size, max_size)
+        }
+        TooManyMaps(num_maps: usize, max_num_maps: usize) {
+            display("The number of maps {} exceeds the maximum of {}", num_maps, max_num_maps)
+        }
+        InvalidMipLevels(num_levels: usize, size: usize) {
+            display("Invalid number of mip levels {} for map size {}", num_levels, size)
+        }
+        InvalidCameraAngle(angle: f32) {
+            display("Invalid camera angle {}, expected a finite non-negative angle", angle)
+        }
+        InvalidFrustum {
+            display("Invalid camera frustum")
+        }
+        InvalidCameraDistance(distance: f32) {
+            display("Invalid camera distance {}, expected 0 or a finite distance larger than 1", distance)
+        }
+        DuplicateCameraAngle(angle: f32) {
+            display("Multiple maps with the camera angle {}", angle)
+        }
+        ValueOutOfRange(map: usize, index: usize, value: f32) {
+            display("Value {} at index {} of map {} is outside of the range [0, 1]", value, index, map)
+        }
     }
 }
 
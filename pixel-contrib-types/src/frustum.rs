@@ -0,0 +1,405 @@
+use std::{
+    f32::consts::FRAC_PI_2,
+    io::{Read, Write},
+};
+
+use byteorder::{ReadBytesExt, WriteBytesExt};
+use nalgebra_glm::{Mat4, Vec2, Vec3};
+
+use crate::{Error, Result};
+
+/// The maximal difference of two frustum shapes, s.t. they are considered to be equal.
+const FRUSTUM_SHAPE_EPSILON: f32 = 1e-3;
+
+/// The frustum of the camera used for computing a pixel contribution map.
+///
+/// The frustum is defined by the aspect ratio of the frame and the half-angles between the view
+/// axis and the left, right, bottom and top planes. The half-angles are in radians and positive
+/// if the plane lies on the respective side of the view axis, i.e., a symmetric frustum has equal
+/// half-angles and an off-axis frustum, e.g., of a tiled display or a VR headset, has different
+/// ones. All half-angles are 0 for orthographic cameras.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CameraFrustum {
+    /// The aspect ratio of the frame, i.e., width / height.
+    aspect_ratio: f32,
+
+    /// The half-angle between the view axis and the left plane.
+    left: f32,
+
+    /// The half-angle between the view axis and the right plane.
+    right: f32,
+
+    /// The half-angle between the view axis and the bottom plane.
+    bottom: f32,
+
+    /// The half-angle between the view axis and the top plane.
+    top: f32,
+}
+
+impl Default for CameraFrustum {
+    fn default() -> Self {
+        Self::orthographic(1.0)
+    }
+}
+
+impl CameraFrustum {
+    /// Creates the frustum of an orthographic camera.
+    ///
+    /// # Arguments
+    /// * `aspect_ratio` - The aspect ratio of the frame, i.e., width / height.
+    pub fn orthographic(aspect_ratio: f32) -> Self {
+        Self {
+            aspect_ratio,
+            left: 0.0,
+            right: 0.0,
+            bottom: 0.0,
+            top: 0.0,
+        }
+    }
+
+    /// Creates the symmetric frustum of a perspective camera.
+    ///
+    /// # Arguments
+    /// * `fovy` - The field of view in y-direction in radians.
+    /// * `aspect_ratio` - The aspect ratio of the frame, i.e., width / height.
+    pub fn perspective(fovy: f32, aspect_ratio: f32) -> Self {
+        let half_fovx = (aspect_ratio * (fovy / 2.0).tan()).atan();
+
+        Self {
+            aspect_ratio,
+            left: half_fovx,
+            right: half_fovx,
+            bottom: fovy / 2.0,
+            top: fovy / 2.0,
+        }
+    }
+
+    /// Creates the symmetric frustum of a camera with a quadratic frame, i.e., the frustum for
+    /// which the maps of a camera angle have been computed before frusta were supported.
+    ///
+    /// # Arguments
+    /// * `camera_angle` - The field of view in radians. A value of 0 results in an orthographic
+    ///   camera.
+    pub fn square(camera_angle: f32) -> Self {
+        if camera_angle > 0.0 {
+            Self::perspective(camera_angle, 1.0)
+        } else {
+            Self::orthographic(1.0)
+        }
+    }
+
+    /// Creates the possibly asymmetric frustum of a perspective camera from its half-angles. The
+    /// aspect ratio is derived from the half-angles.
+    ///
+    /// # Arguments
+    /// * `left` - The half-angle between the view axis and the left plane in radians.
+    /// * `right` - The half-angle between the view axis and the right plane in radians.
+    /// * `bottom` - The half-angle between the view axis and the bottom plane in radians.
+    /// * `top` - The half-angle between the view axis and the top plane in radians.
+    pub fn off_axis(left: f32, right: f32, bottom: f32, top: f32) -> Self {
+        let width = left.tan() + right.tan();
+        let height = bottom.tan() + top.tan();
+
+        Self {
+            aspect_ratio: width / height,
+            left,
+            right,
+            bottom,
+            top,
+        }
+    }
+
+    /// Extracts the frustum from the given OpenGL-style projection matrix.
+    /// Returns None, if the matrix is neither a perspective nor an orthographic projection.
+    ///
+    /// # Arguments
+    /// * `projection` - The projection matrix.
+    pub fn from_projection_matrix(projection: &Mat4) -> Option<Self> {
+        let (m00, m11) = (projection[(0, 0)], projection[(1, 1)]);
+        if m00 == 0.0 || m11 == 0.0 {
+            return None;
+        }
+
+        if projection[(3, 2)] == -1.0 && projection[(3, 3)] == 0.0 {
+            // the tangents of the half-angles, see the definition of glFrustum
+            let (m02, m12) = (projection[(0, 2)], projection[(1, 2)]);
+            Some(Self::off_axis(
+                ((1.0 - m02) / m00).atan(),
+                ((1.0 + m02) / m00).atan(),
+                ((1.0 - m12) / m11).atan(),
+                ((1.0 + m12) / m11).atan(),
+            ))
+        } else if projection[(3, 2)] == 0.0 && projection[(3, 3)] == 1.0 {
+            Some(Self::orthographic(m11 / m00))
+        } else {
+            None
+        }
+    }
+
+    /// Returns the aspect ratio of the frame, i.e., width / height.
+    #[inline]
+    pub fn aspect_ratio(&self) -> f32 {
+        self.aspect_ratio
+    }
+
+    /// Returns the half-angles between the view axis and the left and right plane in radians.
+    #[inline]
+    pub fn horizontal_half_angles(&self) -> (f32, f32) {
+        (self.left, self.right)
+    }
+
+    /// Returns the half-angles between the view axis and the bottom and top plane in radians.
+    #[inline]
+    pub fn vertical_half_angles(&self) -> (f32, f32) {
+        (self.bottom, self.top)
+    }
+
+    /// Returns true if the frustum belongs to an orthographic camera.
+    #[inline]
+    pub fn is_orthographic(&self) -> bool {
+        self.left == 0.0 && self.right == 0.0 && self.bottom == 0.0 && self.top == 0.0
+    }
+
+    /// Returns true if the frustum is symmetric w.r.t. its view axis.
+    #[inline]
+    pub fn is_symmetric(&self) -> bool {
+        self.left == self.right && self.bottom == self.top
+    }
+
+    /// Returns true if the frustum is well-defined, i.e., the aspect ratio is positive and the
+    /// frustum has a positive extent in both directions, which is less than 180°.
+    pub fn is_valid(&self) -> bool {
+        let angles = [self.left, self.right, self.bottom, self.top];
+        if !(self.aspect_ratio.is_finite() && self.aspect_ratio > 0.0)
+            || angles
+                .iter()
+                .any(|a| !a.is_finite() || a.abs() >= FRAC_PI_2)
+        {
+            return false;
+        }
+
+        self.is_orthographic() || (self.left + self.right > 0.0 && self.bottom + self.top > 0.0)
+    }
+
+    /// Returns the offset of the center of the frame from the view axis relative to the extent
+    /// of the frame, i.e., (0, 0) for symmetric frusta and (-1, -1) to (1, 1) for off-axis
+    /// frusta whose view axis lies on the border of the frame.
+    pub fn offset(&self) -> Vec2 {
+        if self.is_orthographic() {
+            return Vec2::zeros();
+        }
+
+        let (l, r, b, t) = self.tangents();
+        Vec2::new((r - l) / (r + l), (t - b) / (t + b))
+    }
+
+    /// Returns the difference between the shapes of both frusta, i.e., between their aspect
+    /// ratios and offsets, regardless of their fields of view.
+    ///
+    /// # Arguments
+    /// * `other` - The frustum to compare with.
+    pub fn shape_distance(&self, other: &CameraFrustum) -> f32 {
+        (self.aspect_ratio / other.aspect_ratio).ln().abs()
+            + (self.offset() - other.offset()).abs().sum()
+    }
+
+    /// Returns true if both frusta have the same shape, see [`Self::shape_distance`].
+    ///
+    /// # Arguments
+    /// * `other` - The frustum to compare with.
+    #[inline]
+    pub fn has_same_shape(&self, other: &CameraFrustum) -> bool {
+        self.shape_distance(other) <= FRUSTUM_SHAPE_EPSILON
+    }
+
+    /// Returns the normalized direction in view space, i.e., looking along -z, pointing to the
+    /// center of the frame. The bounding sphere is centered on this direction when computing a
+    /// map for the frustum.
+    pub fn sphere_direction(&self) -> Vec3 {
+        let (l, r, b, t) = self.tangents();
+        Vec3::new((r - l) / 2.0, (t - b) / 2.0, -1.0).normalize()
+    }
+
+    /// Returns the angle of the largest cone around [`Self::sphere_direction`] that fits into the
+    /// frustum, i.e., the angle under which a bounding sphere fitted into the frustum is seen.
+    /// Returns 0 for orthographic frusta.
+    pub fn sphere_angle(&self) -> f32 {
+        if self.is_orthographic() {
+            return 0.0;
+        }
+
+        let dir = self.sphere_direction();
+        let sin_half_angle = self
+            .plane_normals()
+            .iter()
+            .map(|n| n.dot(&dir))
+            .fold(f32::MAX, f32::min);
+
+        sin_half_angle.clamp(0.0, 1.0).asin() * 2.0
+    }
+
+    /// Returns the tangents of the left, right, bottom and top half-angles.
+    #[inline]
+    fn tangents(&self) -> (f32, f32, f32, f32) {
+        (
+            self.left.tan(),
+            self.right.tan(),
+            self.bottom.tan(),
+            self.top.tan(),
+        )
+    }
+
+    /// Returns the inward-facing normals of the left, right, bottom and top planes in view space.
+    fn plane_normals(&self) -> [Vec3; 4] {
+        [
+            Vec3::new(self.left.cos(), 0.0, -self.left.sin()),
+            Vec3::new(-self.right.cos(), 0.0, -self.right.sin()),
+            Vec3::new(0.0, self.bottom.cos(), -self.bottom.sin()),
+            Vec3::new(0.0, -self.top.cos(), -self.top.sin()),
+        ]
+    }
+
+    /// Returns the aspect ratio followed by the left, right, bottom and top half-angles.
+    pub(crate) fn to_array(self) -> [f32; 5] {
+        [
+            self.aspect_ratio,
+            self.left,
+            self.right,
+            self.bottom,
+            self.top,
+        ]
+    }
+
+    /// Creates the frustum from the values returned by [`Self::to_array`].
+    ///
+    /// # Arguments
+    /// * `values` - The aspect ratio followed by the left, right, bottom and top half-angles.
+    pub(crate) fn from_array(values: [f32; 5]) -> Self {
+        let [aspect_ratio, left, right, bottom, top] = values;
+        Self {
+            aspect_ratio,
+            left,
+            right,
+            bottom,
+            top,
+        }
+    }
+
+    /// Returns the comma-separated values of [`Self::to_array`], used by the text-based
+    /// attributes of the image formats.
+    #[allow(dead_code)]
+    pub(crate) fn to_text(self) -> String {
+        self.to_array()
+            .iter()
+            .map(|v| v.to_string())
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    /// Parses the frustum from the text returned by [`Self::to_text`].
+    ///
+    /// # Arguments
+    /// * `text` - The comma-separated values of the frustum.
+    #[allow(dead_code)]
+    pub(crate) fn from_text(text: &str) -> Result<Self> {
+        let values = text
+            .split(',')
+            .map(|v| v.trim().parse::<f32>())
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .ok()
+            .and_then(|v| <[f32; 5]>::try_from(v).ok())
+            .ok_or_else(|| Error::IO(format!("Invalid camera frustum '{}'", text)))?;
+
+        Ok(Self::from_array(values))
+    }
+
+    /// Writes the aspect ratio followed by the half-angles to the given writer.
+    ///
+    /// # Arguments
+    /// * `writer` - The writer to which the frustum should be written.
+    pub(crate) fn write_writer<W: Write>(&self, writer: &mut W) -> Result<()> {
+        for v in self.to_array() {
+            writer.write_f32::<byteorder::LittleEndian>(v)?;
+        }
+
+        Ok(())
+    }
+
+    /// Reads the frustum written by [`Self::write_writer`] from the given reader.
+    ///
+    /// # Arguments
+    /// * `reader` - The reader from which the frustum should be read.
+    pub(crate) fn from_reader<R: Read>(reader: &mut R) -> Result<Self> {
+        let mut values = [0f32; 5];
+        for v in values.iter_mut() {
+            *v = reader.read_f32::<byteorder::LittleEndian>()?;
+        }
+
+        Ok(Self::from_array(values))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_frustum_from_projection_matrix() {
+        let fovy = 60f32.to_radians();
+        let projection = nalgebra_glm::perspective(16.0 / 9.0, fovy, 0.1, 100.0);
+        let frustum = CameraFrustum::from_projection_matrix(&projection).unwrap();
+
+        let expected = CameraFrustum::perspective(fovy, 16.0 / 9.0);
+        assert!((frustum.aspect_ratio() - expected.aspect_ratio()).abs() < 1e-5);
+        assert!((frustum.vertical_half_angles().1 - fovy / 2.0).abs() < 1e-5);
+        assert!((frustum.horizontal_half_angles().0 - expected.left).abs() < 1e-5);
+        assert!(frustum.is_symmetric() || frustum.offset().norm() < 1e-5);
+        assert!(frustum.has_same_shape(&expected));
+
+        // the fitted sphere is limited by the smaller vertical field of view
+        assert!((frustum.sphere_angle() - fovy).abs() < 1e-4);
+        assert!((frustum.sphere_direction() - Vec3::new(0.0, 0.0, -1.0)).norm() < 1e-5);
+
+        let projection = nalgebra_glm::ortho(-2.0, 2.0, -1.0, 1.0, 0.1, 10.0);
+        let frustum = CameraFrustum::from_projection_matrix(&projection).unwrap();
+        assert!(frustum.is_orthographic());
+        assert_eq!(frustum.aspect_ratio(), 2.0);
+        assert_eq!(frustum.sphere_angle(), 0.0);
+
+        assert!(CameraFrustum::from_projection_matrix(&Mat4::zeros()).is_none());
+    }
+
+    #[test]
+    fn test_off_axis_frustum() {
+        let frustum = CameraFrustum::off_axis(
+            10f32.to_radians(),
+            50f32.to_radians(),
+            30f32.to_radians(),
+            30f32.to_radians(),
+        );
+        assert!(frustum.is_valid());
+        assert!(!frustum.is_symmetric());
+
+        // the center of the frame lies to the right of the view axis
+        let offset = frustum.offset();
+        assert!(offset.x > 0.0 && offset.y.abs() < 1e-6);
+        assert!(frustum.sphere_direction().x > 0.0);
+
+        // the fitted sphere touches the frustum, but does not exceed it
+        let angle = frustum.sphere_angle();
+        assert!(angle > 0.0 && angle <= 60f32.to_radians() + 1e-5);
+
+        let dir = frustum.sphere_direction();
+        for n in frustum.plane_normals() {
+            assert!(n.dot(&dir) >= (angle / 2.0).sin() - 1e-5);
+        }
+
+        let symmetric = CameraFrustum::perspective(60f32.to_radians(), 1.0);
+        assert!(!frustum.has_same_shape(&symmetric));
+
+        assert!(!CameraFrustum::off_axis(0.2, -0.3, 0.1, 0.1).is_valid());
+        assert!(!CameraFrustum::orthographic(0.0).is_valid());
+        assert!(CameraFrustum::default().is_valid());
+    }
+}
@@ -0,0 +1,578 @@
+use crate::{interpolate_angle, search_angle_range, CameraFrustum, PixelContribColorMapDescriptor};
+
+/// Determines how angles outside of the range of the camera angles of the maps are handled.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Extrapolation {
+    /// The value of the first or last map is returned.
+    #[default]
+    Clamp,
+
+    /// The interpolation of the first or last two maps is continued beyond their angles.
+    Extend,
+}
+
+/// An interpolator for the pixel contribution values of the maps w.r.t their camera angles.
+pub trait AngleInterpolator: Send + Sync {
+    /// Returns the name of the interpolator.
+    fn name(&self) -> &'static str;
+
+    /// Interpolates the pixel contribution for the given camera angle. Angles outside of the
+    /// range of the given camera angles are clamped to the first or last value.
+    ///
+    /// # Arguments
+    /// * `angle` - The camera angle to interpolate the pixel contribution for.
+    /// * `num` - The number of maps. Must be at least one.
+    /// * `angle_at` - Returns the camera angle of the map at the given index. The camera angles
+    ///   must be sorted in ascending order.
+    /// * `value_at` - Returns the pixel contribution value of the map at the given index.
+    fn interpolate(
+        &self,
+        angle: f32,
+        num: usize,
+        angle_at: &dyn Fn(usize) -> f32,
+        value_at: &dyn Fn(usize) -> f32,
+    ) -> f32 {
+        self.interpolate_with_extrapolation(angle, num, angle_at, value_at, Extrapolation::Clamp)
+    }
+
+    /// Interpolates the pixel contribution for the given camera angle like
+    /// [`Self::interpolate`], but handles angles outside of the range of the given camera angles
+    /// as configured.
+    ///
+    /// # Arguments
+    /// * `angle` - The camera angle to interpolate the pixel contribution for.
+    /// * `num` - The number of maps. Must be at least one.
+    /// * `angle_at` - Returns the camera angle of the map at the given index. The camera angles
+    ///   must be sorted in ascending order.
+    /// * `value_at` - Returns the pixel contribution value of the map at the given index.
+    /// * `extrapolation` - Determines how angles outside of the range are handled.
+    fn interpolate_with_extrapolation(
+        &self,
+        angle: f32,
+        num: usize,
+        angle_at: &dyn Fn(usize) -> f32,
+        value_at: &dyn Fn(usize) -> f32,
+        extrapolation: Extrapolation,
+    ) -> f32;
+}
+
+/// Returns the indices of the two maps the given angle is interpolated between or, if there are
+/// no such maps, the index of the map whose value is returned. Outside of the range of the camera
+/// angles, the first or last two maps are returned for [`Extrapolation::Extend`].
+///
+/// # Arguments
+/// * `num` - The number of maps. Must be at least one.
+/// * `angle` - The camera angle to interpolate the pixel contribution for.
+/// * `angle_at` - Returns the camera angle of the map at the given index.
+/// * `extrapolation` - Determines how angles outside of the range are handled.
+fn select_interval(
+    num: usize,
+    angle: f32,
+    angle_at: &dyn Fn(usize) -> f32,
+    extrapolation: Extrapolation,
+) -> Result<(usize, usize), usize> {
+    let (i0, i1) = match search_angle_range(num, angle, angle_at) {
+        (i0, Some(i1)) => (i0, i1),
+        (_, None) if extrapolation == Extrapolation::Extend && num >= 2 => {
+            if angle < angle_at(0) {
+                (0, 1)
+            } else {
+                (num - 2, num - 1)
+            }
+        }
+        (i0, None) => return Err(i0),
+    };
+
+    if angle_at(i1) > angle_at(i0) {
+        Ok((i0, i1))
+    } else {
+        Err(i0)
+    }
+}
+
+/// Interpolates linearly w.r.t the camera angle between the two maps bracketing the angle.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LinearAngleInterpolator;
+
+impl AngleInterpolator for LinearAngleInterpolator {
+    fn name(&self) -> &'static str {
+        "Linear"
+    }
+
+    fn interpolate_with_extrapolation(
+        &self,
+        angle: f32,
+        num: usize,
+        angle_at: &dyn Fn(usize) -> f32,
+        value_at: &dyn Fn(usize) -> f32,
+        extrapolation: Extrapolation,
+    ) -> f32 {
+        match select_interval(num, angle, angle_at, extrapolation) {
+            Ok((i0, i1)) => {
+                let t = (angle - angle_at(i0)) / (angle_at(i1) - angle_at(i0));
+                value_at(i0) * (1.0 - t) + value_at(i1) * t
+            }
+            Err(i) => value_at(i),
+        }
+    }
+}
+
+/// Interpolates linearly w.r.t the tangent of the half camera angle between the two maps
+/// bracketing the angle. This is the default interpolator.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TangentAngleInterpolator;
+
+impl AngleInterpolator for TangentAngleInterpolator {
+    fn name(&self) -> &'static str {
+        "Tangent"
+    }
+
+    fn interpolate_with_extrapolation(
+        &self,
+        angle: f32,
+        num: usize,
+        angle_at: &dyn Fn(usize) -> f32,
+        value_at: &dyn Fn(usize) -> f32,
+        extrapolation: Extrapolation,
+    ) -> f32 {
+        match select_interval(num, angle, angle_at, extrapolation) {
+            Ok((i0, i1)) => interpolate_angle(
+                angle,
+                angle_at(i0),
+                value_at(i0),
+                angle_at(i1),
+                value_at(i1),
+            ),
+            Err(i) => value_at(i),
+        }
+    }
+}
+
+/// Interpolates with a quadratic polynomial through the two maps bracketing the angle and the
+/// closer of their neighboring maps. Falls back to linear interpolation for less than 3 maps.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct QuadraticAngleInterpolator;
+
+impl AngleInterpolator for QuadraticAngleInterpolator {
+    fn name(&self) -> &'static str {
+        "Quadratic"
+    }
+
+    fn interpolate_with_extrapolation(
+        &self,
+        angle: f32,
+        num: usize,
+        angle_at: &dyn Fn(usize) -> f32,
+        value_at: &dyn Fn(usize) -> f32,
+        extrapolation: Extrapolation,
+    ) -> f32 {
+        if num < 3 {
+            return LinearAngleInterpolator.interpolate_with_extrapolation(
+                angle,
+                num,
+                angle_at,
+                value_at,
+                extrapolation,
+            );
+        }
+
+        let (i0, i1) = match select_interval(num, angle, angle_at, extrapolation) {
+            Ok(interval) => interval,
+            Err(i) => return value_at(i),
+        };
+
+        // select the neighbor which is closer to the angle as third point
+        let start = if i0 == 0 {
+            0
+        } else if i1 + 1 >= num || angle - angle_at(i0 - 1) <= angle_at(i1 + 1) - angle {
+            i0 - 1
+        } else {
+            i0
+        };
+
+        let x = [angle_at(start), angle_at(start + 1), angle_at(start + 2)];
+        if x[0] >= x[1] || x[1] >= x[2] {
+            return LinearAngleInterpolator.interpolate_with_extrapolation(
+                angle,
+                num,
+                angle_at,
+                value_at,
+                extrapolation,
+            );
+        }
+
+        // evaluate the Lagrange polynomial through the three points
+        (0..3)
+            .map(|j| {
+                let weight = (0..3)
+                    .filter(|k| *k != j)
+                    .fold(1f32, |w, k| w * (angle - x[k]) / (x[j] - x[k]));
+
+                weight * value_at(start + j)
+            })
+            .sum()
+    }
+}
+
+/// Interpolates with a piecewise cubic Hermite polynomial whose slopes are chosen to preserve
+/// the monotonicity of the values (PCHIP), see Fritsch and Carlson, "Monotone Piecewise Cubic
+/// Interpolation". In contrast to the quadratic interpolator, the result never overshoots the
+/// values of the bracketing maps.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PchipAngleInterpolator;
+
+impl AngleInterpolator for PchipAngleInterpolator {
+    fn name(&self) -> &'static str {
+        "PCHIP"
+    }
+
+    fn interpolate_with_extrapolation(
+        &self,
+        angle: f32,
+        num: usize,
+        angle_at: &dyn Fn(usize) -> f32,
+        value_at: &dyn Fn(usize) -> f32,
+        extrapolation: Extrapolation,
+    ) -> f32 {
+        let (i0, i1) = match select_interval(num, angle, angle_at, extrapolation) {
+            Ok(interval) => interval,
+            Err(i) => return value_at(i),
+        };
+
+        // fetch the values required for the slopes at both ends of the interval
+        let lo = i0.saturating_sub(1);
+        let hi = (i1 + 1).min(num - 1);
+        let values: Vec<f32> = (lo..=hi).map(value_at).collect();
+        let y = |k: usize| values[k - lo];
+
+        let h = |k: usize| angle_at(k + 1) - angle_at(k);
+        let d = |k: usize| {
+            let h = h(k);
+            if h > 0.0 {
+                (y(k + 1) - y(k)) / h
+            } else {
+                0.0
+            }
+        };
+
+        let slope = |k: usize| -> f32 {
+            if k == 0 || k == num - 1 {
+                // one-sided three-point estimate at the boundaries
+                if num < 3 {
+                    return d(0);
+                }
+                let (k0, k1) = if k == 0 { (0, 1) } else { (num - 2, num - 3) };
+
+                let (h0, h1) = (h(k0), h(k1));
+                let (d0, d1) = (d(k0), d(k1));
+                let m = ((2.0 * h0 + h1) * d0 - h0 * d1) / (h0 + h1);
+
+                if m.signum() != d0.signum() || d0 == 0.0 {
+                    0.0
+                } else if d0.signum() != d1.signum() && m.abs() > 3.0 * d0.abs() {
+                    3.0 * d0
+                } else {
+                    m
+                }
+            } else {
+                let (h0, h1) = (h(k - 1), h(k));
+                let (d0, d1) = (d(k - 1), d(k));
+                if d0 * d1 <= 0.0 {
+                    return 0.0;
+                }
+
+                // weighted harmonic mean of the neighboring secants
+                let w0 = 2.0 * h1 + h0;
+                let w1 = h1 + 2.0 * h0;
+
+                (w0 + w1) / (w0 / d0 + w1 / d1)
+            }
+        };
+
+        let h0 = h(i0);
+        let t = (angle - angle_at(i0)) / h0;
+        let t2 = t * t;
+        let t3 = t2 * t;
+
+        let h00 = 2.0 * t3 - 3.0 * t2 + 1.0;
+        let h10 = t3 - 2.0 * t2 + t;
+        let h01 = -2.0 * t3 + 3.0 * t2;
+        let h11 = t3 - t2;
+
+        h00 * y(i0) + h10 * h0 * slope(i0) + h01 * y(i1) + h11 * h0 * slope(i1)
+    }
+}
+
+/// Interpolates the pixel contribution of maps computed for different camera frusta and camera
+/// distances.
+///
+/// Only the maps of the frustum shape closest to the given frustum are used, see
+/// [`CameraFrustum::shape_distance`]. These maps are split into groups of maps computed at the
+/// same distance, where each map computed at the distance fitting its frustum forms a group of
+/// its own. Inside of each group, the values are interpolated w.r.t the camera angle of the given
+/// frustum. The values of the groups are then interpolated w.r.t the given sphere angle. Hence,
+/// maps computed only at the fitting distance are interpolated w.r.t the sphere angle alone.
+///
+/// A map computed at the fitting distance, whose sphere angle equals the one of a group of
+/// another distance, has been computed for a camera at that distance, e.g., the map of 60 degrees
+/// and a distance of 2 radii. Such a map is merged into the group and replaces the map of the
+/// group with the same camera angle, if any, s.t. the sphere angles of all groups are distinct.
+///
+/// # Arguments
+/// * `interpolator` - The interpolator used for both interpolations.
+/// * `num` - The number of maps. Must be at least one.
+/// * `descriptor_at` - Returns the descriptor of the map at the given index. The descriptors must
+///   be sorted in ascending order w.r.t their camera angles and, for equal angles, distances.
+/// * `frustum` - The frustum of the camera.
+/// * `sphere_angle` - The angle under which the bounding sphere is seen from the camera.
+/// * `value_at` - Returns the pixel contribution value of the map at the given index.
+pub(crate) fn interpolate_camera(
+    interpolator: &dyn AngleInterpolator,
+    num: usize,
+    descriptor_at: &dyn Fn(usize) -> PixelContribColorMapDescriptor,
+    frustum: &CameraFrustum,
+    sphere_angle: f32,
+    value_at: &dyn Fn(usize) -> f32,
+) -> f32 {
+    // select the maps of the frustum shape closest to the one of the camera
+    let shape = (0..num)
+        .map(|i| descriptor_at(i).frustum())
+        .min_by(|f1, f2| {
+            f1.shape_distance(frustum)
+                .total_cmp(&f2.shape_distance(frustum))
+        })
+        .unwrap_or(*frustum);
+
+    // split the maps into groups of the same distance ordered by ascending sphere angle
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    for i in (0..num).filter(|i| descriptor_at(*i).frustum().has_same_shape(&shape)) {
+        let distance = descriptor_at(i).distance();
+        match groups
+            .iter_mut()
+            .find(|g| distance > 0.0 && descriptor_at(g[0]).distance() == distance)
+        {
+            Some(group) => group.push(i),
+            None => groups.push(vec![i]),
+        }
+    }
+
+    groups.sort_by(|g1, g2| {
+        descriptor_at(g1[0])
+            .sphere_angle()
+            .total_cmp(&descriptor_at(g2[0]).sphere_angle())
+    });
+
+    // merge the fitted maps into the groups of other distances with the same sphere angle
+    let sphere_angle_of = |g: &[usize]| descriptor_at(g[0]).sphere_angle();
+    let mut merged: Vec<Vec<usize>> = Vec::with_capacity(groups.len());
+    for group in groups {
+        let previous = match merged.last_mut() {
+            Some(previous) if sphere_angle_of(previous) == sphere_angle_of(&group) => previous,
+            _ => {
+                merged.push(group);
+                continue;
+            }
+        };
+
+        // keep the group of the distance and insert the fitted maps into it
+        let (mut target, fitted) = if descriptor_at(previous[0]).distance() > 0.0 {
+            (std::mem::take(previous), group)
+        } else {
+            (group, std::mem::take(previous))
+        };
+
+        for i in fitted {
+            let angle = descriptor_at(i).camera_angle();
+            let pos = target.partition_point(|j| descriptor_at(*j).camera_angle() < angle);
+            match target.get(pos) {
+                Some(j) if descriptor_at(*j).camera_angle() == angle => target[pos] = i,
+                _ => target.insert(pos, i),
+            }
+        }
+
+        *previous = target;
+    }
+    let groups = merged;
+
+    let camera_angle = frustum.sphere_angle();
+    interpolator.interpolate(
+        sphere_angle,
+        groups.len(),
+        &|g| descriptor_at(groups[g][0]).sphere_angle(),
+        &|g| {
+            let group = &groups[g];
+            interpolator.interpolate(
+                camera_angle,
+                group.len(),
+                &|i| descriptor_at(group[i]).camera_angle(),
+                &|i| value_at(group[i]),
+            )
+        },
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const INTERPOLATORS: [&dyn AngleInterpolator; 4] = [
+        &LinearAngleInterpolator,
+        &TangentAngleInterpolator,
+        &QuadraticAngleInterpolator,
+        &PchipAngleInterpolator,
+    ];
+
+    #[test]
+    fn test_exact_match_and_out_of_range() {
+        let angles = [0.0, 0.4, 0.9, 1.5, 2.0];
+        let values = [0.1, 0.3, 0.2, 0.6, 0.7];
+        let value_at = |i: usize| values[i];
+
+        for interpolator in INTERPOLATORS {
+            for (angle, value) in angles.iter().zip(values.iter()) {
+                let v = interpolator.interpolate(*angle, angles.len(), &|i| angles[i], &value_at);
+                assert!((v - value).abs() < 1e-5, "{}", interpolator.name());
+            }
+
+            assert_eq!(
+                interpolator.interpolate(-1.0, angles.len(), &|i| angles[i], &value_at),
+                0.1
+            );
+            assert_eq!(
+                interpolator.interpolate(3.0, angles.len(), &|i| angles[i], &value_at),
+                0.7
+            );
+
+            // a single map is returned for any angle
+            assert_eq!(
+                interpolator.interpolate(0.5, 1, &|i| angles[i], &value_at),
+                0.1
+            );
+        }
+    }
+
+    #[test]
+    fn test_extrapolation() {
+        let angles = [0.5, 1.0, 1.5];
+        let quadratic = |x: f32| 0.1 + 0.2 * x - 0.15 * x * x;
+        let values: Vec<f32> = angles.iter().map(|x| quadratic(*x)).collect();
+        let interpolate = |interpolator: &dyn AngleInterpolator, angle: f32, extrapolation| {
+            interpolator.interpolate_with_extrapolation(
+                angle,
+                angles.len(),
+                &|i| angles[i],
+                &|i| values[i],
+                extrapolation,
+            )
+        };
+
+        for interpolator in INTERPOLATORS {
+            // the extrapolation does not change the values inside of the range
+            for angle in [0.5, 0.7, 1.2, 1.5] {
+                assert_eq!(
+                    interpolate(interpolator, angle, Extrapolation::Extend),
+                    interpolate(interpolator, angle, Extrapolation::Clamp),
+                    "{}",
+                    interpolator.name()
+                );
+            }
+
+            assert_eq!(
+                interpolate(interpolator, 2.0, Extrapolation::Clamp),
+                values[2]
+            );
+        }
+
+        // the interpolation of the boundary maps is continued
+        let linear = |i0: usize, x: f32| {
+            let t = (x - angles[i0]) / (angles[i0 + 1] - angles[i0]);
+            values[i0] * (1.0 - t) + values[i0 + 1] * t
+        };
+        let v = interpolate(&LinearAngleInterpolator, 0.0, Extrapolation::Extend);
+        assert!((v - linear(0, 0.0)).abs() < 1e-6);
+        let v = interpolate(&LinearAngleInterpolator, 2.0, Extrapolation::Extend);
+        assert!((v - linear(1, 2.0)).abs() < 1e-6);
+
+        let v = interpolate(&TangentAngleInterpolator, 2.0, Extrapolation::Extend);
+        let expected = interpolate_angle(2.0, angles[1], values[1], angles[2], values[2]);
+        assert!((v - expected).abs() < 1e-6);
+
+        for x in [0.0, 0.25, 1.75, 2.0] {
+            let v = interpolate(&QuadraticAngleInterpolator, x, Extrapolation::Extend);
+            assert!((v - quadratic(x)).abs() < 1e-5);
+        }
+
+        // a single map is returned for any angle
+        let v = LinearAngleInterpolator.interpolate_with_extrapolation(
+            2.0,
+            1,
+            &|i| angles[i],
+            &|i| values[i],
+            Extrapolation::Extend,
+        );
+        assert_eq!(v, values[0]);
+    }
+
+    #[test]
+    fn test_equal_angles() {
+        // equal adjacent angles must not divide by zero
+        let angles = [0.5, 1.0, 1.0];
+        let values = [0.1, 0.2, 0.6];
+        for interpolator in INTERPOLATORS {
+            for angle in [0.75, 1.0, 1.5] {
+                let v = interpolator.interpolate(angle, 3, &|i| angles[i], &|i| values[i]);
+                assert!(v.is_finite(), "{}", interpolator.name());
+            }
+
+            let v = interpolator.interpolate(1.0, 2, &|i| angles[i + 1], &|i| values[i + 1]);
+            assert!(v == 0.2 || v == 0.6, "{}", interpolator.name());
+        }
+    }
+
+    #[test]
+    fn test_reproduce_polynomials() {
+        let angles = [0.0, 0.5, 1.0, 1.5, 2.0];
+
+        // linear functions are reproduced by the linear, quadratic and PCHIP interpolators
+        let linear = |x: f32| 0.2 + 0.3 * x;
+        let values: Vec<f32> = angles.iter().map(|x| linear(*x)).collect();
+        for interpolator in [
+            &LinearAngleInterpolator as &dyn AngleInterpolator,
+            &QuadraticAngleInterpolator,
+            &PchipAngleInterpolator,
+        ] {
+            for i in 0..40 {
+                let x = i as f32 * 0.05;
+                let v = interpolator.interpolate(x, angles.len(), &|i| angles[i], &|i| values[i]);
+                assert!((v - linear(x)).abs() < 1e-5, "{}", interpolator.name());
+            }
+        }
+
+        // quadratic functions are reproduced by the quadratic interpolator
+        let quadratic = |x: f32| 0.1 + 0.2 * x - 0.15 * x * x;
+        let values: Vec<f32> = angles.iter().map(|x| quadratic(*x)).collect();
+        for i in 0..40 {
+            let x = i as f32 * 0.05;
+            let v = QuadraticAngleInterpolator
+                .interpolate(x, angles.len(), &|i| angles[i], &|i| values[i]);
+            assert!((v - quadratic(x)).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_pchip_monotone() {
+        let angles = [0.0, 0.2, 0.3, 1.0, 1.1, 2.0];
+        let values = [0.0, 0.0, 0.5, 0.55, 1.0, 1.0];
+
+        let mut last = 0f32;
+        for i in 0..=200 {
+            let x = i as f32 * 0.01;
+            let v =
+                PchipAngleInterpolator.interpolate(x, angles.len(), &|i| angles[i], &|i| values[i]);
+
+            assert!(v >= last - 1e-6, "{} {} {}", x, v, last);
+            assert!((0.0..=1.0).contains(&v));
+            last = v;
+        }
+    }
+}
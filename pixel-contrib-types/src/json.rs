@@ -0,0 +1,289 @@
+use std::{
+    io::{BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde_json::{json, Map, Value};
+
+use crate::{
+    CameraFrustum, Error, PixelContribColorMapDescriptor, PixelContributionMap,
+    PixelContributionMaps, PixelContributionMetadata, Result, SphereParameterization,
+};
+
+/// The version of the JSON representation.
+const JSON_VERSION: u64 = 1;
+
+/// The value of the format field of the JSON representation.
+const JSON_FORMAT: &str = "PCMP";
+
+/// Export and import of the JSON representation. Requires the `json` feature.
+///
+/// The JSON document has the following layout, where the values of each map are stored as
+/// base64 encoded little-endian 32-bit floats in row-major order, the optional frustum as the
+/// aspect ratio followed by the left, right, bottom and top half-angles and the optional camera
+/// distance as multiple of the sphere radius, where 0 denotes the distance fitting the frustum:
+/// ```json
+/// {
+///   "format": "PCMP",
+///   "version": 1,
+///   "metadata": { "key": "value" },
+///   "maps": [
+///     {
+///       "size": 64, "camera_angle": 1.5708, "parameterization": "octahedral",
+///       "frustum": [1.0, 0.7854, 0.7854, 0.7854, 0.7854], "distance": 0.0, "values": "..."
+///     }
+///   ]
+/// }
+/// ```
+impl PixelContributionMaps {
+    /// Returns the JSON representation of the maps.
+    pub fn to_json(&self) -> String {
+        self.to_json_value().to_string()
+    }
+
+    /// Writes the JSON representation of the maps to the given writer.
+    ///
+    /// # Arguments
+    /// * `writer` - The writer to which the maps should be written.
+    pub fn write_json_writer<W: Write>(&self, writer: &mut W) -> Result<()> {
+        serde_json::to_writer(writer, &self.to_json_value())
+            .map_err(|err| Error::IO(format!("Failed to write JSON: {}", err)))
+    }
+
+    /// Writes the JSON representation of the maps to the given path.
+    ///
+    /// # Arguments
+    /// * `path` - The path to which the maps should be written.
+    pub fn write_json_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let file = std::fs::File::create(path)?;
+        let mut writer = BufWriter::new(file);
+        self.write_json_writer(&mut writer)?;
+        writer.flush()?;
+
+        Ok(())
+    }
+
+    /// Parses the maps from the given JSON representation.
+    ///
+    /// # Arguments
+    /// * `json` - The JSON representation of the maps.
+    pub fn from_json(json: &str) -> Result<Self> {
+        let value: Value = serde_json::from_str(json)
+            .map_err(|err| Error::IO(format!("Failed to parse JSON: {}", err)))?;
+
+        Self::from_json_value(&value)
+    }
+
+    /// Reads the maps from the JSON representation provided by the given reader.
+    ///
+    /// # Arguments
+    /// * `reader` - The reader from which the maps should be read.
+    pub fn from_json_reader<R: Read>(reader: &mut R) -> Result<Self> {
+        let value: Value = serde_json::from_reader(reader)
+            .map_err(|err| Error::IO(format!("Failed to parse JSON: {}", err)))?;
+
+        Self::from_json_value(&value)
+    }
+
+    /// Reads the maps from the JSON file at the given path.
+    ///
+    /// # Arguments
+    /// * `path` - The path from which the maps should be read.
+    pub fn from_json_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file = std::fs::File::open(path)?;
+        Self::from_json_reader(&mut BufReader::new(file))
+    }
+
+    /// Returns the JSON representation of the maps as value.
+    fn to_json_value(&self) -> Value {
+        let metadata: Map<String, Value> = self
+            .metadata
+            .iter()
+            .map(|(k, v)| (k.to_string(), Value::String(v.to_string())))
+            .collect();
+
+        let maps: Vec<Value> = self
+            .maps
+            .iter()
+            .map(|map| {
+                let values: Vec<u8> = map
+                    .pixel_contrib
+                    .iter()
+                    .flat_map(|v| v.to_le_bytes())
+                    .collect();
+
+                json!({
+                    "size": map.descriptor.size(),
+                    "camera_angle": map.descriptor.camera_angle(),
+                    "parameterization": map.descriptor.parameterization().name(),
+                    "frustum": map.descriptor.frustum().to_array(),
+                    "distance": map.descriptor.distance(),
+                    "values": BASE64.encode(values),
+                })
+            })
+            .collect();
+
+        json!({
+            "format": JSON_FORMAT,
+            "version": JSON_VERSION,
+            "metadata": metadata,
+            "maps": maps,
+        })
+    }
+
+    /// Creates the maps from the given JSON value.
+    ///
+    /// # Arguments
+    /// * `value` - The JSON representation of the maps.
+    fn from_json_value(value: &Value) -> Result<Self> {
+        if value["format"].as_str() != Some(JSON_FORMAT) {
+            return Err(Error::IO(
+                "Invalid JSON pixel contribution maps".to_string(),
+            ));
+        }
+
+        let version = value["version"].as_u64().unwrap_or_default();
+        if version == 0 || version > JSON_VERSION {
+            return Err(Error::IO(format!("Unsupported JSON version {}", version)));
+        }
+
+        let mut metadata = PixelContributionMetadata::new();
+        if let Some(entries) = value["metadata"].as_object() {
+            for (key, value) in entries {
+                let value = value.as_str().ok_or_else(|| {
+                    Error::IO(format!("Metadata value for '{}' is not a string", key))
+                })?;
+
+                metadata.set(key.as_str(), value);
+            }
+        }
+
+        let maps = value["maps"]
+            .as_array()
+            .ok_or_else(|| Error::IO("Missing maps in JSON".to_string()))?
+            .iter()
+            .map(map_from_json_value)
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut result = Self::from_maps(maps);
+        result.metadata = metadata;
+
+        Ok(result)
+    }
+}
+
+/// Creates a single map from the given JSON value.
+///
+/// # Arguments
+/// * `value` - The JSON representation of the map.
+fn map_from_json_value(value: &Value) -> Result<PixelContributionMap> {
+    let size = value["size"]
+        .as_u64()
+        .ok_or_else(|| Error::IO("Missing map size in JSON".to_string()))? as usize;
+    let camera_angle = value["camera_angle"]
+        .as_f64()
+        .ok_or_else(|| Error::IO("Missing camera angle in JSON".to_string()))?
+        as f32;
+    let parameterization = match value["parameterization"].as_str() {
+        Some(name) => SphereParameterization::from_name(name)?,
+        None => SphereParameterization::default(),
+    };
+
+    let values = value["values"]
+        .as_str()
+        .ok_or_else(|| Error::IO("Missing map values in JSON".to_string()))?;
+    let values = BASE64
+        .decode(values)
+        .map_err(|err| Error::IO(format!("Invalid map values in JSON: {}", err)))?;
+
+    let mut descriptor = PixelContribColorMapDescriptor::new_with_parameterization(
+        size,
+        camera_angle,
+        parameterization,
+    );
+    if let Some(frustum) = value["frustum"].as_array() {
+        let frustum = frustum
+            .iter()
+            .map(|v| v.as_f64().map(|v| v as f32))
+            .collect::<Option<Vec<_>>>()
+            .and_then(|v| <[f32; 5]>::try_from(v).ok())
+            .ok_or_else(|| Error::IO("Invalid camera frustum in JSON".to_string()))?;
+        descriptor = descriptor.with_frustum(CameraFrustum::from_array(frustum));
+    }
+    if let Some(distance) = value["distance"].as_f64() {
+        descriptor = descriptor.with_distance(distance as f32);
+    }
+    if values.len() != descriptor.num_values() * 4 {
+        return Err(Error::IO(format!(
+            "Expected {} values for map size {}, but got {} bytes",
+            descriptor.num_values(),
+            size,
+            values.len()
+        )));
+    }
+
+    let mut map = PixelContributionMap::new(descriptor);
+    map.pixel_contrib
+        .iter_mut()
+        .zip(values.chunks_exact(4))
+        .for_each(|(p, v)| *p = f32::from_le_bytes([v[0], v[1], v[2], v[3]]));
+
+    Ok(map)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_json_round_trip() {
+        let mut maps = Vec::new();
+        for (i, parameterization) in [
+            SphereParameterization::Octahedral,
+            SphereParameterization::CubeMap,
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let descriptor = PixelContribColorMapDescriptor::new_with_parameterization(
+                8 + i,
+                0.3 + i as f32,
+                parameterization,
+            );
+
+            let mut map = PixelContributionMap::new(descriptor);
+            map.pixel_contrib
+                .iter_mut()
+                .enumerate()
+                .for_each(|(j, p)| *p = (j as f32 * 0.1).sin().abs());
+            maps.push(map);
+        }
+
+        let frustum = CameraFrustum::off_axis(0.2, 0.7, 0.3, 0.4);
+        maps.push(PixelContributionMap::new(
+            PixelContribColorMapDescriptor::new_with_frustum(4, frustum, Default::default())
+                .with_distance(1.5),
+        ));
+
+        let mut maps = PixelContributionMaps::from_maps(maps);
+        maps.metadata_mut().set_source_model("models/\"duck\".glb");
+        maps.metadata_mut().set("custom", "line 1\nline 2");
+
+        let json = maps.to_json();
+        let maps2 = PixelContributionMaps::from_json(&json).unwrap();
+        assert!(maps == maps2);
+
+        let mut buf = Vec::new();
+        maps.write_json_writer(&mut buf).unwrap();
+        let maps2 = PixelContributionMaps::from_json_reader(&mut buf.as_slice()).unwrap();
+        assert!(maps == maps2);
+
+        // invalid documents
+        assert!(PixelContributionMaps::from_json("{}").is_err());
+        assert!(PixelContributionMaps::from_json("[1, 2").is_err());
+
+        let truncated = json.replace("\"size\":8", "\"size\":9");
+        assert!(PixelContributionMaps::from_json(&truncated).is_err());
+    }
+}
@@ -1,19 +1,66 @@
+mod archive;
+mod checksum;
+mod encoding;
 mod error;
+mod frustum;
+mod interpolation;
+#[cfg(feature = "json")]
+mod json;
+mod metadata;
+mod mipmap;
+#[cfg(feature = "npy")]
+mod numpy;
 mod octahedron;
+#[cfg(feature = "exr")]
+mod openexr;
+mod operations;
+mod parameterization;
+mod resampling;
+mod sampling;
+#[cfg(feature = "serde")]
+mod serde_support;
+mod spherical_harmonics;
+mod statistics;
+mod stream_writer;
+#[cfg(feature = "tiff")]
+mod tiff_image;
+mod validation;
+mod view;
 
 use std::{
     io::{BufReader, Read, Write},
     path::Path,
+    sync::Arc,
 };
 
 use byteorder::{ReadBytesExt, WriteBytesExt};
-use math::clamp;
+use checksum::{ChecksumReader, ChecksumWriter};
 use nalgebra_glm::{Vec2, Vec3};
 
+pub use archive::*;
+pub use encoding::*;
 pub use error::*;
+pub use frustum::*;
+pub use interpolation::*;
+pub use metadata::*;
 pub use octahedron::*;
-
-const PIXEL_CONTRIBUTION_MAP_VERSION: u32 = 1;
+pub use operations::*;
+pub use parameterization::*;
+pub use sampling::*;
+pub use spherical_harmonics::*;
+pub use statistics::*;
+pub use stream_writer::*;
+pub use validation::*;
+pub use view::*;
+
+/// The current version of the pixel contribution map file format.
+///
+/// Version history:
+/// * `1` - Number of maps followed by the descriptor and raw values of each map.
+/// * `2` - Adds a key/value metadata block, the sphere parameterization, the camera frustum and
+///   the camera distance to the descriptor, a value encoding and compression, the optional mip
+///   chain and a CRC32 checksum after each map.
+const PIXEL_CONTRIBUTION_MAP_VERSION: u32 = 2;
 const PIXEL_CONTRIBUTION_MAP_IDENTIFIER: [u8; 4] = *b"PCMP";
 
 /// The descriptor for the pixel contribution map.
@@ -25,10 +72,22 @@ pub struct PixelContribColorMapDescriptor {
     /// The camera angle for the pixel contribution map. The angle is in radians.
     /// A value of 0 means that the camera is orthographic.
     camera_angle: f32,
+
+    /// The parameterization of the camera directions onto the map.
+    parameterization: SphereParameterization,
+
+    /// The frustum of the camera used for computing the map.
+    frustum: CameraFrustum,
+
+    /// The distance between the camera and the center of the bounding sphere as multiple of the
+    /// sphere radius. A value of 0 means that the camera has been placed where the sphere fits
+    /// the frustum.
+    distance: f32,
 }
 
 impl PixelContribColorMapDescriptor {
-    /// Creates a new descriptor for the pixel contribution map.
+    /// Creates a new descriptor for the pixel contribution map using the octahedral
+    /// parameterization.
     ///
     /// # Arguments
     /// * `size` - The size of the quadratic pixel contribution map.
@@ -36,12 +95,98 @@ impl PixelContribColorMapDescriptor {
     ///                    The angle is in radians. A value of 0 means that the camera is
     ///                    orthographic.
     pub fn new(size: usize, camera_angle: f32) -> Self {
+        Self::new_with_parameterization(size, camera_angle, SphereParameterization::Octahedral)
+    }
+
+    /// Creates a new descriptor for the pixel contribution map using the given parameterization.
+    /// The frustum is the quadratic symmetric frustum with the camera angle as field of view.
+    ///
+    /// # Arguments
+    /// * `size` - The size of the quadratic pixel contribution map.
+    /// * `camera_angle` - The camera angle for the pixel contribution map. The angle is in
+    ///   radians. A value of 0 means that the camera is orthographic.
+    /// * `parameterization` - The parameterization of the camera directions onto the map.
+    pub fn new_with_parameterization(
+        size: usize,
+        camera_angle: f32,
+        parameterization: SphereParameterization,
+    ) -> Self {
         Self {
             map_size: size,
             camera_angle,
+            parameterization,
+            frustum: CameraFrustum::square(camera_angle),
+            distance: 0.0,
+        }
+    }
+
+    /// Creates a new descriptor for the pixel contribution map computed with the given camera
+    /// frustum. The camera angle is the angle under which the bounding sphere fitted into the
+    /// frustum is seen, see [`CameraFrustum::sphere_angle`].
+    ///
+    /// # Arguments
+    /// * `size` - The size of the quadratic pixel contribution map.
+    /// * `frustum` - The frustum of the camera used for computing the map.
+    /// * `parameterization` - The parameterization of the camera directions onto the map.
+    pub fn new_with_frustum(
+        size: usize,
+        frustum: CameraFrustum,
+        parameterization: SphereParameterization,
+    ) -> Self {
+        Self {
+            map_size: size,
+            camera_angle: frustum.sphere_angle(),
+            parameterization,
+            frustum,
+            distance: 0.0,
+        }
+    }
+
+    /// Returns a copy of the descriptor with the given map size.
+    ///
+    /// # Arguments
+    /// * `size` - The size of the quadratic pixel contribution map.
+    #[inline]
+    pub fn with_size(&self, size: usize) -> Self {
+        Self {
+            map_size: size,
+            ..*self
         }
     }
 
+    /// Returns a copy of the descriptor with the given parameterization.
+    ///
+    /// # Arguments
+    /// * `parameterization` - The parameterization of the camera directions onto the map.
+    #[inline]
+    pub fn with_parameterization(&self, parameterization: SphereParameterization) -> Self {
+        Self {
+            parameterization,
+            ..*self
+        }
+    }
+
+    /// Returns a copy of the descriptor with the given frustum, but the same camera angle.
+    /// Used when reading descriptors, which store both.
+    ///
+    /// # Arguments
+    /// * `frustum` - The frustum of the camera used for computing the map.
+    #[inline]
+    pub(crate) fn with_frustum(&self, frustum: CameraFrustum) -> Self {
+        Self { frustum, ..*self }
+    }
+
+    /// Returns a copy of the descriptor for a camera placed at the given distance from the center
+    /// of the bounding sphere instead of where the sphere fits the frustum.
+    ///
+    /// # Arguments
+    /// * `distance` - The distance as multiple of the sphere radius. Must be larger than 1, s.t.
+    ///   the camera is outside of the sphere, or 0 for the distance fitting the frustum.
+    #[inline]
+    pub fn with_distance(&self, distance: f32) -> Self {
+        Self { distance, ..*self }
+    }
+
     /// Returns the size of the quadratic pixel contribution map.
     #[inline]
     pub fn size(&self) -> usize {
@@ -55,6 +200,47 @@ impl PixelContribColorMapDescriptor {
         self.camera_angle
     }
 
+    /// Returns the parameterization of the camera directions onto the map.
+    #[inline]
+    pub fn parameterization(&self) -> SphereParameterization {
+        self.parameterization
+    }
+
+    /// Returns the frustum of the camera used for computing the map.
+    #[inline]
+    pub fn frustum(&self) -> CameraFrustum {
+        self.frustum
+    }
+
+    /// Returns the distance between the camera and the center of the bounding sphere as multiple
+    /// of the sphere radius. A value of 0 means that the camera has been placed where the sphere
+    /// fits the frustum.
+    #[inline]
+    pub fn distance(&self) -> f32 {
+        self.distance
+    }
+
+    /// Returns the angle in radians under which the bounding sphere is seen from the camera. For
+    /// maps computed at the distance fitting the frustum, this is the camera angle.
+    pub fn sphere_angle(&self) -> f32 {
+        if self.distance > 0.0 {
+            (1.0 / self.distance).min(1.0).asin() * 2.0
+        } else {
+            self.camera_angle
+        }
+    }
+
+    /// Compares the descriptors by their camera angles and, for equal angles, by their
+    /// distances. This is the order in which the maps are kept.
+    ///
+    /// # Arguments
+    /// * `other` - The descriptor to compare with.
+    pub(crate) fn cmp_camera(&self, other: &Self) -> std::cmp::Ordering {
+        self.camera_angle
+            .total_cmp(&other.camera_angle)
+            .then(self.distance.total_cmp(&other.distance))
+    }
+
     /// Returns total number of values for the pixel contribution map.
     #[inline]
     pub fn num_values(&self) -> usize {
@@ -66,12 +252,35 @@ impl PixelContribColorMapDescriptor {
     /// # Arguments
     /// * `index` - The index of the pixel contribution value.
     pub fn camera_dir_from_index(&self, index: usize) -> Vec3 {
-        let u = (index % self.map_size) as f32 + 0.5;
-        let v = (index / self.map_size) as f32 + 0.5;
+        self.camera_dir_from_index_with_offset(index, Vec2::new(0.5, 0.5))
+    }
+
+    /// Returns the camera direction vector for the given position inside the texel with the
+    /// given index.
+    ///
+    /// # Arguments
+    /// * `index` - The index of the pixel contribution value.
+    /// * `offset` - The position inside the texel in the range [0, 1]^2, where (0.5, 0.5) is the
+    ///   texel center.
+    pub fn camera_dir_from_index_with_offset(&self, index: usize, offset: Vec2) -> Vec3 {
+        let u = (index % self.map_size) as f32 + offset.x;
+        let v = (index / self.map_size) as f32 + offset.y;
 
         let uv = Vec2::new(u, v) / self.map_size as f32;
 
-        decode_octahedron_normal(uv)
+        self.parameterization.decode(uv)
+    }
+
+    /// Returns the solid angle in steradians covered by the value at the given index.
+    ///
+    /// # Arguments
+    /// * `index` - The index of the pixel contribution value.
+    pub fn solid_angle_from_index(&self, index: usize) -> f32 {
+        self.parameterization.texel_solid_angle(
+            self.map_size,
+            index % self.map_size,
+            index / self.map_size,
+        )
     }
 
     /// Returns the index for the given camera direction vector.
@@ -79,19 +288,89 @@ impl PixelContribColorMapDescriptor {
     /// # Arguments
     /// * `dir` - The camera direction vector to the object.
     pub fn index_from_camera_dir(&self, dir: Vec3) -> usize {
-        let uv = encode_octahedron_normal(dir) * self.map_size as f32 - Vec2::new(0.5, 0.5);
+        self.parameterization.index_from_dir(self.map_size, dir)
+    }
+
+    /// Returns the continuous texel position for the given camera direction vector, where
+    /// integer positions are located at the texel centers.
+    ///
+    /// # Arguments
+    /// * `dir` - The camera direction vector to the object.
+    pub fn texel_from_camera_dir(&self, dir: Vec3) -> Vec2 {
+        self.parameterization.encode(dir) * self.map_size as f32 - Vec2::new(0.5, 0.5)
+    }
 
-        let u = clamp(uv.x.round() as usize, 0, self.map_size - 1);
-        let v = clamp(uv.y.round() as usize, 0, self.map_size - 1);
+    /// Returns the index for the given texel position in the neighborhood of the given anchor.
+    /// The position may lie outside of the map or beyond a seam of the parameterization, in
+    /// which case it is wrapped onto the adjacent texel on the sphere. For the octahedral
+    /// projection, crossing an edge mirrors the position along that edge and the corners are
+    /// thereby mapped onto the diagonally opposite corner.
+    ///
+    /// # Arguments
+    /// * `anchor` - The continuous texel position the neighborhood is located around.
+    /// * `x` - The horizontal texel position.
+    /// * `y` - The vertical texel position.
+    pub fn neighbor_index(&self, anchor: Vec2, x: isize, y: isize) -> usize {
+        self.parameterization
+            .neighbor_index(self.map_size, anchor, x, y)
+    }
+
+    /// Writes the descriptor to the given writer.
+    ///
+    /// # Arguments
+    /// * `writer` - The writer to which the descriptor should be written.
+    fn write_writer<W: Write>(&self, writer: &mut W) -> Result<()> {
+        writer.write_u32::<byteorder::LittleEndian>(self.map_size as u32)?;
+        writer.write_f32::<byteorder::LittleEndian>(self.camera_angle)?;
+        writer.write_u8(self.parameterization.to_id())?;
+        self.frustum.write_writer(writer)?;
+        writer.write_f32::<byteorder::LittleEndian>(self.distance)?;
 
-        v * self.map_size + u
+        Ok(())
+    }
+
+    /// Reads the descriptor from the given reader.
+    ///
+    /// # Arguments
+    /// * `reader` - The reader from which the descriptor should be read.
+    /// * `version` - The version of the file format.
+    fn from_reader<R: Read>(reader: &mut R, version: u32) -> Result<Self> {
+        let map_size = reader.read_u32::<byteorder::LittleEndian>()? as usize;
+        let angle = reader.read_f32::<byteorder::LittleEndian>()?;
+
+        // Version 1 only stores the size and the camera angle
+        if version < 2 {
+            return Ok(Self::new(map_size, angle));
+        }
+
+        let parameterization = SphereParameterization::from_id(reader.read_u8()?)?;
+        let frustum = CameraFrustum::from_reader(reader)?;
+        let distance = reader.read_f32::<byteorder::LittleEndian>()?;
+
+        Ok(
+            Self::new_with_parameterization(map_size, angle, parameterization)
+                .with_frustum(frustum)
+                .with_distance(distance),
+        )
     }
 }
 
 /// The pixel contribution maps for different configurations
-#[derive(Clone, PartialEq)]
+#[derive(Clone)]
 pub struct PixelContributionMaps {
     pub maps: Vec<PixelContributionMap>,
+
+    /// Additional information about how the maps have been computed.
+    pub metadata: PixelContributionMetadata,
+
+    /// The interpolator used for camera angles between the angles of the maps.
+    interpolator: Arc<dyn AngleInterpolator>,
+}
+
+impl PartialEq for PixelContributionMaps {
+    fn eq(&self, other: &Self) -> bool {
+        self.maps == other.maps && self.metadata == other.metadata
+    }
 }
 
 impl Default for PixelContributionMaps {
@@ -102,7 +381,11 @@ impl Default for PixelContributionMaps {
 
 impl PixelContributionMaps {
     pub fn new() -> Self {
-        Self { maps: Vec::new() }
+        Self {
+            maps: Vec::new(),
+            metadata: PixelContributionMetadata::new(),
+            interpolator: Arc::new(TangentAngleInterpolator),
+        }
     }
 
     /// Creates a new pixel contribution maps object from the given maps.
@@ -112,7 +395,11 @@ impl PixelContributionMaps {
     pub fn from_maps(mut maps: Vec<PixelContributionMap>) -> Self {
         Self::sort_maps(&mut maps);
 
-        Self { maps }
+        Self {
+            maps,
+            metadata: PixelContributionMetadata::new(),
+            interpolator: Arc::new(TangentAngleInterpolator),
+        }
     }
 
     /// Adds a new pixel contribution map.
@@ -129,59 +416,258 @@ impl PixelContributionMaps {
         &self.maps
     }
 
+    /// Returns a reference to the metadata of the pixel contribution maps.
+    #[inline]
+    pub fn metadata(&self) -> &PixelContributionMetadata {
+        &self.metadata
+    }
+
+    /// Returns a mutable reference to the metadata of the pixel contribution maps.
+    #[inline]
+    pub fn metadata_mut(&mut self) -> &mut PixelContributionMetadata {
+        &mut self.metadata
+    }
+
+    /// Returns the interpolator used for camera angles between the angles of the maps.
+    #[inline]
+    pub fn angle_interpolator(&self) -> &dyn AngleInterpolator {
+        self.interpolator.as_ref()
+    }
+
+    /// Sets the interpolator used for camera angles between the angles of the maps.
+    /// By default, the [`TangentAngleInterpolator`] is used.
+    ///
+    /// # Arguments
+    /// * `interpolator` - The new angle interpolator.
+    pub fn set_angle_interpolator<I: AngleInterpolator + 'static>(&mut self, interpolator: I) {
+        self.interpolator = Arc::new(interpolator);
+    }
+
     /// Returns the pixel contribution for the given camera direction vector.
     ///
     /// # Arguments
     /// * `dir` - The camera direction vector to the object.
     /// * `angle` - The angle of the camera to return the contribution values for.
     pub fn get_pixel_contrib_for_camera_dir(&self, dir: Vec3, angle: f32) -> f32 {
-        let (i0, i1) = self.search_starting_map_for_angle(angle);
+        self.sample_pixel_contrib_for_camera_dir(dir, angle, SamplingMode::Nearest)
+    }
+
+    /// Returns the pixel contribution for the given camera direction vector using the given
+    /// filter for the lookup in the maps. The camera is assumed to have a quadratic symmetric
+    /// frustum with the given angle as field of view, which fits the bounding sphere, see
+    /// [`Self::sample_pixel_contrib_for_camera`].
+    ///
+    /// # Arguments
+    /// * `dir` - The camera direction vector to the object.
+    /// * `angle` - The angle of the camera to return the contribution values for.
+    /// * `mode` - The filter used for the lookup in the maps.
+    pub fn sample_pixel_contrib_for_camera_dir(
+        &self,
+        dir: Vec3,
+        angle: f32,
+        mode: SamplingMode,
+    ) -> f32 {
+        self.sample_pixel_contrib_for_camera(dir, &CameraFrustum::square(angle), angle, mode)
+    }
+
+    /// Returns the pixel contribution for the given camera direction vector seen by a camera
+    /// with the given frustum using the given filter for the lookup in the maps.
+    ///
+    /// Only the maps computed for the frustum shape closest to the given frustum are used, see
+    /// [`Self::frustum_groups`]. These are split into groups of the same camera distance, see
+    /// [`Self::distance_groups`]. Inside of each group, the values are interpolated w.r.t the
+    /// camera angle of the given frustum, see [`CameraFrustum::sphere_angle`]. The values of the
+    /// groups are then interpolated w.r.t the given sphere angle. Since each map computed at the
+    /// distance fitting its frustum forms a group of its own, these maps are interpolated w.r.t
+    /// the sphere angle alone.
+    ///
+    /// # Arguments
+    /// * `dir` - The camera direction vector to the object.
+    /// * `frustum` - The frustum of the camera.
+    /// * `sphere_angle` - The angle under which the bounding sphere is seen from the camera.
+    /// * `mode` - The filter used for the lookup in the maps.
+    pub fn sample_pixel_contrib_for_camera(
+        &self,
+        dir: Vec3,
+        frustum: &CameraFrustum,
+        sphere_angle: f32,
+        mode: SamplingMode,
+    ) -> f32 {
+        interpolation::interpolate_camera(
+            self.interpolator.as_ref(),
+            self.maps.len(),
+            &|i| self.maps[i].descriptor,
+            frustum,
+            sphere_angle,
+            &|i| self.maps[i].sample_pixel_contrib_for_camera_dir(dir, mode),
+        )
+    }
 
-        let map0 = &self.maps[i0];
-        let p0 = map0.get_pixel_contrib_for_camera_dir(dir);
-        if let Some(i1) = i1 {
-            let map1 = &self.maps[i1];
-            let p1 = map1.get_pixel_contrib_for_camera_dir(dir);
+    /// Returns the statistical summaries of all maps ordered by their camera angle, see
+    /// [`PixelContributionMap::statistics`].
+    pub fn statistics(&self) -> Vec<PixelContributionStatistics> {
+        self.maps.iter().map(|m| m.statistics()).collect()
+    }
 
-            let a0 = (map0.descriptor.camera_angle() / 2f32).tan();
-            let a1 = (map1.descriptor.camera_angle() / 2f32).tan();
-            let a = (angle / 2f32).tan();
+    /// Generates the mip chains of all maps, s.t. they can be queried with a level of detail.
+    /// The mip chains are stored together with the maps when being written.
+    pub fn generate_mip_chains(&mut self) {
+        self.maps.iter_mut().for_each(|m| m.generate_mip_chain());
+    }
 
-            let t = (a1 - a) / (a1 - a0);
+    /// Splits the maps into groups of maps computed for frusta of the same shape, see
+    /// [`CameraFrustum::has_same_shape`]. Each group keeps the metadata and the angle
+    /// interpolator and is queried by the camera angle as usual. The groups are ordered by the
+    /// first occurrence of their shape.
+    pub fn frustum_groups(&self) -> Vec<PixelContributionMaps> {
+        let mut groups: Vec<PixelContributionMaps> = Vec::new();
+        for map in self.maps.iter() {
+            let frustum = map.descriptor.frustum();
+            match groups
+                .iter_mut()
+                .find(|g| g.maps[0].descriptor.frustum().has_same_shape(&frustum))
+            {
+                Some(group) => group.maps.push(map.clone()),
+                None => groups.push(Self {
+                    maps: vec![map.clone()],
+                    metadata: self.metadata.clone(),
+                    interpolator: self.interpolator.clone(),
+                }),
+            }
+        }
 
-            p0 * t + p1 * (1.0 - t)
-        } else {
-            p0
+        groups
+    }
+
+    /// Splits the maps into groups of maps computed at the same distance between the camera and
+    /// the bounding sphere, see [`PixelContribColorMapDescriptor::distance`]. Each map computed
+    /// at the distance fitting its frustum forms a group of its own. Each group keeps the metadata
+    /// and the angle interpolator and is queried by the camera angle as usual. The groups are
+    /// ordered by ascending sphere angle, see [`PixelContribColorMapDescriptor::sphere_angle`],
+    /// i.e., by descending distance.
+    pub fn distance_groups(&self) -> Vec<PixelContributionMaps> {
+        let mut groups: Vec<PixelContributionMaps> = Vec::new();
+        for map in self.maps.iter() {
+            let distance = map.descriptor.distance();
+            match groups
+                .iter_mut()
+                .find(|g| distance > 0.0 && g.maps[0].descriptor.distance() == distance)
+            {
+                Some(group) => group.maps.push(map.clone()),
+                None => groups.push(Self {
+                    maps: vec![map.clone()],
+                    metadata: self.metadata.clone(),
+                    interpolator: self.interpolator.clone(),
+                }),
+            }
         }
+
+        groups.sort_by(|g1, g2| {
+            g1.maps[0]
+                .descriptor
+                .sphere_angle()
+                .total_cmp(&g2.maps[0].descriptor.sphere_angle())
+        });
+
+        groups
+    }
+
+    /// Returns the pixel contribution for the given camera direction vector at the given level
+    /// of detail, see [`PixelContributionMap::get_pixel_contrib_for_camera_dir_lod`].
+    ///
+    /// # Arguments
+    /// * `dir` - The camera direction vector to the object.
+    /// * `angle` - The angle of the camera to return the contribution values for.
+    /// * `lod` - The level of detail, where 0 is the base level.
+    pub fn get_pixel_contrib_for_camera_dir_lod(&self, dir: Vec3, angle: f32, lod: f32) -> f32 {
+        self.sample_pixel_contrib_for_camera_dir_lod(dir, angle, lod, SamplingMode::Nearest)
+    }
+
+    /// Returns the pixel contribution for the given camera direction vector at the given level
+    /// of detail using the given filter for the lookup in the mip levels. The camera is assumed
+    /// to have a quadratic symmetric frustum with the given angle as field of view, see
+    /// [`Self::sample_pixel_contrib_for_camera_dir`].
+    ///
+    /// # Arguments
+    /// * `dir` - The camera direction vector to the object.
+    /// * `angle` - The angle of the camera to return the contribution values for.
+    /// * `lod` - The level of detail, where 0 is the base level.
+    /// * `mode` - The filter used for the lookup in the mip levels.
+    pub fn sample_pixel_contrib_for_camera_dir_lod(
+        &self,
+        dir: Vec3,
+        angle: f32,
+        lod: f32,
+        mode: SamplingMode,
+    ) -> f32 {
+        self.sample_pixel_contrib_for_camera_lod(
+            dir,
+            &CameraFrustum::square(angle),
+            angle,
+            lod,
+            mode,
+        )
+    }
+
+    /// Returns the pixel contribution for the given camera direction vector seen by a camera
+    /// with the given frustum at the given level of detail using the given filter for the lookup
+    /// in the mip levels. See [`Self::sample_pixel_contrib_for_camera`] for the selection and
+    /// interpolation of the maps.
+    ///
+    /// # Arguments
+    /// * `dir` - The camera direction vector to the object.
+    /// * `frustum` - The frustum of the camera.
+    /// * `sphere_angle` - The angle under which the bounding sphere is seen from the camera.
+    /// * `lod` - The level of detail, where 0 is the base level.
+    /// * `mode` - The filter used for the lookup in the mip levels.
+    pub fn sample_pixel_contrib_for_camera_lod(
+        &self,
+        dir: Vec3,
+        frustum: &CameraFrustum,
+        sphere_angle: f32,
+        lod: f32,
+        mode: SamplingMode,
+    ) -> f32 {
+        interpolation::interpolate_camera(
+            self.interpolator.as_ref(),
+            self.maps.len(),
+            &|i| self.maps[i].descriptor,
+            frustum,
+            sphere_angle,
+            &|i| self.maps[i].sample_pixel_contrib_for_camera_dir_lod(dir, lod, mode),
+        )
     }
 
     /// Writes the pixel contribution map to the given writer as binary file.
+    /// The values are stored as uncompressed 32-bit floats.
     ///
     /// # Arguments
     /// * `writer` - The writer to which the pixel contribution should be written.
     pub fn write_writer<W: Write>(&self, writer: &mut W) -> Result<()> {
-        let header: PixelContributionMapHeader = Default::default();
+        self.write_writer_with_encoding(writer, MapEncoding::default())
+    }
 
-        // Write the header
-        writer.write_all(header.identifier.as_ref())?;
-        writer.write_u32::<byteorder::LittleEndian>(header.version)?;
+    /// Writes the pixel contribution map to the given writer as binary file using the given
+    /// encoding for the values. See [`ValueEncoding::error_bound`] for the loss of precision.
+    ///
+    /// # Arguments
+    /// * `writer` - The writer to which the pixel contribution should be written.
+    /// * `encoding` - The encoding and compression used for storing the values.
+    pub fn write_writer_with_encoding<W: Write>(
+        &self,
+        writer: &mut W,
+        encoding: MapEncoding,
+    ) -> Result<()> {
+        // Write the header and the metadata
+        PixelContributionMapHeader::default().write_writer(writer)?;
+        self.metadata.write_writer(writer)?;
 
         // Write the number of pixel contribution maps
         writer.write_u32::<byteorder::LittleEndian>(self.maps.len() as u32)?;
 
-        // Write the pixel contribution maps
+        // Write the pixel contribution maps, each followed by its checksum
         for map in &self.maps {
-            // Write the descriptor
-            let map_size = map.descriptor.size() as u32;
-            let angle = map.descriptor.camera_angle();
-
-            writer.write_u32::<byteorder::LittleEndian>(map_size)?;
-            writer.write_f32::<byteorder::LittleEndian>(angle)?;
-
-            // Write the pixel contribution
-            for x in &map.pixel_contrib {
-                writer.write_f32::<byteorder::LittleEndian>(*x)?;
-            }
+            map.write_record(writer, encoding)?;
         }
 
         Ok(())
@@ -192,89 +678,151 @@ impl PixelContributionMaps {
     /// # Arguments
     /// * `path` - The path from which the pixel contribution should be read.
     pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::from_file_with_options(path, &ReadOptions::default())
+    }
+
+    /// Reads the pixel contribution map from the given path using the given limits and checks.
+    ///
+    /// # Arguments
+    /// * `path` - The path from which the pixel contribution should be read.
+    /// * `options` - The limits and checks applied when reading.
+    pub fn from_file_with_options<P: AsRef<Path>>(path: P, options: &ReadOptions) -> Result<Self> {
         let file = std::fs::File::open(path)?;
-        Self::from_reader(&mut BufReader::new(file))
+        Self::from_reader_with_options(&mut BufReader::new(file), options)
     }
 
-    /// Reads the pixel contribution map from the given reader.
+    /// Reads the pixel contribution map from the given reader using the default limits, see
+    /// [`ReadOptions`]. Files of all previous versions are supported as well.
     ///
     /// # Arguments
     /// * `reader` - The reader from which the pixel contribution should be read.
     pub fn from_reader<R: Read>(reader: &mut R) -> Result<Self> {
+        Self::from_reader_with_options(reader, &ReadOptions::default())
+    }
+
+    /// Reads the pixel contribution map from the given reader using the given limits and checks.
+    /// Files of all previous versions are supported as well. The camera angles of the maps must
+    /// be finite, non-negative and unique.
+    ///
+    /// # Arguments
+    /// * `reader` - The reader from which the pixel contribution should be read.
+    /// * `options` - The limits and checks applied when reading.
+    pub fn from_reader_with_options<R: Read>(
+        reader: &mut R,
+        options: &ReadOptions,
+    ) -> Result<Self> {
         // Read the header and check if it is valid
         let mut header = PixelContributionMapHeader::default();
         reader.read_exact(header.identifier.as_mut())?;
         header.version = reader.read_u32::<byteorder::LittleEndian>()?;
         header.check()?;
 
+        // Read the metadata, which is not available in version 1
+        let metadata = if header.version >= 2 {
+            PixelContributionMetadata::from_reader(reader)?
+        } else {
+            PixelContributionMetadata::new()
+        };
+
         // Read the number of pixel contribution maps
         let num_maps = reader.read_u32::<byteorder::LittleEndian>()? as usize;
+        options.check_num_maps(num_maps)?;
 
         // Read the pixel contribution maps
         let mut maps = Vec::with_capacity(num_maps);
-        for _ in 0..num_maps {
-            // Read the descriptor
-            let map_size = reader.read_u32::<byteorder::LittleEndian>()? as usize;
-            let angle = reader.read_f32::<byteorder::LittleEndian>()?;
-
-            let descriptor = PixelContribColorMapDescriptor::new(map_size, angle);
+        for i in 0..num_maps {
+            let map = if header.version >= 2 {
+                let mut checksum_reader = ChecksumReader::new(reader);
+                let map = PixelContributionMap::from_reader(
+                    &mut checksum_reader,
+                    header.version,
+                    options,
+                )?;
+                let checksum = checksum_reader.checksum();
+
+                if reader.read_u32::<byteorder::LittleEndian>()? != checksum {
+                    return Err(Error::ChecksumMismatch(i));
+                }
 
-            // Read the pixel contribution
-            let mut pixel_contrib = Vec::with_capacity(map_size * map_size);
-            for _ in 0..map_size * map_size {
-                pixel_contrib.push(reader.read_f32::<byteorder::LittleEndian>()?);
-            }
+                map
+            } else {
+                PixelContributionMap::from_reader(reader, header.version, options)?
+            };
 
-            maps.push(PixelContributionMap {
-                descriptor,
-                pixel_contrib,
-            });
+            options.check_values(i, map.pixel_contrib.iter().copied())?;
+            maps.push(map);
         }
 
         Self::sort_maps(&mut maps);
+        validation::check_unique_angles(maps.iter().map(|m| &m.descriptor))?;
 
-        Ok(Self { maps })
+        Ok(Self {
+            maps,
+            metadata,
+            interpolator: Arc::new(TangentAngleInterpolator),
+        })
     }
 
     /// Helper function used to ensure that the maps are always sorted in ascending order w.r.t
-    /// their camera angles.
+    /// their camera angles and, for equal angles, their camera distances.
     ///
     /// # Arguments
     /// * `maps` - The maps to sort.
     fn sort_maps(maps: &mut [PixelContributionMap]) {
-        maps.sort_by(|m1, m2| {
-            m1.descriptor
-                .camera_angle()
-                .partial_cmp(&m2.descriptor.camera_angle())
-                .unwrap()
-        });
+        maps.sort_by(|m1, m2| m1.descriptor.cmp_camera(&m2.descriptor));
     }
+}
 
-    /// Searches for the pair of maps where the given angle is between their camera angles.
-    /// If the angle os out of range, only one angle is being returned.
-    ///
-    /// # Arguments
-    /// * `angle` - The given angle to search the pair of maps for.
-    fn search_starting_map_for_angle(&self, angle: f32) -> (usize, Option<usize>) {
-        for (i, m) in self.maps.iter().enumerate() {
-            let cur_angle = m.descriptor.camera_angle();
-
-            // If the current angle is already too large, then it must be the first element and we
-            // stop.
-            // If we've reached the end, we also stop here
-            if cur_angle > angle || i + 1 >= self.maps.len() {
-                return (i, None);
-            }
-
-            // get the next angle and check if it is larger or equal
-            let next_angle = self.maps[i + 1].descriptor.camera_angle();
-            if next_angle >= angle {
-                return (i, Some(i + 1));
-            }
+/// Searches for the pair of indices where the given angle is between the camera angles at these
+/// indices. The camera angles must be sorted in ascending order.
+/// If the angle is out of range, only one index is being returned.
+///
+/// # Arguments
+/// * `num` - The number of camera angles.
+/// * `angle` - The given angle to search the pair of indices for.
+/// * `angle_at` - Returns the camera angle for the given index.
+fn search_angle_range<F>(num: usize, angle: f32, angle_at: F) -> (usize, Option<usize>)
+where
+    F: Fn(usize) -> f32,
+{
+    for i in 0..num {
+        let cur_angle = angle_at(i);
+
+        // If the current angle is already too large, then it must be the first element and we
+        // stop.
+        // If we've reached the end, we also stop here
+        if cur_angle > angle || i + 1 >= num {
+            return (i, None);
         }
 
-        (self.maps.len() - 1, None)
+        // get the next angle and check if it is larger or equal
+        let next_angle = angle_at(i + 1);
+        if next_angle >= angle {
+            return (i, Some(i + 1));
+        }
     }
+
+    (num - 1, None)
+}
+
+/// Interpolates between two pixel contribution values w.r.t the tangent of their half camera
+/// angles.
+///
+/// # Arguments
+/// * `angle` - The camera angle to interpolate the pixel contribution for.
+/// * `angle0` - The camera angle of the first pixel contribution value.
+/// * `p0` - The first pixel contribution value.
+/// * `angle1` - The camera angle of the second pixel contribution value.
+/// * `p1` - The second pixel contribution value.
+#[inline]
+fn interpolate_angle(angle: f32, angle0: f32, p0: f32, angle1: f32, p1: f32) -> f32 {
+    let a0 = (angle0 / 2f32).tan();
+    let a1 = (angle1 / 2f32).tan();
+    let a = (angle / 2f32).tan();
+
+    let t = (a1 - a) / (a1 - a0);
+
+    p0 * t + p1 * (1.0 - t)
 }
 
 /// The resulting pixel contribution for all possible views.
@@ -287,6 +835,10 @@ pub struct PixelContributionMap {
     /// projection. The normal then defines the camera view direction onto the object.
     /// The pixel contribution values are in the range [0, 1].
     pub pixel_contrib: Vec<f32>,
+
+    /// The coarser mip levels of the map, where each level has half the size of the previous
+    /// one down to a single texel. Empty, if no mip chain has been generated.
+    pub mip_levels: Vec<PixelContributionMap>,
 }
 
 impl PixelContributionMap {
@@ -298,6 +850,7 @@ impl PixelContributionMap {
         Self {
             descriptor,
             pixel_contrib: vec![0.0; descriptor.num_values()],
+            mip_levels: Vec::new(),
         }
     }
 
@@ -309,6 +862,278 @@ impl PixelContributionMap {
         let index = self.descriptor.index_from_camera_dir(dir);
         self.pixel_contrib[index]
     }
+
+    /// Returns the pixel contribution for the given camera direction vector using the given
+    /// filter.
+    ///
+    /// # Arguments
+    /// * `dir` - The camera direction vector to the object.
+    /// * `mode` - The filter used for the lookup.
+    pub fn sample_pixel_contrib_for_camera_dir(&self, dir: Vec3, mode: SamplingMode) -> f32 {
+        mode.sample(&self.descriptor, dir, |i| self.pixel_contrib[i])
+    }
+
+    /// Returns the statistical summary of the values, where each value is weighted by the solid
+    /// angle it covers.
+    pub fn statistics(&self) -> PixelContributionStatistics {
+        PixelContributionStatistics::from_map(self)
+    }
+
+    /// Returns the solid-angle-weighted percentile of the values, i.e., the smallest value s.t.
+    /// the given percentage of the sphere of camera directions has a pixel contribution less than
+    /// or equal to it.
+    ///
+    /// # Arguments
+    /// * `percentile` - The percentile in the range [0, 100]. Values outside are clamped.
+    pub fn percentile(&self, percentile: f32) -> f32 {
+        statistics::weighted_percentile(self, percentile)
+    }
+
+    /// Generates the mip chain of the map, replacing any previously generated one.
+    /// See [`Self::mip_levels`] for the layout of the chain.
+    pub fn generate_mip_chain(&mut self) {
+        self.mip_levels = mipmap::generate_mip_chain(self);
+    }
+
+    /// Returns the number of mip levels including the base level.
+    #[inline]
+    pub fn num_mip_levels(&self) -> usize {
+        self.mip_levels.len() + 1
+    }
+
+    /// Returns the mip level with the given index, where 0 is the map itself. The index is
+    /// clamped to the coarsest available level.
+    ///
+    /// # Arguments
+    /// * `level` - The index of the mip level.
+    pub fn mip_level(&self, level: usize) -> &PixelContributionMap {
+        match level.min(self.mip_levels.len()) {
+            0 => self,
+            level => &self.mip_levels[level - 1],
+        }
+    }
+
+    /// Returns the level of detail whose texels cover the given solid angle, i.e., the
+    /// footprint of a lookup. The result is 0 for footprints up to the size of a texel of the
+    /// base level.
+    ///
+    /// # Arguments
+    /// * `solid_angle` - The solid angle of the footprint in steradians.
+    pub fn lod_from_footprint(&self, solid_angle: f32) -> f32 {
+        let sphere = match self.descriptor.parameterization() {
+            SphereParameterization::HemiOctahedral => 2.0 * std::f32::consts::PI,
+            _ => 4.0 * std::f32::consts::PI,
+        };
+        let texel_solid_angle = sphere / self.descriptor.num_values() as f32;
+
+        // each level quadruples the solid angle covered by a texel
+        (0.5 * (solid_angle / texel_solid_angle).log2()).max(0.0)
+    }
+
+    /// Returns the pixel contribution for the given camera direction vector at the given level
+    /// of detail. Fractional levels are linearly interpolated between the adjacent mip levels.
+    /// If no mip chain has been generated, the base level is used.
+    ///
+    /// # Arguments
+    /// * `dir` - The camera direction vector to the object.
+    /// * `lod` - The level of detail, where 0 is the base level.
+    pub fn get_pixel_contrib_for_camera_dir_lod(&self, dir: Vec3, lod: f32) -> f32 {
+        self.sample_pixel_contrib_for_camera_dir_lod(dir, lod, SamplingMode::Nearest)
+    }
+
+    /// Returns the pixel contribution for the given camera direction vector at the given level
+    /// of detail using the given filter for the lookup in the mip levels.
+    ///
+    /// # Arguments
+    /// * `dir` - The camera direction vector to the object.
+    /// * `lod` - The level of detail, where 0 is the base level.
+    /// * `mode` - The filter used for the lookup in the mip levels.
+    pub fn sample_pixel_contrib_for_camera_dir_lod(
+        &self,
+        dir: Vec3,
+        lod: f32,
+        mode: SamplingMode,
+    ) -> f32 {
+        let lod = lod.clamp(0.0, self.mip_levels.len() as f32);
+        let level = lod.floor() as usize;
+        let t = lod - level as f32;
+
+        let p0 = self
+            .mip_level(level)
+            .sample_pixel_contrib_for_camera_dir(dir, mode);
+        if t > 0.0 {
+            let p1 = self
+                .mip_level(level + 1)
+                .sample_pixel_contrib_for_camera_dir(dir, mode);
+
+            p0 * (1.0 - t) + p1 * t
+        } else {
+            p0
+        }
+    }
+
+    /// Writes the record of the pixel contribution map, i.e., the descriptor and the encoded
+    /// values followed by the checksum of both, to the given writer.
+    ///
+    /// # Arguments
+    /// * `writer` - The writer to which the pixel contribution map should be written.
+    /// * `encoding` - The encoding and compression used for storing the values.
+    fn write_record<W: Write>(&self, writer: &mut W, encoding: MapEncoding) -> Result<()> {
+        let mut checksum_writer = ChecksumWriter::new(writer);
+        self.write_writer(&mut checksum_writer, encoding)?;
+
+        let checksum = checksum_writer.checksum();
+        writer.write_u32::<byteorder::LittleEndian>(checksum)?;
+
+        Ok(())
+    }
+
+    /// Writes the descriptor and the encoded values of the pixel contribution map to the given
+    /// writer.
+    ///
+    /// # Arguments
+    /// * `writer` - The writer to which the pixel contribution map should be written.
+    /// * `encoding` - The encoding and compression used for storing the values.
+    fn write_writer<W: Write>(&self, writer: &mut W, encoding: MapEncoding) -> Result<()> {
+        self.descriptor.write_writer(writer)?;
+
+        // Write the encoding followed by the values
+        writer.write_u8(encoding.values.to_id())?;
+        writer.write_u8(encoding.compression.to_id())?;
+        write_encoded_values(writer, &self.pixel_contrib, encoding)?;
+
+        // Write the mip chain using the same encoding
+        writer.write_u8(self.mip_levels.len() as u8)?;
+        for level in &self.mip_levels {
+            write_encoded_values(writer, &level.pixel_contrib, encoding)?;
+        }
+
+        Ok(())
+    }
+
+    /// Reads the descriptor and the values of a pixel contribution map from the given reader.
+    ///
+    /// # Arguments
+    /// * `reader` - The reader from which the pixel contribution map should be read.
+    /// * `version` - The version of the file format.
+    /// * `options` - The limits and checks applied when reading.
+    fn from_reader<R: Read>(reader: &mut R, version: u32, options: &ReadOptions) -> Result<Self> {
+        let descriptor = PixelContribColorMapDescriptor::from_reader(reader, version)?;
+        options.check_descriptor(&descriptor)?;
+        let map_size = descriptor.size();
+
+        // Version 1 stores the values as raw floats without a mip chain
+        if version < 2 {
+            return Ok(Self {
+                descriptor,
+                pixel_contrib: read_raw_values(reader, descriptor.num_values())?,
+                mip_levels: Vec::new(),
+            });
+        }
+
+        // Read the encoding followed by the values
+        let values = ValueEncoding::from_id(reader.read_u8()?)?;
+        let compression = Compression::from_id(reader.read_u8()?)?;
+        let encoding = MapEncoding::new(values, compression);
+        let pixel_contrib = read_encoded_values(reader, encoding, descriptor.num_values())?;
+
+        // Read the mip chain
+        let num_levels = reader.read_u8()? as usize;
+        if num_levels > mipmap::mip_sizes(map_size).count() {
+            return Err(Error::InvalidMipLevels(num_levels, map_size));
+        }
+
+        let mut mip_levels = Vec::new();
+        for size in mipmap::mip_sizes(map_size).take(num_levels) {
+            let mut level = PixelContributionMap::new(descriptor.with_size(size));
+            level.pixel_contrib = read_encoded_values(reader, encoding, size * size)?;
+
+            mip_levels.push(level);
+        }
+
+        Ok(Self {
+            descriptor,
+            pixel_contrib,
+            mip_levels,
+        })
+    }
+}
+
+/// Reads the given number of raw little-endian floats as stored in version 1. The values are
+/// read before allocating them, s.t. a corrupt map size cannot request more memory than the
+/// input provides.
+///
+/// # Arguments
+/// * `reader` - The reader from which the values should be read.
+/// * `num_values` - The number of values to read.
+fn read_raw_values<R: Read>(reader: &mut R, num_values: usize) -> Result<Vec<f32>> {
+    let len = (num_values as u64).saturating_mul(4);
+    let mut data = Vec::new();
+    reader.take(len).read_to_end(&mut data)?;
+    if data.len() as u64 != len {
+        return Err(Error::IO("Unexpected end of map data".to_string()));
+    }
+
+    Ok(data
+        .chunks_exact(4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .collect())
+}
+
+/// Writes the value range used for quantization followed by the encoded values.
+///
+/// # Arguments
+/// * `writer` - The writer to which the values should be written.
+/// * `values` - The values to write.
+/// * `encoding` - The encoding and compression used for storing the values.
+fn write_encoded_values<W: Write>(
+    writer: &mut W,
+    values: &[f32],
+    encoding: MapEncoding,
+) -> Result<()> {
+    let (min, max) = values.iter().fold((f32::MAX, f32::MIN), |(min, max), v| {
+        (min.min(*v), max.max(*v))
+    });
+    let (min, max) = if min <= max { (min, max) } else { (0f32, 0f32) };
+
+    writer.write_f32::<byteorder::LittleEndian>(min)?;
+    writer.write_f32::<byteorder::LittleEndian>(max)?;
+
+    let data = encoding.values.encode(values, min, max);
+    let data = encoding.compression.compress(data)?;
+
+    writer.write_u32::<byteorder::LittleEndian>(data.len() as u32)?;
+    writer.write_all(&data)?;
+
+    Ok(())
+}
+
+/// Reads the value range used for quantization followed by the encoded values.
+///
+/// # Arguments
+/// * `reader` - The reader from which the values should be read.
+/// * `encoding` - The encoding and compression of the values.
+/// * `num_values` - The number of values to read.
+fn read_encoded_values<R: Read>(
+    reader: &mut R,
+    encoding: MapEncoding,
+    num_values: usize,
+) -> Result<Vec<f32>> {
+    let min = reader.read_f32::<byteorder::LittleEndian>()?;
+    let max = reader.read_f32::<byteorder::LittleEndian>()?;
+
+    let len = reader.read_u32::<byteorder::LittleEndian>()? as u64;
+    let mut data = Vec::new();
+    reader.take(len).read_to_end(&mut data)?;
+    if data.len() as u64 != len {
+        return Err(Error::IO("Unexpected end of map data".to_string()));
+    }
+
+    let data = encoding
+        .compression
+        .decompress(data, encoding.values.encoded_size(num_values))?;
+
+    encoding.values.decode(&data, num_values, min, max)
 }
 
 /// The header for the pixel contribution map used for serialization.
@@ -331,17 +1156,28 @@ impl Default for PixelContributionMapHeader {
 }
 
 impl PixelContributionMapHeader {
+    /// Writes the identifier and the version to the given writer.
+    ///
+    /// # Arguments
+    /// * `writer` - The writer to which the header should be written.
+    pub fn write_writer<W: Write>(&self, writer: &mut W) -> Result<()> {
+        writer.write_all(self.identifier.as_ref())?;
+        writer.write_u32::<byteorder::LittleEndian>(self.version)?;
+
+        Ok(())
+    }
+
     /// Checks if the given header is valid.
     ///
     /// # Arguments
     /// * `header` - The header to check.
     pub fn check(&self) -> Result<()> {
         if self.identifier != PIXEL_CONTRIBUTION_MAP_IDENTIFIER {
-            return Err(Error::IO("Invalid identifier".to_string()));
+            return Err(Error::InvalidIdentifier);
         }
 
-        if self.version != PIXEL_CONTRIBUTION_MAP_VERSION {
-            return Err(Error::IO("Invalid version".to_string()));
+        if self.version == 0 || self.version > PIXEL_CONTRIBUTION_MAP_VERSION {
+            return Err(Error::UnsupportedVersion(self.version));
         }
 
         Ok(())
@@ -368,9 +1204,7 @@ mod test {
                 *p = i as f32 / 255.0;
             });
 
-        let pixel_contribs = PixelContributionMaps {
-            maps: vec![pixel_contrib.clone()],
-        };
+        let pixel_contribs = PixelContributionMaps::from_maps(vec![pixel_contrib.clone()]);
 
         let mut buf = Vec::new();
         pixel_contribs.write_writer(&mut buf).unwrap();
@@ -383,12 +1217,573 @@ mod test {
         assert_eq!(pixel_contrib.pixel_contrib, pixel_contrib2.pixel_contrib);
     }
 
+    #[test]
+    fn test_serialization_with_frustum() {
+        let frustum = CameraFrustum::off_axis(
+            20f32.to_radians(),
+            45f32.to_radians(),
+            15f32.to_radians(),
+            25f32.to_radians(),
+        );
+        let descriptor = PixelContribColorMapDescriptor::new_with_frustum(
+            8,
+            frustum,
+            SphereParameterization::Octahedral,
+        );
+        assert_eq!(descriptor.camera_angle(), frustum.sphere_angle());
+
+        let mut map = PixelContributionMap::new(descriptor);
+        map.pixel_contrib
+            .iter_mut()
+            .enumerate()
+            .for_each(|(i, p)| *p = i as f32 / 64.0);
+        map.generate_mip_chain();
+        assert!(map
+            .mip_levels
+            .iter()
+            .all(|m| m.descriptor.frustum() == frustum));
+
+        let maps = PixelContributionMaps::from_maps(vec![
+            map,
+            PixelContributionMap::new(PixelContribColorMapDescriptor::new(8, 0.0)),
+        ]);
+
+        let mut buf = Vec::new();
+        maps.write_writer(&mut buf).unwrap();
+        let maps2 = PixelContributionMaps::from_reader(&mut buf.as_slice()).unwrap();
+        assert!(maps == maps2);
+
+        let frustums: Vec<_> = maps2
+            .get_maps()
+            .iter()
+            .map(|m| m.descriptor.frustum())
+            .collect();
+        assert!(frustums.contains(&frustum));
+        assert!(frustums.contains(&CameraFrustum::orthographic(1.0)));
+
+        // an invalid frustum is rejected when reading
+        let invalid = PixelContributionMaps::from_maps(vec![PixelContributionMap::new(
+            PixelContribColorMapDescriptor::new_with_frustum(
+                8,
+                CameraFrustum::off_axis(0.3, -0.5, 0.2, 0.2),
+                SphereParameterization::Octahedral,
+            ),
+        )]);
+        let mut buf = Vec::new();
+        invalid.write_writer(&mut buf).unwrap();
+        assert!(matches!(
+            PixelContributionMaps::from_reader(&mut buf.as_slice()),
+            Err(Error::InvalidFrustum)
+        ));
+    }
+
+    #[test]
+    fn test_frustum_groups() {
+        let square = |angle: f32| CameraFrustum::perspective(angle, 1.0);
+        let wide = |angle: f32| CameraFrustum::perspective(angle, 16.0 / 9.0);
+
+        // the wide frustum has the same camera angle as the square one with the same fovy
+        let maps = PixelContributionMaps::from_maps(
+            [square(0.5), wide(0.5), square(1.0), wide(1.0), wide(1.5)]
+                .into_iter()
+                .map(|f| {
+                    PixelContributionMap::new(PixelContribColorMapDescriptor::new_with_frustum(
+                        4,
+                        f,
+                        SphereParameterization::Octahedral,
+                    ))
+                })
+                .collect(),
+        );
+
+        let mut buf = Vec::new();
+        maps.write_writer(&mut buf).unwrap();
+        let maps = PixelContributionMaps::from_reader(&mut buf.as_slice()).unwrap();
+
+        let groups = maps.frustum_groups();
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups.iter().map(|g| g.maps.len()).sum::<usize>(), 5);
+
+        for group in groups.iter() {
+            let frustum = group.maps[0].descriptor.frustum();
+            assert!(group
+                .maps
+                .iter()
+                .all(|m| m.descriptor.frustum().has_same_shape(&frustum)));
+            assert!(group
+                .maps
+                .windows(2)
+                .all(|m| m[0].descriptor.camera_angle() < m[1].descriptor.camera_angle()));
+        }
+    }
+
+    #[test]
+    fn test_distance_groups() {
+        let fitted = |angle: f32| PixelContribColorMapDescriptor::new(4, angle);
+        let near = |angle: f32, distance: f32| fitted(angle).with_distance(distance);
+
+        let maps = PixelContributionMaps::from_maps(
+            [
+                fitted(0.5),
+                near(1.0, 1.5),
+                fitted(1.0),
+                near(0.5, 1.5),
+                near(1.0, 1.2),
+                near(0.5, 1.2),
+            ]
+            .into_iter()
+            .map(PixelContributionMap::new)
+            .collect(),
+        );
+
+        let mut buf = Vec::new();
+        maps.write_writer(&mut buf).unwrap();
+        let maps2 = PixelContributionMaps::from_reader(&mut buf.as_slice()).unwrap();
+        assert!(maps == maps2);
+
+        // the fitted maps form a group each and the groups are ordered by their sphere angles
+        let groups = maps2.distance_groups();
+        let distances: Vec<f32> = groups
+            .iter()
+            .map(|g| g.maps[0].descriptor.distance())
+            .collect();
+        assert_eq!(distances, vec![0.0, 0.0, 1.5, 1.2]);
+        assert_eq!(groups[0].maps[0].descriptor.sphere_angle(), 0.5);
+        assert!(groups.windows(2).all(
+            |g| g[0].maps[0].descriptor.sphere_angle() < g[1].maps[0].descriptor.sphere_angle()
+        ));
+        for group in groups.iter().skip(2) {
+            assert_eq!(group.maps.len(), 2);
+            assert!(
+                group.maps[0].descriptor.camera_angle() < group.maps[1].descriptor.camera_angle()
+            );
+        }
+
+        // the same camera angle and distance must not occur twice
+        let duplicate = PixelContributionMaps::from_maps(vec![
+            PixelContributionMap::new(near(0.5, 1.5)),
+            PixelContributionMap::new(near(0.5, 1.5)),
+        ]);
+        let mut buf = Vec::new();
+        duplicate.write_writer(&mut buf).unwrap();
+        assert!(matches!(
+            PixelContributionMaps::from_reader(&mut buf.as_slice()),
+            Err(Error::DuplicateCameraAngle(_))
+        ));
+
+        // the camera must be outside of the sphere
+        let invalid =
+            PixelContributionMaps::from_maps(vec![PixelContributionMap::new(near(0.5, 0.8))]);
+        let mut buf = Vec::new();
+        invalid.write_writer(&mut buf).unwrap();
+        assert!(matches!(
+            PixelContributionMaps::from_reader(&mut buf.as_slice()),
+            Err(Error::InvalidCameraDistance(_))
+        ));
+    }
+
+    #[test]
+    fn test_lookup_multiple_distances() {
+        let map = |descriptor: PixelContribColorMapDescriptor, value: f32| {
+            let mut map = PixelContributionMap::new(descriptor);
+            map.pixel_contrib.iter_mut().for_each(|p| *p = value);
+            map
+        };
+
+        let fitted = |angle: f32| PixelContribColorMapDescriptor::new(4, angle);
+        let wide = PixelContribColorMapDescriptor::new_with_frustum(
+            4,
+            CameraFrustum::perspective(0.5, 16.0 / 9.0),
+            SphereParameterization::Octahedral,
+        );
+
+        // the maps computed at the distance of 2 radii share the angles of the fitted maps
+        let maps = PixelContributionMaps::from_maps(vec![
+            map(fitted(0.5), 0.2),
+            map(fitted(1.0), 0.4),
+            map(fitted(0.5).with_distance(2.0), 0.6),
+            map(fitted(1.0).with_distance(2.0), 0.8),
+            map(wide, 0.9),
+        ]);
+
+        let mut buf = Vec::new();
+        maps.write_writer(&mut buf).unwrap();
+        let maps = PixelContributionMaps::from_reader(&mut buf.as_slice()).unwrap();
+        let view = PixelContributionMapsView::from_bytes(&buf).unwrap();
+
+        let dir = Vec3::new(0.3, -0.5, 0.8);
+        let near_angle = 2.0 * 0.5f32.asin();
+        let mode = SamplingMode::Nearest;
+
+        // the lookup by angle only uses the quadratic frustum fitting the sphere
+        for angle in [0.5, 1.0] {
+            let expected = if angle == 0.5 { 0.2 } else { 0.4 };
+            assert_eq!(maps.get_pixel_contrib_for_camera_dir(dir, angle), expected);
+            assert_eq!(view.get_pixel_contrib_for_camera_dir(dir, angle), expected);
+        }
+
+        // the maps at the same distance are selected by the angle of the camera frustum
+        for (frustum, sphere_angle, expected) in [
+            (CameraFrustum::square(0.5), near_angle, 0.6),
+            (CameraFrustum::square(1.0), near_angle, 0.8),
+            (CameraFrustum::square(1.0), 2.5, 0.8),
+            (CameraFrustum::square(1.0), 0.1, 0.2),
+            (CameraFrustum::perspective(0.5, 16.0 / 9.0), 0.5, 0.9),
+        ] {
+            let p = maps.sample_pixel_contrib_for_camera(dir, &frustum, sphere_angle, mode);
+            assert!((p - expected).abs() < 1e-5, "{} != {}", p, expected);
+            assert_eq!(
+                view.sample_pixel_contrib_for_camera(dir, &frustum, sphere_angle, mode),
+                p
+            );
+        }
+
+        // between the distances, the values are interpolated w.r.t the sphere angle
+        let angle = (1.0 + near_angle) / 2.0;
+        let p = maps.sample_pixel_contrib_for_camera(dir, &CameraFrustum::square(1.0), angle, mode);
+        assert!(p > 0.4 && p < 0.8);
+    }
+
+    #[test]
+    fn test_lookup_fitted_map_at_distance() {
+        let map = |descriptor: PixelContribColorMapDescriptor, value: f32| {
+            let mut map = PixelContributionMap::new(descriptor);
+            map.pixel_contrib.iter_mut().for_each(|p| *p = value);
+            map
+        };
+
+        // the fitted map of 60 degrees is seen from the distance of 2 radii, i.e., both maps
+        // have exactly the same sphere angle
+        let angle = 2.0 * 0.5f32.asin();
+        let fitted = |angle: f32| PixelContribColorMapDescriptor::new(4, angle);
+        assert_eq!(
+            fitted(angle).sphere_angle(),
+            fitted(angle).with_distance(2.0).sphere_angle()
+        );
+
+        let dir = Vec3::new(0.3, -0.5, 0.8);
+        let maps = PixelContributionMaps::from_maps(vec![
+            map(fitted(angle), 0.2),
+            map(fitted(angle).with_distance(2.0), 0.6),
+        ]);
+        assert_eq!(maps.get_pixel_contrib_for_camera_dir(dir, angle), 0.2);
+
+        // the fitted map replaces the map of the distance with the same camera angle
+        let maps = PixelContributionMaps::from_maps(vec![
+            map(fitted(0.5), 0.1),
+            map(fitted(angle), 0.2),
+            map(fitted(0.5).with_distance(2.0), 0.4),
+            map(fitted(angle).with_distance(2.0), 0.6),
+        ]);
+        let mode = SamplingMode::Nearest;
+        for interpolator in [
+            &LinearAngleInterpolator as &dyn AngleInterpolator,
+            &TangentAngleInterpolator,
+            &QuadraticAngleInterpolator,
+            &PchipAngleInterpolator,
+        ] {
+            let p = interpolation::interpolate_camera(
+                interpolator,
+                maps.maps.len(),
+                &|i| maps.maps[i].descriptor,
+                &CameraFrustum::square(angle),
+                angle,
+                &|i| maps.maps[i].sample_pixel_contrib_for_camera_dir(dir, mode),
+            );
+            assert_eq!(p, 0.2, "{}", interpolator.name());
+        }
+
+        // the other maps of the distance are interpolated with the fitted map
+        let p = maps.sample_pixel_contrib_for_camera(
+            dir,
+            &CameraFrustum::square((0.5 + angle) / 2.0),
+            angle,
+            mode,
+        );
+        assert!(p > 0.2 && p < 0.4, "{}", p);
+    }
+
     #[test]
     fn test_serialization2() {
         let random_bytes = [0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0xFF];
         assert!(PixelContributionMaps::from_reader(&mut &random_bytes[..]).is_err());
     }
 
+    #[test]
+    fn test_read_version_1() {
+        // a version 1 file has neither metadata nor checksums and stores the maps as raw floats
+        let map_size = 4usize;
+        let angles = [0.75f32, 0.25];
+
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&PIXEL_CONTRIBUTION_MAP_IDENTIFIER);
+        buf.write_u32::<byteorder::LittleEndian>(1).unwrap();
+        buf.write_u32::<byteorder::LittleEndian>(angles.len() as u32)
+            .unwrap();
+        for (i, angle) in angles.iter().enumerate() {
+            buf.write_u32::<byteorder::LittleEndian>(map_size as u32)
+                .unwrap();
+            buf.write_f32::<byteorder::LittleEndian>(*angle).unwrap();
+            for j in 0..map_size * map_size {
+                buf.write_f32::<byteorder::LittleEndian>((i * 16 + j) as f32 / 64.0)
+                    .unwrap();
+            }
+        }
+
+        let pixel_contribs = PixelContributionMaps::from_reader(&mut buf.as_slice()).unwrap();
+        assert!(pixel_contribs.metadata().is_empty());
+
+        // the maps are sorted by their camera angle
+        let maps = pixel_contribs.get_maps();
+        assert_eq!(maps.len(), 2);
+        assert_eq!(
+            maps[0].descriptor,
+            PixelContribColorMapDescriptor::new(4, 0.25)
+        );
+        assert_eq!(
+            maps[1].descriptor,
+            PixelContribColorMapDescriptor::new(4, 0.75)
+        );
+        assert_eq!(maps[0].pixel_contrib[5], 21.0 / 64.0);
+        assert_eq!(maps[1].pixel_contrib[5], 5.0 / 64.0);
+    }
+
+    #[test]
+    fn test_read_version_1_truncated() {
+        // a corrupt map size must not allocate the values before they have been read
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&PIXEL_CONTRIBUTION_MAP_IDENTIFIER);
+        buf.write_u32::<byteorder::LittleEndian>(1).unwrap();
+        buf.write_u32::<byteorder::LittleEndian>(1).unwrap();
+        buf.write_u32::<byteorder::LittleEndian>(u32::MAX).unwrap();
+        buf.write_f32::<byteorder::LittleEndian>(0.5).unwrap();
+        buf.write_f32::<byteorder::LittleEndian>(0.25).unwrap();
+
+        let options = ReadOptions {
+            max_map_size: usize::MAX,
+            ..Default::default()
+        };
+        assert!(
+            PixelContributionMaps::from_reader_with_options(&mut buf.as_slice(), &options).is_err()
+        );
+    }
+
+    #[test]
+    fn test_serialization_metadata_and_checksum() {
+        let descriptor = PixelContribColorMapDescriptor::new(8, 0.5);
+        let mut pixel_contrib = PixelContributionMap::new(descriptor);
+        pixel_contrib.pixel_contrib[3] = 0.25;
+
+        let mut pixel_contribs = PixelContributionMaps::from_maps(vec![pixel_contrib]);
+        let metadata = pixel_contribs.metadata_mut();
+        metadata.set_source_model("models/duck.glb");
+        metadata.set_bounding_sphere(&(Vec3::new(1.0, 2.0, 3.0), 4.5).into());
+        metadata.set_frame_size(512);
+        metadata.set_renderer("Simple Rasterizer");
+        metadata.set("custom", "value");
+
+        let mut buf = Vec::new();
+        pixel_contribs.write_writer(&mut buf).unwrap();
+
+        let pixel_contribs2 = PixelContributionMaps::from_reader(&mut buf.as_slice()).unwrap();
+        assert!(pixel_contribs == pixel_contribs2);
+
+        let metadata = pixel_contribs2.metadata();
+        assert_eq!(metadata.source_model(), Some("models/duck.glb"));
+        assert_eq!(metadata.frame_size(), Some(512));
+        assert_eq!(metadata.renderer(), Some("Simple Rasterizer"));
+        assert_eq!(metadata.get("custom"), Some("value"));
+
+        let sphere = metadata.bounding_sphere().unwrap();
+        assert_eq!(sphere.center, Vec3::new(1.0, 2.0, 3.0));
+        assert_eq!(sphere.radius, 4.5);
+
+        // corrupt one of the pixel contribution values, which must be detected by the checksum
+        let n = buf.len();
+        buf[n - 8] ^= 0x01;
+        assert!(matches!(
+            PixelContributionMaps::from_reader(&mut buf.as_slice()),
+            Err(Error::ChecksumMismatch(0))
+        ));
+    }
+
+    #[test]
+    fn test_serialization_validation() {
+        let descriptor = PixelContribColorMapDescriptor::new(8, 0.5);
+        let mut pixel_contrib = PixelContributionMap::new(descriptor);
+        pixel_contrib.pixel_contrib[3] = 1.5;
+
+        let mut buf = Vec::new();
+        PixelContributionMaps::from_maps(vec![pixel_contrib.clone()])
+            .write_writer(&mut buf)
+            .unwrap();
+
+        // the offsets of the number of maps, the map size and the camera angle
+        let (num_maps, size, angle) = (12, 16, 20);
+
+        let read = |buf: &[u8], options: &ReadOptions| {
+            PixelContributionMaps::from_reader_with_options(&mut &buf[..], options)
+        };
+        let options = ReadOptions::default();
+        assert!(read(&buf, &options).is_ok());
+
+        let mut corrupt = buf.clone();
+        corrupt[size..size + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(matches!(
+            read(&corrupt, &options),
+            Err(Error::InvalidMapSize(s, DEFAULT_MAX_MAP_SIZE)) if s == u32::MAX as usize
+        ));
+
+        corrupt[size..size + 4].copy_from_slice(&0u32.to_le_bytes());
+        assert!(matches!(
+            read(&corrupt, &options),
+            Err(Error::InvalidMapSize(0, _))
+        ));
+
+        let mut corrupt = buf.clone();
+        corrupt[num_maps..num_maps + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(matches!(
+            read(&corrupt, &options),
+            Err(Error::TooManyMaps(_, DEFAULT_MAX_NUM_MAPS))
+        ));
+
+        for invalid_angle in [f32::NAN, f32::INFINITY, -0.5] {
+            let mut corrupt = buf.clone();
+            corrupt[angle..angle + 4].copy_from_slice(&invalid_angle.to_le_bytes());
+            assert!(matches!(
+                read(&corrupt, &options),
+                Err(Error::InvalidCameraAngle(_))
+            ));
+        }
+
+        // limits and value checks configured by the caller
+        let small = ReadOptions {
+            max_map_size: 4,
+            ..Default::default()
+        };
+        assert!(matches!(
+            read(&buf, &small),
+            Err(Error::InvalidMapSize(8, 4))
+        ));
+
+        let checked = ReadOptions {
+            check_value_range: true,
+            ..Default::default()
+        };
+        assert!(matches!(
+            read(&buf, &checked),
+            Err(Error::ValueOutOfRange(0, 3, v)) if v == 1.5
+        ));
+
+        // maps with the same camera angle
+        let mut buf = Vec::new();
+        PixelContributionMaps::from_maps(vec![pixel_contrib.clone(), pixel_contrib])
+            .write_writer(&mut buf)
+            .unwrap();
+        assert!(matches!(
+            read(&buf, &options),
+            Err(Error::DuplicateCameraAngle(a)) if a == 0.5
+        ));
+        assert!(matches!(
+            PixelContributionMapsView::from_bytes(&buf),
+            Err(Error::DuplicateCameraAngle(_))
+        ));
+    }
+
+    #[test]
+    fn test_serialization_with_encoding() {
+        let descriptor = PixelContribColorMapDescriptor::new(33, 1.0);
+        let mut pixel_contrib = PixelContributionMap::new(descriptor);
+        pixel_contrib
+            .pixel_contrib
+            .iter_mut()
+            .enumerate()
+            .for_each(|(i, p)| {
+                *p = 0.1 + (i % 97) as f32 / 200.0;
+            });
+
+        let pixel_contribs = PixelContributionMaps::from_maps(vec![pixel_contrib.clone()]);
+
+        let encodings = [
+            ValueEncoding::F32,
+            ValueEncoding::F16,
+            ValueEncoding::Quantized8,
+            ValueEncoding::Quantized12,
+            ValueEncoding::Quantized16,
+        ];
+
+        for values in encodings {
+            for compression in [Compression::None, Compression::Deflate] {
+                let encoding = MapEncoding::new(values, compression);
+
+                let mut buf = Vec::new();
+                pixel_contribs
+                    .write_writer_with_encoding(&mut buf, encoding)
+                    .unwrap();
+
+                let pixel_contribs2 =
+                    PixelContributionMaps::from_reader(&mut buf.as_slice()).unwrap();
+                let pixel_contrib2 = &pixel_contribs2.get_maps()[0];
+                assert_eq!(pixel_contrib.descriptor, pixel_contrib2.descriptor);
+
+                let bound = values.error_bound(0.1, 0.58) + 1e-6;
+                for (p0, p1) in pixel_contrib
+                    .pixel_contrib
+                    .iter()
+                    .zip(pixel_contrib2.pixel_contrib.iter())
+                {
+                    assert!((p0 - p1).abs() <= bound);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_serialization_with_mip_chain() {
+        let descriptor = PixelContribColorMapDescriptor::new(32, 1.0);
+        let mut pixel_contrib = PixelContributionMap::new(descriptor);
+        pixel_contrib
+            .pixel_contrib
+            .iter_mut()
+            .enumerate()
+            .for_each(|(i, p)| {
+                *p = 0.5 + 0.4 * descriptor.camera_dir_from_index(i).z;
+            });
+
+        let mut pixel_contribs = PixelContributionMaps::from_maps(vec![pixel_contrib]);
+        pixel_contribs.generate_mip_chains();
+
+        let pixel_contrib = &pixel_contribs.get_maps()[0];
+        assert_eq!(pixel_contrib.num_mip_levels(), 6);
+        assert_eq!(pixel_contrib.mip_level(5).descriptor.size(), 1);
+        assert_eq!(pixel_contrib.mip_level(10).descriptor.size(), 1);
+        assert_eq!(pixel_contrib.lod_from_footprint(0.0), 0.0);
+
+        let mut buf = Vec::new();
+        pixel_contribs.write_writer(&mut buf).unwrap();
+
+        let pixel_contribs2 = PixelContributionMaps::from_reader(&mut buf.as_slice()).unwrap();
+        assert!(pixel_contribs == pixel_contribs2);
+
+        // the lod lookup blends between the adjacent levels
+        let dir = Vec3::new(0.3, -0.2, 0.9).normalize();
+        let p1 = pixel_contrib.get_pixel_contrib_for_camera_dir_lod(dir, 1.0);
+        let p2 = pixel_contrib.get_pixel_contrib_for_camera_dir_lod(dir, 2.0);
+        let p = pixel_contrib.get_pixel_contrib_for_camera_dir_lod(dir, 1.5);
+        assert!((p - 0.5 * (p1 + p2)).abs() < 1e-6);
+        assert_eq!(
+            pixel_contrib.get_pixel_contrib_for_camera_dir_lod(dir, 0.0),
+            pixel_contrib.get_pixel_contrib_for_camera_dir(dir)
+        );
+
+        // the view skips the mip chain, but still provides the base level
+        let view = PixelContributionMapsView::from_bytes(&buf).unwrap();
+        assert_eq!(
+            view.get_maps()[0].to_map().pixel_contrib,
+            pixel_contrib.pixel_contrib
+        );
+    }
+
     #[test]
     fn test_camera_dir_index_mapping() {
         let map_sizes = [16, 32, 64, 128, 256, 512, 1024];
@@ -405,6 +1800,50 @@ mod test {
         }
     }
 
+    #[test]
+    fn test_camera_dir_index_mapping_parameterizations() {
+        let parameterizations = [
+            SphereParameterization::HemiOctahedral,
+            SphereParameterization::EqualArea,
+            SphereParameterization::CubeMap,
+        ];
+
+        for parameterization in parameterizations {
+            for map_size in [16, 31, 64, 128] {
+                let descriptor = PixelContribColorMapDescriptor::new_with_parameterization(
+                    map_size,
+                    0f32,
+                    parameterization,
+                );
+
+                for i in 0..descriptor.num_values() {
+                    let dir = descriptor.camera_dir_from_index(i);
+                    let index = descriptor.index_from_camera_dir(dir);
+
+                    assert_eq!(i, index, "{:?}", parameterization);
+                }
+            }
+
+            // the parameterization is stored in the file
+            let descriptor = PixelContribColorMapDescriptor::new_with_parameterization(
+                12,
+                0.5,
+                parameterization,
+            );
+            let maps =
+                PixelContributionMaps::from_maps(vec![PixelContributionMap::new(descriptor)]);
+
+            let mut buf = Vec::new();
+            maps.write_writer(&mut buf).unwrap();
+
+            let maps2 = PixelContributionMaps::from_reader(&mut buf.as_slice()).unwrap();
+            assert_eq!(
+                maps2.get_maps()[0].descriptor.parameterization(),
+                parameterization
+            );
+        }
+    }
+
     /// Creates a list of directional vectors for testing.
     fn create_directional_vectors() -> Vec<Vec3> {
         let num = 20;
@@ -480,4 +1919,37 @@ mod test {
             }
         }
     }
+
+    #[test]
+    fn test_pixel_contribution_angle_interpolator() {
+        let maps: Vec<PixelContributionMap> = [(0.0, 0.2), (0.5, 0.4), (1.0, 0.5)]
+            .iter()
+            .map(|(angle, value)| {
+                let mut map =
+                    PixelContributionMap::new(PixelContribColorMapDescriptor::new(4, *angle));
+                map.pixel_contrib.iter_mut().for_each(|v| *v = *value);
+                map
+            })
+            .collect();
+
+        let mut maps = PixelContributionMaps::from_maps(maps);
+        let dir = Vec3::new(0.0, 0.0, 1.0);
+
+        // the tangent interpolator is used by default
+        let p = maps.get_pixel_contrib_for_camera_dir(dir, 0.25);
+        assert_eq!(maps.angle_interpolator().name(), "Tangent");
+        assert!((p - 0.3).abs() < 1e-2);
+
+        maps.set_angle_interpolator(LinearAngleInterpolator);
+        let p = maps.get_pixel_contrib_for_camera_dir(dir, 0.25);
+        assert!((p - 0.3).abs() < 1e-6);
+
+        // the quadratic interpolator uses all three maps
+        maps.set_angle_interpolator(QuadraticAngleInterpolator);
+        let p = maps.get_pixel_contrib_for_camera_dir(dir, 0.25);
+        assert!((p - 0.3125).abs() < 1e-6);
+
+        // the interpolator is not part of the comparison
+        assert!(maps == maps.clone());
+    }
 }
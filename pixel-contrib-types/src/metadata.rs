@@ -0,0 +1,249 @@
+use std::{
+    collections::BTreeMap,
+    io::{Read, Write},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use byteorder::{ReadBytesExt, WriteBytesExt};
+use math::BoundingSphere;
+use nalgebra_glm::Vec3;
+
+use crate::{Error, Result};
+
+/// The metadata key for the path of the model the maps have been computed for.
+pub const METADATA_SOURCE_MODEL: &str = "source_model";
+
+/// The metadata key for the bounding sphere used for fitting the camera to the model.
+pub const METADATA_BOUNDING_SPHERE: &str = "bounding_sphere";
+
+/// The metadata key for the size of the quadratic frame buffer used for rendering.
+pub const METADATA_FRAME_SIZE: &str = "frame_size";
+
+/// The metadata key for the name of the renderer used for computing the maps.
+pub const METADATA_RENDERER: &str = "renderer";
+
+/// The metadata key for the creation time in seconds since the UNIX epoch.
+pub const METADATA_CREATION_TIME: &str = "creation_time";
+
+/// The metadata key for the index of the scene instance the maps have been computed for.
+pub const METADATA_INSTANCE_INDEX: &str = "instance_index";
+
+/// Key/value metadata stored alongside the pixel contribution maps.
+#[derive(Clone, Debug, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct PixelContributionMetadata {
+    entries: BTreeMap<String, String>,
+}
+
+impl PixelContributionMetadata {
+    /// Creates a new empty metadata object.
+    pub fn new() -> Self {
+        Self {
+            entries: BTreeMap::new(),
+        }
+    }
+
+    /// Returns true if there are no metadata entries.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the number of metadata entries.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns the value for the given key, if present.
+    ///
+    /// # Arguments
+    /// * `key` - The key of the metadata entry.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries.get(key).map(|v| v.as_str())
+    }
+
+    /// Sets the value for the given key and overwrites any previous value.
+    ///
+    /// # Arguments
+    /// * `key` - The key of the metadata entry.
+    /// * `value` - The value of the metadata entry.
+    pub fn set<K: Into<String>, V: Into<String>>(&mut self, key: K, value: V) {
+        self.entries.insert(key.into(), value.into());
+    }
+
+    /// Removes the entry for the given key and returns its value, if present.
+    ///
+    /// # Arguments
+    /// * `key` - The key of the metadata entry.
+    pub fn remove(&mut self, key: &str) -> Option<String> {
+        self.entries.remove(key)
+    }
+
+    /// Returns an iterator over all key/value pairs sorted by key.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.entries.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
+    /// Returns the path of the model the maps have been computed for.
+    pub fn source_model(&self) -> Option<&str> {
+        self.get(METADATA_SOURCE_MODEL)
+    }
+
+    /// Sets the path of the model the maps have been computed for.
+    ///
+    /// # Arguments
+    /// * `path` - The path of the source model.
+    pub fn set_source_model<S: Into<String>>(&mut self, path: S) {
+        self.set(METADATA_SOURCE_MODEL, path);
+    }
+
+    /// Returns the bounding sphere used for fitting the camera to the model.
+    pub fn bounding_sphere(&self) -> Option<BoundingSphere> {
+        let values: Vec<f32> = self
+            .get(METADATA_BOUNDING_SPHERE)?
+            .split_whitespace()
+            .map(|s| s.parse::<f32>())
+            .collect::<std::result::Result<_, _>>()
+            .ok()?;
+
+        match values.as_slice() {
+            [x, y, z, r] => Some(BoundingSphere::from((Vec3::new(*x, *y, *z), *r))),
+            _ => None,
+        }
+    }
+
+    /// Sets the bounding sphere used for fitting the camera to the model.
+    ///
+    /// # Arguments
+    /// * `sphere` - The bounding sphere.
+    pub fn set_bounding_sphere(&mut self, sphere: &BoundingSphere) {
+        let c = sphere.center;
+        self.set(
+            METADATA_BOUNDING_SPHERE,
+            format!("{} {} {} {}", c.x, c.y, c.z, sphere.radius),
+        );
+    }
+
+    /// Returns the size of the quadratic frame buffer used for rendering.
+    pub fn frame_size(&self) -> Option<usize> {
+        self.get(METADATA_FRAME_SIZE)?.parse().ok()
+    }
+
+    /// Sets the size of the quadratic frame buffer used for rendering.
+    ///
+    /// # Arguments
+    /// * `frame_size` - The size of the frame buffer in pixels.
+    pub fn set_frame_size(&mut self, frame_size: usize) {
+        self.set(METADATA_FRAME_SIZE, frame_size.to_string());
+    }
+
+    /// Returns the name of the renderer used for computing the maps.
+    pub fn renderer(&self) -> Option<&str> {
+        self.get(METADATA_RENDERER)
+    }
+
+    /// Sets the name of the renderer used for computing the maps.
+    ///
+    /// # Arguments
+    /// * `name` - The name of the renderer.
+    pub fn set_renderer<S: Into<String>>(&mut self, name: S) {
+        self.set(METADATA_RENDERER, name);
+    }
+
+    /// Returns the index of the scene instance the maps have been computed for. Maps without an
+    /// instance index cover the whole scene.
+    pub fn instance_index(&self) -> Option<usize> {
+        self.get(METADATA_INSTANCE_INDEX)?.parse().ok()
+    }
+
+    /// Sets the index of the scene instance the maps have been computed for.
+    ///
+    /// # Arguments
+    /// * `index` - The index of the instance within the scene.
+    pub fn set_instance_index(&mut self, index: usize) {
+        self.set(METADATA_INSTANCE_INDEX, index.to_string());
+    }
+
+    /// Returns the time the maps have been created.
+    pub fn creation_time(&self) -> Option<SystemTime> {
+        let secs: u64 = self.get(METADATA_CREATION_TIME)?.parse().ok()?;
+
+        Some(UNIX_EPOCH + Duration::from_secs(secs))
+    }
+
+    /// Sets the time the maps have been created. The time is stored with a resolution of seconds.
+    ///
+    /// # Arguments
+    /// * `time` - The creation time.
+    pub fn set_creation_time(&mut self, time: SystemTime) {
+        let secs = time
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+
+        self.set(METADATA_CREATION_TIME, secs.to_string());
+    }
+
+    /// Writes the metadata block to the given writer.
+    ///
+    /// # Arguments
+    /// * `writer` - The writer to which the metadata should be written.
+    pub(crate) fn write_writer<W: Write>(&self, writer: &mut W) -> Result<()> {
+        writer.write_u32::<byteorder::LittleEndian>(self.entries.len() as u32)?;
+
+        for (key, value) in self.entries.iter() {
+            write_string(writer, key)?;
+            write_string(writer, value)?;
+        }
+
+        Ok(())
+    }
+
+    /// Reads the metadata block from the given reader.
+    ///
+    /// # Arguments
+    /// * `reader` - The reader from which the metadata should be read.
+    pub(crate) fn from_reader<R: Read>(reader: &mut R) -> Result<Self> {
+        let num_entries = reader.read_u32::<byteorder::LittleEndian>()?;
+
+        let mut entries = BTreeMap::new();
+        for _ in 0..num_entries {
+            let key = read_string(reader)?;
+            let value = read_string(reader)?;
+
+            entries.insert(key, value);
+        }
+
+        Ok(Self { entries })
+    }
+}
+
+/// Writes the given string as length-prefixed UTF-8 bytes.
+///
+/// # Arguments
+/// * `writer` - The writer to which the string should be written.
+/// * `s` - The string to write.
+fn write_string<W: Write>(writer: &mut W, s: &str) -> Result<()> {
+    writer.write_u32::<byteorder::LittleEndian>(s.len() as u32)?;
+    writer.write_all(s.as_bytes())?;
+
+    Ok(())
+}
+
+/// Reads a length-prefixed UTF-8 string.
+///
+/// # Arguments
+/// * `reader` - The reader from which the string should be read.
+fn read_string<R: Read>(reader: &mut R) -> Result<String> {
+    let len = reader.read_u32::<byteorder::LittleEndian>()? as u64;
+
+    let mut buf = Vec::new();
+    reader.take(len).read_to_end(&mut buf)?;
+    if buf.len() as u64 != len {
+        return Err(Error::IO("Unexpected end of metadata".to_string()));
+    }
+
+    String::from_utf8(buf).map_err(|_| Error::IO("Invalid UTF-8 in metadata".to_string()))
+}
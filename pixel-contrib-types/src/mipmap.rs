@@ -0,0 +1,195 @@
+use nalgebra_glm::Vec2;
+
+use crate::PixelContributionMap;
+
+/// Returns the size of the next coarser mip level for the given map size.
+///
+/// # Arguments
+/// * `size` - The size of the finer mip level.
+#[inline]
+pub(crate) fn mip_size(size: usize) -> usize {
+    size.div_ceil(2)
+}
+
+/// Returns the sizes of all mip levels coarser than the given map size down to a single texel.
+///
+/// # Arguments
+/// * `size` - The size of the base level.
+pub(crate) fn mip_sizes(size: usize) -> impl Iterator<Item = usize> {
+    std::iter::successors(Some(size), |s| (*s > 1).then(|| mip_size(*s))).skip(1)
+}
+
+/// Generates the mip chain for the given map, i.e., all coarser levels down to a single texel.
+/// The base level itself is not part of the chain.
+///
+/// # Arguments
+/// * `map` - The base level of the mip chain.
+pub(crate) fn generate_mip_chain(map: &PixelContributionMap) -> Vec<PixelContributionMap> {
+    let mut levels: Vec<PixelContributionMap> = Vec::new();
+
+    while levels.last().unwrap_or(map).descriptor.size() > 1 {
+        let level = downsample(levels.last().unwrap_or(map));
+        levels.push(level);
+    }
+
+    levels
+}
+
+/// Downsamples the given map to the next coarser mip level.
+///
+/// Each coarse texel is computed with a tent filter covering two fine texels in each direction,
+/// where each fine texel is additionally weighted by its solid angle. Filter taps beyond the
+/// border of the map are wrapped onto the adjacent texels on the sphere, s.t. the octahedral
+/// seams do not bleed in values from the wrong side of the sphere.
+///
+/// # Arguments
+/// * `map` - The map to downsample.
+fn downsample(map: &PixelContributionMap) -> PixelContributionMap {
+    let fine = &map.descriptor;
+    let size = fine.size();
+    let coarse_size = mip_size(size);
+
+    let solid_angles: Vec<f32> = (0..fine.num_values())
+        .map(|i| fine.solid_angle_from_index(i))
+        .collect();
+
+    let descriptor = fine.with_size(coarse_size);
+
+    // the radius of the filter in fine texels
+    let radius = size as f32 / coarse_size as f32;
+
+    let mut result = PixelContributionMap::new(descriptor);
+    for (index, value) in result.pixel_contrib.iter_mut().enumerate() {
+        let (x, y) = (index % coarse_size, index / coarse_size);
+
+        // the center of the coarse texel in continuous fine texel coordinates
+        let center = Vec2::new(x as f32 + 0.5, y as f32 + 0.5) * radius - Vec2::new(0.5, 0.5);
+        let x_taps = filter_taps(center.x, radius);
+        let y_taps = filter_taps(center.y, radius);
+
+        let mut sum = 0f32;
+        let mut total_weight = 0f32;
+        for (ty, wy) in y_taps.clone() {
+            for (tx, wx) in x_taps.clone() {
+                let i = fine.neighbor_index(center, tx, ty);
+                let weight = wx * wy * solid_angles[i];
+
+                sum += weight * map.pixel_contrib[i];
+                total_weight += weight;
+            }
+        }
+
+        *value = if total_weight > 0.0 {
+            sum / total_weight
+        } else {
+            0.0
+        };
+    }
+
+    result
+}
+
+/// Returns the positions and weights of the tent filter taps along one axis.
+///
+/// # Arguments
+/// * `center` - The center of the filter in continuous texel coordinates.
+/// * `radius` - The radius of the filter in texels.
+fn filter_taps(center: f32, radius: f32) -> impl Iterator<Item = (isize, f32)> + Clone {
+    let first = (center - radius).floor() as isize + 1;
+    let last = (center + radius).ceil() as isize - 1;
+
+    (first..=last).map(move |t| (t, (1.0 - (t as f32 - center).abs() / radius).max(0.0)))
+}
+
+#[cfg(test)]
+mod test {
+    use nalgebra_glm::Vec3;
+
+    use super::*;
+    use crate::{PixelContribColorMapDescriptor, SphereParameterization};
+
+    const PARAMETERIZATIONS: [SphereParameterization; 4] = [
+        SphereParameterization::Octahedral,
+        SphereParameterization::HemiOctahedral,
+        SphereParameterization::EqualArea,
+        SphereParameterization::CubeMap,
+    ];
+
+    fn create_map(
+        size: usize,
+        parameterization: SphereParameterization,
+        f: impl Fn(Vec3) -> f32,
+    ) -> PixelContributionMap {
+        let descriptor =
+            PixelContribColorMapDescriptor::new_with_parameterization(size, 1.0, parameterization);
+
+        let mut map = PixelContributionMap::new(descriptor);
+        for (i, v) in map.pixel_contrib.iter_mut().enumerate() {
+            *v = f(descriptor.camera_dir_from_index(i));
+        }
+
+        map
+    }
+
+    #[test]
+    fn test_mip_sizes() {
+        assert_eq!(
+            mip_sizes(256).collect::<Vec<_>>(),
+            [128, 64, 32, 16, 8, 4, 2, 1]
+        );
+        assert_eq!(mip_sizes(31).collect::<Vec<_>>(), [16, 8, 4, 2, 1]);
+        assert_eq!(mip_sizes(1).count(), 0);
+
+        let map = create_map(31, SphereParameterization::Octahedral, |_| 0.5);
+        let levels = generate_mip_chain(&map);
+        assert_eq!(levels.len(), 5);
+        assert!(levels
+            .iter()
+            .all(|l| l.pixel_contrib.iter().all(|v| (v - 0.5).abs() < 1e-6)));
+    }
+
+    #[test]
+    fn test_downsample_smooth_function() {
+        let f = |dir: Vec3| 0.5 + 0.3 * dir.z + 0.1 * dir.x;
+
+        for parameterization in PARAMETERIZATIONS {
+            let map = create_map(64, parameterization, f);
+            let levels = generate_mip_chain(&map);
+
+            // the values of the coarse levels still approximate the smooth function, including
+            // the texels at the border of the map
+            for level in levels.iter().take(3) {
+                let descriptor = level.descriptor;
+                for (i, v) in level.pixel_contrib.iter().enumerate() {
+                    let expected = f(descriptor.camera_dir_from_index(i));
+                    assert!(
+                        (v - expected).abs() < 0.05,
+                        "{:?} size={} index={} {} != {}",
+                        parameterization,
+                        descriptor.size(),
+                        i,
+                        v,
+                        expected
+                    );
+                }
+            }
+
+            // the weighted mean over the sphere is preserved
+            let mean = |m: &PixelContributionMap| {
+                let (sum, total) = m.pixel_contrib.iter().enumerate().fold(
+                    (0f32, 0f32),
+                    |(sum, total), (i, v)| {
+                        let w = m.descriptor.solid_angle_from_index(i);
+                        (sum + w * v, total + w)
+                    },
+                );
+
+                sum / total
+            };
+
+            let m0 = mean(&map);
+            let m1 = mean(&levels[2]);
+            assert!((m0 - m1).abs() < 1e-2, "{:?}", parameterization);
+        }
+    }
+}
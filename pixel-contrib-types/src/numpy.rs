@@ -0,0 +1,661 @@
+use std::{
+    io::{BufReader, BufWriter, Read, Seek, Write},
+    path::Path,
+};
+
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use zip::{result::ZipError, write::SimpleFileOptions, CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::{
+    CameraFrustum, Error, PixelContribColorMapDescriptor, PixelContributionMap,
+    PixelContributionMaps, PixelContributionMetadata, Result, SphereParameterization,
+};
+
+/// The magic string at the beginning of each `.npy` file.
+const NPY_MAGIC: &[u8; 6] = b"\x93NUMPY";
+
+/// The alignment of the data of a `.npy` file, i.e., the header is padded to this size.
+const NPY_ALIGNMENT: usize = 64;
+
+/// The name of the array with the camera angles in a `.npz` archive.
+const NPZ_CAMERA_ANGLES: &str = "camera_angles.npy";
+
+/// The name of the array with the parameterization ids in a `.npz` archive.
+const NPZ_PARAMETERIZATIONS: &str = "parameterizations.npy";
+
+/// The name of the array with the camera frusta in a `.npz` archive.
+const NPZ_FRUSTUMS: &str = "frustums.npy";
+
+/// The name of the array with the camera distances in a `.npz` archive.
+const NPZ_DISTANCES: &str = "distances.npy";
+
+/// The name of the array with the metadata key/value pairs in a `.npz` archive.
+const NPZ_METADATA: &str = "metadata.npy";
+
+/// The values of a `.npy` array.
+#[derive(Clone, Debug, PartialEq)]
+enum NpyData {
+    F32(Vec<f32>),
+    U8(Vec<u8>),
+    Str(Vec<String>),
+}
+
+/// An n-dimensional array of a `.npy` file stored in row-major order.
+#[derive(Clone, Debug, PartialEq)]
+struct NpyArray {
+    shape: Vec<usize>,
+    data: NpyData,
+}
+
+/// Export and import of single maps as NumPy `.npy` arrays. Requires the `npy` feature.
+impl PixelContributionMap {
+    /// Writes the values of the map as 2D NumPy array of 32-bit floats with the shape
+    /// `(size, size)`, where the first axis is the vertical map position. The descriptor is not
+    /// stored, see [`PixelContributionMaps::write_npz_writer`].
+    ///
+    /// # Arguments
+    /// * `writer` - The writer to which the array should be written.
+    pub fn write_npy_writer<W: Write>(&self, writer: &mut W) -> Result<()> {
+        let size = self.descriptor.size();
+        write_npy(
+            writer,
+            &[size, size],
+            &NpyData::F32(self.pixel_contrib.clone()),
+        )
+    }
+
+    /// Reads the map from the given 2D NumPy array of 32-bit or 64-bit floats.
+    ///
+    /// # Arguments
+    /// * `reader` - The reader from which the array should be read.
+    /// * `camera_angle` - The camera angle of the map in radians.
+    /// * `parameterization` - The parameterization of the map.
+    pub fn from_npy_reader<R: Read>(
+        reader: &mut R,
+        camera_angle: f32,
+        parameterization: SphereParameterization,
+    ) -> Result<Self> {
+        map_from_npy(read_npy(reader)?, camera_angle, parameterization)
+    }
+}
+
+/// Export and import of NumPy `.npz` archives. Requires the `npy` feature.
+///
+/// The archive contains the array `map_<i>` with the values of the i-th map, see
+/// [`PixelContributionMap::write_npy_writer`], the array `camera_angles` with the camera angle
+/// of each map, the array `parameterizations` with the id of the parameterization of each map,
+/// the array `frustums` of shape `(n, 5)` with the aspect ratio and the left, right, bottom and
+/// top half-angles of the camera frustum of each map, the array `distances` with the camera
+/// distance of each map and the array `metadata` with the metadata key/value pairs as strings of shape `(n, 2)`.
+/// The archive can be loaded in Python via `numpy.load(path)`.
+impl PixelContributionMaps {
+    /// Writes the maps as NumPy `.npz` archive to the given writer.
+    ///
+    /// # Arguments
+    /// * `writer` - The writer to which the archive should be written.
+    pub fn write_npz_writer<W: Write + Seek>(&self, writer: W) -> Result<()> {
+        let mut zip = ZipWriter::new(writer);
+
+        let mut write_array = |name: &str, shape: &[usize], data: &NpyData| -> Result<()> {
+            zip.start_file(
+                name,
+                SimpleFileOptions::default().compression_method(CompressionMethod::Deflated),
+            )
+            .map_err(zip_error)?;
+
+            write_npy(&mut zip, shape, data)
+        };
+
+        let angles = self
+            .maps
+            .iter()
+            .map(|m| m.descriptor.camera_angle())
+            .collect();
+        write_array(NPZ_CAMERA_ANGLES, &[self.maps.len()], &NpyData::F32(angles))?;
+
+        let parameterizations = self
+            .maps
+            .iter()
+            .map(|m| m.descriptor.parameterization().to_id())
+            .collect();
+        write_array(
+            NPZ_PARAMETERIZATIONS,
+            &[self.maps.len()],
+            &NpyData::U8(parameterizations),
+        )?;
+
+        let frustums = self
+            .maps
+            .iter()
+            .flat_map(|m| m.descriptor.frustum().to_array())
+            .collect();
+        write_array(NPZ_FRUSTUMS, &[self.maps.len(), 5], &NpyData::F32(frustums))?;
+
+        let distances = self.maps.iter().map(|m| m.descriptor.distance()).collect();
+        write_array(NPZ_DISTANCES, &[self.maps.len()], &NpyData::F32(distances))?;
+
+        let metadata = self
+            .metadata
+            .iter()
+            .flat_map(|(k, v)| [k.to_string(), v.to_string()])
+            .collect();
+        write_array(
+            NPZ_METADATA,
+            &[self.metadata.len(), 2],
+            &NpyData::Str(metadata),
+        )?;
+
+        for (i, map) in self.maps.iter().enumerate() {
+            let size = map.descriptor.size();
+            write_array(
+                &format!("map_{}.npy", i),
+                &[size, size],
+                &NpyData::F32(map.pixel_contrib.clone()),
+            )?;
+        }
+
+        zip.finish().map_err(zip_error)?;
+
+        Ok(())
+    }
+
+    /// Writes the maps as NumPy `.npz` archive to the given path.
+    ///
+    /// # Arguments
+    /// * `path` - The path to which the archive should be written.
+    pub fn write_npz_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let file = std::fs::File::create(path)?;
+        self.write_npz_writer(BufWriter::new(file))
+    }
+
+    /// Reads the maps from the NumPy `.npz` archive provided by the given reader. The arrays
+    /// for the parameterizations, the frusta, the distances and the metadata are optional.
+    ///
+    /// # Arguments
+    /// * `reader` - The reader from which the archive should be read.
+    pub fn from_npz_reader<R: Read + Seek>(reader: R) -> Result<Self> {
+        let mut zip = ZipArchive::new(reader).map_err(zip_error)?;
+
+        let mut read_array = |name: &str| -> Result<Option<NpyArray>> {
+            match zip.by_name(name) {
+                Ok(mut file) => Ok(Some(read_npy(&mut file)?)),
+                Err(ZipError::FileNotFound) => Ok(None),
+                Err(err) => Err(zip_error(err)),
+            }
+        };
+
+        let angles = match read_array(NPZ_CAMERA_ANGLES)? {
+            Some(NpyArray {
+                data: NpyData::F32(angles),
+                ..
+            }) => angles,
+            _ => {
+                return Err(Error::IO(format!(
+                    "Missing or invalid array {} in archive",
+                    NPZ_CAMERA_ANGLES
+                )))
+            }
+        };
+
+        let parameterizations = match read_array(NPZ_PARAMETERIZATIONS)? {
+            Some(NpyArray {
+                data: NpyData::U8(ids),
+                ..
+            }) if ids.len() == angles.len() => ids
+                .into_iter()
+                .map(SphereParameterization::from_id)
+                .collect::<Result<Vec<_>>>()?,
+            None => vec![SphereParameterization::default(); angles.len()],
+            _ => {
+                return Err(Error::IO(format!(
+                    "Invalid array {} in archive",
+                    NPZ_PARAMETERIZATIONS
+                )))
+            }
+        };
+
+        let frustums = match read_array(NPZ_FRUSTUMS)? {
+            Some(NpyArray {
+                shape,
+                data: NpyData::F32(values),
+            }) if shape == [angles.len(), 5] => values
+                .chunks_exact(5)
+                .map(|v| Some(CameraFrustum::from_array([v[0], v[1], v[2], v[3], v[4]])))
+                .collect(),
+            None => vec![None; angles.len()],
+            _ => {
+                return Err(Error::IO(format!(
+                    "Invalid array {} in archive",
+                    NPZ_FRUSTUMS
+                )))
+            }
+        };
+
+        let distances = match read_array(NPZ_DISTANCES)? {
+            Some(NpyArray {
+                shape,
+                data: NpyData::F32(values),
+            }) if shape == [angles.len()] => values,
+            None => vec![0.0; angles.len()],
+            _ => {
+                return Err(Error::IO(format!(
+                    "Invalid array {} in archive",
+                    NPZ_DISTANCES
+                )))
+            }
+        };
+
+        let mut metadata = PixelContributionMetadata::new();
+        match read_array(NPZ_METADATA)? {
+            Some(NpyArray {
+                shape,
+                data: NpyData::Str(entries),
+            }) if shape.len() == 2 && shape[1] == 2 => {
+                entries
+                    .chunks_exact(2)
+                    .for_each(|e| metadata.set(e[0].as_str(), e[1].as_str()));
+            }
+            None => {}
+            _ => {
+                return Err(Error::IO(format!(
+                    "Invalid array {} in archive",
+                    NPZ_METADATA
+                )))
+            }
+        }
+
+        let mut maps = Vec::with_capacity(angles.len());
+        for (i, (((angle, parameterization), frustum), distance)) in angles
+            .iter()
+            .zip(parameterizations)
+            .zip(frustums)
+            .zip(distances)
+            .enumerate()
+        {
+            let name = format!("map_{}.npy", i);
+            let array = read_array(&name)?
+                .ok_or_else(|| Error::IO(format!("Missing array {} in archive", name)))?;
+
+            let mut map = map_from_npy(array, *angle, parameterization)?;
+            if let Some(frustum) = frustum {
+                map.descriptor = map.descriptor.with_frustum(frustum);
+            }
+            map.descriptor = map.descriptor.with_distance(distance);
+
+            maps.push(map);
+        }
+
+        let mut result = Self::from_maps(maps);
+        result.metadata = metadata;
+
+        Ok(result)
+    }
+
+    /// Reads the maps from the NumPy `.npz` archive at the given path.
+    ///
+    /// # Arguments
+    /// * `path` - The path from which the archive should be read.
+    pub fn from_npz_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file = std::fs::File::open(path)?;
+        Self::from_npz_reader(BufReader::new(file))
+    }
+}
+
+/// Creates a map from the given 2D array.
+///
+/// # Arguments
+/// * `array` - The array with the values of the map.
+/// * `camera_angle` - The camera angle of the map in radians.
+/// * `parameterization` - The parameterization of the map.
+fn map_from_npy(
+    array: NpyArray,
+    camera_angle: f32,
+    parameterization: SphereParameterization,
+) -> Result<PixelContributionMap> {
+    let values = match array.data {
+        NpyData::F32(values) if array.shape.len() == 2 && array.shape[0] == array.shape[1] => {
+            values
+        }
+        _ => {
+            return Err(Error::IO(format!(
+                "Expected a quadratic float array, but got shape {:?}",
+                array.shape
+            )))
+        }
+    };
+
+    let descriptor = PixelContribColorMapDescriptor::new_with_parameterization(
+        array.shape[0],
+        camera_angle,
+        parameterization,
+    );
+
+    let mut map = PixelContributionMap::new(descriptor);
+    map.pixel_contrib = values;
+
+    Ok(map)
+}
+
+/// Writes the given array in the `.npy` format version 1.0.
+///
+/// # Arguments
+/// * `writer` - The writer to which the array should be written.
+/// * `shape` - The shape of the array.
+/// * `data` - The values of the array in row-major order.
+fn write_npy<W: Write>(writer: &mut W, shape: &[usize], data: &NpyData) -> Result<()> {
+    // strings are stored as fixed-length UTF-32 code points
+    let max_chars = match data {
+        NpyData::Str(values) => values
+            .iter()
+            .map(|s| s.chars().count())
+            .max()
+            .unwrap_or(0)
+            .max(1),
+        _ => 0,
+    };
+
+    let descr = match data {
+        NpyData::F32(_) => "<f4".to_string(),
+        NpyData::U8(_) => "|u1".to_string(),
+        NpyData::Str(_) => format!("<U{}", max_chars),
+    };
+
+    let shape = match shape {
+        [n] => format!("({},)", n),
+        _ => format!(
+            "({})",
+            shape
+                .iter()
+                .map(|n| n.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    };
+
+    // the header is padded with spaces and terminated by a newline
+    let mut header = format!(
+        "{{'descr': '{}', 'fortran_order': False, 'shape': {}, }}",
+        descr, shape
+    );
+    let unpadded = NPY_MAGIC.len() + 4 + header.len() + 1;
+    header.extend(std::iter::repeat_n(
+        ' ',
+        unpadded.next_multiple_of(NPY_ALIGNMENT) - unpadded,
+    ));
+    header.push('\n');
+
+    writer.write_all(NPY_MAGIC)?;
+    writer.write_all(&[1, 0])?;
+    writer.write_u16::<LittleEndian>(header.len() as u16)?;
+    writer.write_all(header.as_bytes())?;
+
+    match data {
+        NpyData::F32(values) => {
+            for v in values {
+                writer.write_f32::<LittleEndian>(*v)?;
+            }
+        }
+        NpyData::U8(values) => writer.write_all(values)?,
+        NpyData::Str(values) => {
+            for s in values {
+                let n = s.chars().count();
+                for c in s.chars().chain(std::iter::repeat_n('\0', max_chars - n)) {
+                    writer.write_u32::<LittleEndian>(c as u32)?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads an array in the `.npy` format. Only little-endian 32-bit and 64-bit floats, unsigned
+/// bytes and unicode strings are supported, where 64-bit floats are converted to 32-bit floats.
+///
+/// # Arguments
+/// * `reader` - The reader from which the array should be read.
+fn read_npy<R: Read>(reader: &mut R) -> Result<NpyArray> {
+    let mut magic = [0u8; 6];
+    reader.read_exact(&mut magic)?;
+    if &magic != NPY_MAGIC {
+        return Err(Error::IO("Invalid NumPy array".to_string()));
+    }
+
+    let major = reader.read_u8()?;
+    let _minor = reader.read_u8()?;
+    let header_len = match major {
+        1 => reader.read_u16::<LittleEndian>()? as usize,
+        2 | 3 => reader.read_u32::<LittleEndian>()? as usize,
+        _ => {
+            return Err(Error::IO(format!(
+                "Unsupported NumPy array version {}",
+                major
+            )))
+        }
+    };
+
+    let mut header = vec![0u8; header_len];
+    reader.read_exact(&mut header)?;
+    let header = String::from_utf8(header)
+        .map_err(|_| Error::IO("Invalid NumPy array header".to_string()))?;
+
+    let descr = header_value(&header, "descr")?
+        .trim_matches(|c| c == '\'' || c == '"')
+        .to_string();
+    let fortran_order = header_value(&header, "fortran_order")? == "True";
+    let shape = header_value(&header, "shape")?
+        .trim_matches(|c| c == '(' || c == ')')
+        .split(',')
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .map(|s| {
+            s.parse::<usize>()
+                .map_err(|_| Error::IO(format!("Invalid NumPy array shape {}", s)))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let n: usize = shape.iter().product();
+    let data = match descr.as_str() {
+        "<f4" => {
+            let mut values = vec![0f32; n];
+            reader.read_f32_into::<LittleEndian>(&mut values)?;
+            NpyData::F32(values)
+        }
+        "<f8" => {
+            let mut values = vec![0f64; n];
+            reader.read_f64_into::<LittleEndian>(&mut values)?;
+            NpyData::F32(values.into_iter().map(|v| v as f32).collect())
+        }
+        "|u1" | "<u1" => {
+            let mut values = vec![0u8; n];
+            reader.read_exact(&mut values)?;
+            NpyData::U8(values)
+        }
+        descr if descr.starts_with("<U") => {
+            let num_chars: usize = descr[2..]
+                .parse()
+                .map_err(|_| Error::IO(format!("Invalid NumPy data type {}", descr)))?;
+
+            let mut values = Vec::with_capacity(n);
+            for _ in 0..n {
+                let mut s = String::new();
+                for _ in 0..num_chars {
+                    let c = reader.read_u32::<LittleEndian>()?;
+                    if c != 0 {
+                        s.push(char::from_u32(c).ok_or_else(|| {
+                            Error::IO(format!("Invalid character {} in NumPy array", c))
+                        })?);
+                    }
+                }
+
+                values.push(s);
+            }
+
+            NpyData::Str(values)
+        }
+        descr => return Err(Error::IO(format!("Unsupported NumPy data type {}", descr))),
+    };
+
+    let data = if fortran_order && shape.len() == 2 {
+        transpose(data, shape[0], shape[1])
+    } else {
+        data
+    };
+
+    Ok(NpyArray { shape, data })
+}
+
+/// Returns the raw value of the given key of the dictionary in the `.npy` header.
+///
+/// # Arguments
+/// * `header` - The header of the `.npy` file.
+/// * `key` - The key of the value to return.
+fn header_value<'a>(header: &'a str, key: &str) -> Result<&'a str> {
+    let missing = || Error::IO(format!("Missing '{}' in NumPy array header", key));
+
+    let start = header
+        .find(&format!("'{}'", key))
+        .or_else(|| header.find(&format!("\"{}\"", key)))
+        .ok_or_else(missing)?;
+    let value = header[start + key.len() + 2..]
+        .trim_start()
+        .strip_prefix(':')
+        .ok_or_else(missing)?
+        .trim_start();
+
+    // the value either is a tuple or ends at the next comma or the end of the dictionary
+    let end = if value.starts_with('(') {
+        value.find(')').map(|i| i + 1)
+    } else {
+        value.find([',', '}'])
+    }
+    .ok_or_else(missing)?;
+
+    Ok(value[..end].trim())
+}
+
+/// Converts the given 2D array from column-major to row-major order.
+///
+/// # Arguments
+/// * `data` - The values of the array in column-major order.
+/// * `rows` - The number of rows of the array.
+/// * `columns` - The number of columns of the array.
+fn transpose(data: NpyData, rows: usize, columns: usize) -> NpyData {
+    fn transpose<T: Clone>(values: Vec<T>, rows: usize, columns: usize) -> Vec<T> {
+        (0..rows * columns)
+            .map(|i| values[(i % columns) * rows + i / columns].clone())
+            .collect()
+    }
+
+    match data {
+        NpyData::F32(values) => NpyData::F32(transpose(values, rows, columns)),
+        NpyData::U8(values) => NpyData::U8(transpose(values, rows, columns)),
+        NpyData::Str(values) => NpyData::Str(transpose(values, rows, columns)),
+    }
+}
+
+/// Converts the given zip error into an error of this crate.
+///
+/// # Arguments
+/// * `err` - The zip error to convert.
+fn zip_error(err: ZipError) -> Error {
+    match err {
+        ZipError::Io(err) => err.into(),
+        err => Error::IO(format!("Invalid NumPy archive: {}", err)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+
+    fn create_maps() -> PixelContributionMaps {
+        let mut maps = Vec::new();
+        for (i, parameterization) in [
+            SphereParameterization::Octahedral,
+            SphereParameterization::EqualArea,
+            SphereParameterization::CubeMap,
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let descriptor = PixelContribColorMapDescriptor::new_with_parameterization(
+                7 + i,
+                0.1 + i as f32 * 0.7,
+                parameterization,
+            )
+            .with_distance(if i == 2 { 1.5 } else { 0.0 });
+
+            let mut map = PixelContributionMap::new(descriptor);
+            map.pixel_contrib
+                .iter_mut()
+                .enumerate()
+                .for_each(|(j, p)| *p = (j as f32 * 0.37).cos().abs());
+            maps.push(map);
+        }
+
+        let mut maps = PixelContributionMaps::from_maps(maps);
+        maps.metadata_mut().set_source_model("models/dück.glb");
+        maps.metadata_mut().set_frame_size(512);
+
+        maps
+    }
+
+    #[test]
+    fn test_npz_round_trip() {
+        let maps = create_maps();
+
+        let mut buf = Cursor::new(Vec::new());
+        maps.write_npz_writer(&mut buf).unwrap();
+
+        buf.set_position(0);
+        let maps2 = PixelContributionMaps::from_npz_reader(buf).unwrap();
+        assert!(maps == maps2);
+
+        assert!(PixelContributionMaps::from_npz_reader(Cursor::new(vec![0u8; 32])).is_err());
+    }
+
+    #[test]
+    fn test_npy_round_trip() {
+        let maps = create_maps();
+        let map = &maps.get_maps()[1];
+
+        let mut buf = Vec::new();
+        map.write_npy_writer(&mut buf).unwrap();
+        assert_eq!(&buf[..6], NPY_MAGIC);
+        assert_eq!((buf.len() - map.pixel_contrib.len() * 4) % NPY_ALIGNMENT, 0);
+
+        let map2 = PixelContributionMap::from_npy_reader(
+            &mut buf.as_slice(),
+            map.descriptor.camera_angle(),
+            map.descriptor.parameterization(),
+        )
+        .unwrap();
+        assert!(*map == map2);
+    }
+
+    #[test]
+    fn test_npy_fortran_order_and_f64() {
+        // the array [[0, 1], [2, 3]] stored as 64-bit floats in column-major order
+        let header = "{'descr': '<f8', 'fortran_order': True, 'shape': (2, 2), }";
+        let mut buf = Vec::new();
+        buf.extend_from_slice(NPY_MAGIC);
+        buf.extend_from_slice(&[1, 0]);
+        buf.extend_from_slice(&(header.len() as u16).to_le_bytes());
+        buf.extend_from_slice(header.as_bytes());
+        for v in [0f64, 2f64, 1f64, 3f64] {
+            buf.extend_from_slice(&v.to_le_bytes());
+        }
+
+        let map = PixelContributionMap::from_npy_reader(
+            &mut buf.as_slice(),
+            0.5,
+            SphereParameterization::Octahedral,
+        )
+        .unwrap();
+        assert_eq!(map.descriptor.size(), 2);
+        assert_eq!(map.pixel_contrib, [0.0, 1.0, 2.0, 3.0]);
+    }
+}
@@ -0,0 +1,295 @@
+use std::{
+    io::{BufReader, BufWriter, Read, Seek, Write},
+    path::Path,
+};
+
+use exr::prelude::{
+    read, AnyChannel, AnyChannels, AttributeValue, Encoding, FlatSamples, Image, ImageAttributes,
+    IntegerBounds, Layer, LayerAttributes, ReadChannels, ReadLayers, SmallVec, Text, WritableImage,
+};
+
+use crate::{
+    CameraFrustum, Error, PixelContribColorMapDescriptor, PixelContributionMap,
+    PixelContributionMaps, PixelContributionMetadata, Result, SphereParameterization,
+};
+
+/// The name of the single channel of each layer.
+const EXR_CHANNEL: &str = "Y";
+
+/// The name of the layer attribute with the camera angle.
+const EXR_CAMERA_ANGLE: &str = "cameraAngle";
+
+/// The name of the layer attribute with the parameterization.
+const EXR_PARAMETERIZATION: &str = "parameterization";
+
+/// The name of the layer attribute with the camera frustum.
+const EXR_FRUSTUM: &str = "frustum";
+
+/// The name of the layer attribute with the camera distance.
+const EXR_DISTANCE: &str = "distance";
+
+/// The prefix of the layer attributes with the metadata entries.
+const EXR_METADATA_PREFIX: &str = "pcmp:";
+
+/// Export and import of OpenEXR images. Requires the `exr` feature.
+///
+/// Each map is stored as separate layer with a single 32-bit float channel `Y`. The camera
+/// angle, the parameterization, the camera frustum and the camera distance are stored as
+/// attributes of the layer and the metadata entries as attributes of the first layer prefixed
+/// with `pcmp:`.
+impl PixelContributionMaps {
+    /// Writes the maps as multi-layer OpenEXR image to the given writer.
+    ///
+    /// # Arguments
+    /// * `writer` - The writer to which the image should be written.
+    pub fn write_exr_writer<W: Write + Seek>(&self, writer: W) -> Result<()> {
+        if self.maps.is_empty() {
+            return Err(Error::InvalidArgument(
+                "OpenEXR images require at least one map".to_string(),
+            ));
+        }
+
+        let layers: Vec<_> = self
+            .maps
+            .iter()
+            .enumerate()
+            .map(|(i, map)| {
+                let descriptor = &map.descriptor;
+
+                let mut attributes = LayerAttributes::named(format!("map_{}", i).as_str());
+                attributes.other.insert(
+                    Text::from(EXR_CAMERA_ANGLE),
+                    AttributeValue::F32(descriptor.camera_angle()),
+                );
+                attributes.other.insert(
+                    Text::from(EXR_PARAMETERIZATION),
+                    AttributeValue::Text(Text::from(descriptor.parameterization().name())),
+                );
+                attributes.other.insert(
+                    Text::from(EXR_FRUSTUM),
+                    AttributeValue::Text(Text::from(descriptor.frustum().to_text().as_str())),
+                );
+                attributes.other.insert(
+                    Text::from(EXR_DISTANCE),
+                    AttributeValue::F32(descriptor.distance()),
+                );
+
+                // the metadata is stored once with the first layer. The values are stored as raw
+                // UTF-8 bytes to preserve non-ASCII characters.
+                if i == 0 {
+                    for (key, value) in self.metadata.iter() {
+                        attributes.other.insert(
+                            Text::from_slice_unchecked(
+                                format!("{}{}", EXR_METADATA_PREFIX, key).as_bytes(),
+                            ),
+                            AttributeValue::Text(Text::from_slice_unchecked(value.as_bytes())),
+                        );
+                    }
+                }
+
+                let channel =
+                    AnyChannel::new(EXR_CHANNEL, FlatSamples::F32(map.pixel_contrib.clone()));
+
+                Layer::new(
+                    (descriptor.size(), descriptor.size()),
+                    attributes,
+                    Encoding::FAST_LOSSLESS,
+                    AnyChannels::sort(SmallVec::from_vec(vec![channel])),
+                )
+            })
+            .collect();
+
+        let size = self
+            .maps
+            .iter()
+            .map(|m| m.descriptor.size())
+            .max()
+            .unwrap_or_default();
+        let attributes = ImageAttributes::new(IntegerBounds::from_dimensions((size, size)));
+
+        Image::from_layers(attributes, layers)
+            .write()
+            .to_buffered(writer)
+            .map_err(exr_error)
+    }
+
+    /// Writes the maps as multi-layer OpenEXR image to the given path.
+    ///
+    /// # Arguments
+    /// * `path` - The path to which the image should be written.
+    pub fn write_exr_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let file = std::fs::File::create(path)?;
+        self.write_exr_writer(BufWriter::new(file))
+    }
+
+    /// Reads the maps from the OpenEXR image provided by the given reader. Each layer must have
+    /// a single channel and the camera angle attribute.
+    ///
+    /// # Arguments
+    /// * `reader` - The reader from which the image should be read.
+    pub fn from_exr_reader<R: Read + Seek>(reader: R) -> Result<Self> {
+        let image = read()
+            .no_deep_data()
+            .largest_resolution_level()
+            .all_channels()
+            .all_layers()
+            .all_attributes()
+            .from_buffered(reader)
+            .map_err(exr_error)?;
+
+        // the metadata may be stored with any layer or shared by all layers
+        let mut metadata = PixelContributionMetadata::new();
+        let attributes = std::iter::once(&image.attributes.other)
+            .chain(image.layer_data.iter().map(|l| &l.attributes.other));
+        for (key, value) in attributes.flatten() {
+            let key = text_to_string(key)?;
+            if let (Some(key), AttributeValue::Text(value)) =
+                (key.strip_prefix(EXR_METADATA_PREFIX), value)
+            {
+                metadata.set(key, text_to_string(value)?);
+            }
+        }
+
+        let maps = image
+            .layer_data
+            .into_iter()
+            .map(|layer| {
+                if layer.size.width() != layer.size.height() {
+                    return Err(Error::IO(format!(
+                        "Expected a quadratic layer, but got {}x{}",
+                        layer.size.width(),
+                        layer.size.height()
+                    )));
+                }
+
+                let camera_angle = match layer.attributes.other.get(&Text::from(EXR_CAMERA_ANGLE)) {
+                    Some(AttributeValue::F32(angle)) => *angle,
+                    _ => return Err(Error::IO("Missing camera angle of layer".to_string())),
+                };
+
+                let parameterization = match layer
+                    .attributes
+                    .other
+                    .get(&Text::from(EXR_PARAMETERIZATION))
+                {
+                    Some(AttributeValue::Text(name)) => {
+                        SphereParameterization::from_name(&text_to_string(name)?)?
+                    }
+                    _ => SphereParameterization::default(),
+                };
+
+                let channels = &layer.channel_data.list;
+                if channels.len() != 1 {
+                    return Err(Error::IO(format!(
+                        "Expected a single channel, but got {}",
+                        channels.len()
+                    )));
+                }
+
+                let mut descriptor = PixelContribColorMapDescriptor::new_with_parameterization(
+                    layer.size.width(),
+                    camera_angle,
+                    parameterization,
+                );
+                if let Some(AttributeValue::Text(frustum)) =
+                    layer.attributes.other.get(&Text::from(EXR_FRUSTUM))
+                {
+                    descriptor = descriptor
+                        .with_frustum(CameraFrustum::from_text(&text_to_string(frustum)?)?);
+                }
+                if let Some(AttributeValue::F32(distance)) =
+                    layer.attributes.other.get(&Text::from(EXR_DISTANCE))
+                {
+                    descriptor = descriptor.with_distance(*distance);
+                }
+
+                let mut map = PixelContributionMap::new(descriptor);
+                map.pixel_contrib = channels[0].sample_data.values_as_f32().collect();
+
+                Ok(map)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut result = Self::from_maps(maps);
+        result.metadata = metadata;
+
+        Ok(result)
+    }
+
+    /// Reads the maps from the OpenEXR image at the given path.
+    ///
+    /// # Arguments
+    /// * `path` - The path from which the image should be read.
+    pub fn from_exr_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file = std::fs::File::open(path)?;
+        Self::from_exr_reader(BufReader::new(file))
+    }
+}
+
+/// Converts the given OpenEXR text, which has been written as UTF-8, into a string.
+///
+/// # Arguments
+/// * `text` - The text to convert.
+fn text_to_string(text: &Text) -> Result<String> {
+    String::from_utf8(text.as_slice().to_vec())
+        .map_err(|_| Error::IO("Invalid text attribute in OpenEXR image".to_string()))
+}
+
+/// Converts the given OpenEXR error into an error of this crate.
+///
+/// # Arguments
+/// * `err` - The OpenEXR error to convert.
+fn exr_error(err: exr::error::Error) -> Error {
+    match err {
+        exr::error::Error::Io(err) => err.into(),
+        err => Error::IO(format!("Invalid OpenEXR image: {}", err)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn test_exr_round_trip() {
+        let mut maps = Vec::new();
+        for (i, parameterization) in [
+            SphereParameterization::Octahedral,
+            SphereParameterization::HemiOctahedral,
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let descriptor = PixelContribColorMapDescriptor::new_with_parameterization(
+                16 - i * 3,
+                0.2 + i as f32 * 0.9,
+                parameterization,
+            )
+            .with_distance(i as f32 * 1.5);
+
+            let mut map = PixelContributionMap::new(descriptor);
+            map.pixel_contrib
+                .iter_mut()
+                .enumerate()
+                .for_each(|(j, p)| *p = (j as f32 * 0.13).sin().abs());
+            maps.push(map);
+        }
+
+        let mut maps = PixelContributionMaps::from_maps(maps);
+        maps.metadata_mut().set_source_model("models/dück.glb");
+        maps.metadata_mut().set_renderer("Simple Rasterizer");
+
+        let mut buf = Cursor::new(Vec::new());
+        maps.write_exr_writer(&mut buf).unwrap();
+
+        buf.set_position(0);
+        let maps2 = PixelContributionMaps::from_exr_reader(buf).unwrap();
+        assert!(maps == maps2);
+
+        let empty = PixelContributionMaps::new();
+        assert!(empty.write_exr_writer(Cursor::new(Vec::new())).is_err());
+        assert!(PixelContributionMaps::from_exr_reader(Cursor::new(vec![0u8; 32])).is_err());
+    }
+}
@@ -0,0 +1,234 @@
+use std::borrow::Cow;
+
+use crate::PixelContributionMap;
+
+/// The error metrics between two pixel contribution maps.
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub struct PixelContributionErrorMetrics {
+    /// The root mean squared error over all values.
+    pub rmse: f32,
+
+    /// The maximal absolute error over all values.
+    pub max_error: f32,
+
+    /// The mean absolute error over all values.
+    pub mean_error: f32,
+
+    /// The root mean squared error weighted by the solid angle of each value.
+    pub weighted_rmse: f32,
+
+    /// The mean absolute error weighted by the solid angle of each value.
+    pub weighted_mean_error: f32,
+}
+
+/// Element-wise operations and comparisons of pixel contribution maps. If the two maps of an
+/// operation differ in size or parameterization, the second map is resampled to the descriptor
+/// of the first one, see [`PixelContributionMap::resample_to`]. The result always has the
+/// descriptor of the first map and no mip chain.
+impl PixelContributionMap {
+    /// Combines the values of this map and the given map element-wise.
+    ///
+    /// # Arguments
+    /// * `other` - The second operand of the operation.
+    /// * `op` - The operation applied to each pair of values.
+    pub fn zip_with<F>(&self, other: &PixelContributionMap, op: F) -> PixelContributionMap
+    where
+        F: Fn(f32, f32) -> f32,
+    {
+        let other = self.aligned(other);
+
+        let mut map = PixelContributionMap::new(self.descriptor);
+        map.pixel_contrib
+            .iter_mut()
+            .zip(self.pixel_contrib.iter().zip(other.pixel_contrib.iter()))
+            .for_each(|(p, (a, b))| *p = op(*a, *b));
+
+        map
+    }
+
+    /// Returns the element-wise difference `self - other`.
+    ///
+    /// # Arguments
+    /// * `other` - The map to subtract.
+    pub fn difference(&self, other: &PixelContributionMap) -> PixelContributionMap {
+        self.zip_with(other, |a, b| a - b)
+    }
+
+    /// Returns the element-wise absolute difference `|self - other|`.
+    ///
+    /// # Arguments
+    /// * `other` - The map to compare with.
+    pub fn abs_difference(&self, other: &PixelContributionMap) -> PixelContributionMap {
+        self.zip_with(other, |a, b| (a - b).abs())
+    }
+
+    /// Returns the element-wise ratio `self / other`. Values where `other` is zero are zero.
+    ///
+    /// # Arguments
+    /// * `other` - The map to divide by.
+    pub fn ratio(&self, other: &PixelContributionMap) -> PixelContributionMap {
+        self.zip_with(other, |a, b| if b != 0.0 { a / b } else { 0.0 })
+    }
+
+    /// Returns the element-wise maximum of both maps.
+    ///
+    /// # Arguments
+    /// * `other` - The map to compare with.
+    pub fn max(&self, other: &PixelContributionMap) -> PixelContributionMap {
+        self.zip_with(other, f32::max)
+    }
+
+    /// Returns the element-wise linear blend `self * (1 - weight) + other * weight`.
+    ///
+    /// # Arguments
+    /// * `other` - The map to blend with.
+    /// * `weight` - The weight of the other map.
+    pub fn blend(&self, other: &PixelContributionMap, weight: f32) -> PixelContributionMap {
+        self.zip_with(other, |a, b| a * (1.0 - weight) + b * weight)
+    }
+
+    /// Computes the error metrics between this map and the given map.
+    ///
+    /// # Arguments
+    /// * `other` - The map to compare with.
+    pub fn error_metrics(&self, other: &PixelContributionMap) -> PixelContributionErrorMetrics {
+        let other = self.aligned(other);
+        let descriptor = &self.descriptor;
+
+        let mut sum_error = 0f64;
+        let mut sum_sqr_error = 0f64;
+        let mut sum_weighted_error = 0f64;
+        let mut sum_weighted_sqr_error = 0f64;
+        let mut sum_weights = 0f64;
+        let mut max_error = 0f32;
+        for (index, (a, b)) in self
+            .pixel_contrib
+            .iter()
+            .zip(other.pixel_contrib.iter())
+            .enumerate()
+        {
+            let error = (a - b).abs();
+            let weight = descriptor.solid_angle_from_index(index) as f64;
+
+            sum_error += error as f64;
+            sum_sqr_error += (error * error) as f64;
+            sum_weighted_error += error as f64 * weight;
+            sum_weighted_sqr_error += (error * error) as f64 * weight;
+            sum_weights += weight;
+            max_error = max_error.max(error);
+        }
+
+        let n = self.pixel_contrib.len() as f64;
+        let mean = |sum: f64, total: f64| if total > 0.0 { sum / total } else { 0.0 };
+
+        PixelContributionErrorMetrics {
+            rmse: mean(sum_sqr_error, n).sqrt() as f32,
+            max_error,
+            mean_error: mean(sum_error, n) as f32,
+            weighted_rmse: mean(sum_weighted_sqr_error, sum_weights).sqrt() as f32,
+            weighted_mean_error: mean(sum_weighted_error, sum_weights) as f32,
+        }
+    }
+
+    /// Returns the given map resampled to the size and parameterization of this map, if needed.
+    ///
+    /// # Arguments
+    /// * `other` - The map to align with this map.
+    fn aligned<'a>(&self, other: &'a PixelContributionMap) -> Cow<'a, PixelContributionMap> {
+        if other.descriptor.size() == self.descriptor.size()
+            && other.descriptor.parameterization() == self.descriptor.parameterization()
+        {
+            Cow::Borrowed(other)
+        } else {
+            Cow::Owned(
+                other.resample_to(
+                    other
+                        .descriptor
+                        .with_size(self.descriptor.size())
+                        .with_parameterization(self.descriptor.parameterization()),
+                ),
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use nalgebra_glm::Vec3;
+
+    use super::*;
+    use crate::{PixelContribColorMapDescriptor, SphereParameterization};
+
+    fn create_map(
+        size: usize,
+        parameterization: SphereParameterization,
+        f: impl Fn(Vec3) -> f32,
+    ) -> PixelContributionMap {
+        let descriptor =
+            PixelContribColorMapDescriptor::new_with_parameterization(size, 0.5, parameterization);
+
+        let mut map = PixelContributionMap::new(descriptor);
+        for (i, v) in map.pixel_contrib.iter_mut().enumerate() {
+            *v = f(descriptor.camera_dir_from_index(i));
+        }
+
+        map
+    }
+
+    #[test]
+    fn test_element_wise_operations() {
+        let a = create_map(16, SphereParameterization::Octahedral, |d| 0.5 + 0.25 * d.z);
+        let b = create_map(16, SphereParameterization::Octahedral, |d| 0.5 + 0.25 * d.x);
+
+        let check = |result: PixelContributionMap, op: fn(f32, f32) -> f32| {
+            assert_eq!(result.descriptor, a.descriptor);
+            for (i, v) in result.pixel_contrib.iter().enumerate() {
+                assert_eq!(*v, op(a.pixel_contrib[i], b.pixel_contrib[i]));
+            }
+        };
+
+        check(a.difference(&b), |a, b| a - b);
+        check(a.abs_difference(&b), |a, b| (a - b).abs());
+        check(a.ratio(&b), |a, b| a / b);
+        check(a.max(&b), f32::max);
+        check(a.blend(&b, 0.25), |a, b| a * 0.75 + b * 0.25);
+
+        let zero = PixelContributionMap::new(a.descriptor);
+        assert!(a.ratio(&zero).pixel_contrib.iter().all(|v| *v == 0.0));
+    }
+
+    #[test]
+    fn test_error_metrics() {
+        let a = create_map(32, SphereParameterization::Octahedral, |d| 0.5 + 0.25 * d.z);
+
+        let metrics = a.error_metrics(&a);
+        assert_eq!(metrics, PixelContributionErrorMetrics::default());
+
+        let b = a.zip_with(&a, |v, _| v + 0.1);
+        let metrics = a.error_metrics(&b);
+        assert!((metrics.rmse - 0.1).abs() < 1e-5);
+        assert!((metrics.max_error - 0.1).abs() < 1e-5);
+        assert!((metrics.mean_error - 0.1).abs() < 1e-5);
+        assert!((metrics.weighted_rmse - 0.1).abs() < 1e-5);
+        assert!((metrics.weighted_mean_error - 0.1).abs() < 1e-5);
+
+        // maps of a different size and parameterization are resampled automatically
+        let f = |d: Vec3| 0.5 + 0.25 * d.z;
+        for parameterization in [
+            SphereParameterization::Octahedral,
+            SphereParameterization::EqualArea,
+            SphereParameterization::CubeMap,
+        ] {
+            let c = create_map(64, parameterization, f);
+            let metrics = a.error_metrics(&c);
+            assert!(metrics.max_error < 0.02, "{:?}", parameterization);
+
+            let d = a.abs_difference(&c);
+            assert_eq!(d.descriptor, a.descriptor);
+        }
+
+        let resampled = a.resample(8);
+        assert_eq!(resampled.descriptor.size(), 8);
+        assert_eq!(resampled.descriptor.camera_angle(), 0.5);
+    }
+}
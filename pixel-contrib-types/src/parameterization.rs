@@ -0,0 +1,708 @@
+use std::f32::consts::FRAC_PI_4;
+
+use nalgebra_glm::{Vec2, Vec3};
+
+use crate::{decode_octahedron_normal, encode_octahedron_normal, Error, Result};
+
+/// The number of faces of the cube map in horizontal direction of the layout.
+const CUBE_MAP_COLUMNS: usize = 3;
+
+/// The number of faces of the cube map in vertical direction of the layout.
+const CUBE_MAP_ROWS: usize = 2;
+
+/// The margin used for keeping positions on the border between two faces on the same face.
+const CUBE_MAP_FACE_EPSILON: f32 = 1e-5;
+
+/// The parameterization of the sphere of camera directions onto the quadratic map.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum SphereParameterization {
+    /// Octahedral mapping of the full sphere, see [`encode_octahedron_normal`].
+    #[default]
+    Octahedral,
+
+    /// Octahedral mapping of the upper hemisphere (z >= 0) onto the full map. Directions below
+    /// the horizon are mirrored onto the upper hemisphere.
+    HemiOctahedral,
+
+    /// Equal-area mapping of the full sphere with the layout of the octahedral mapping, s.t.
+    /// each texel covers the same solid angle.
+    EqualArea,
+
+    /// Cube map with the faces +X, -X, +Y, -Y, +Z, -Z arranged in a 3x2 grid.
+    ///
+    /// Since the map is quadratic, each face covers `size / 3 x size / 2` texels, i.e., the
+    /// texels are 1.5 times as wide as high when projected onto the face. The anisotropy is in
+    /// the order of the distortion of the octahedral mapping, s.t. the angular error of the
+    /// nearest texel is comparable to the octahedral mapping of the same size.
+    CubeMap,
+}
+
+impl SphereParameterization {
+    /// Maps the given direction onto the normalized map position in the range [0, 1].
+    ///
+    /// # Arguments
+    /// * `dir` - The direction to map.
+    pub fn encode(&self, dir: Vec3) -> Vec2 {
+        match self {
+            SphereParameterization::Octahedral => encode_octahedron_normal(dir),
+            SphereParameterization::HemiOctahedral => encode_hemi_octahedron(dir),
+            SphereParameterization::EqualArea => encode_equal_area(dir),
+            SphereParameterization::CubeMap => {
+                let (face, st) = cube_map_face(dir);
+                cube_map_uv(face, st)
+            }
+        }
+    }
+
+    /// Maps the given normalized map position in the range [0, 1] onto the direction.
+    ///
+    /// # Arguments
+    /// * `uv` - The normalized map position.
+    pub fn decode(&self, uv: Vec2) -> Vec3 {
+        match self {
+            SphereParameterization::Octahedral => decode_octahedron_normal(uv),
+            SphereParameterization::HemiOctahedral => decode_hemi_octahedron(uv),
+            SphereParameterization::EqualArea => decode_equal_area(uv),
+            SphereParameterization::CubeMap => {
+                // keep positions on the border between two faces on the same face
+                let (face, st) = cube_map_face_from_uv(uv);
+                let margin = 2.0 * CUBE_MAP_FACE_EPSILON;
+                let st = st.map(|v| v.clamp(-1.0 + margin, 1.0 - margin));
+
+                cube_map_dir(face, st)
+            }
+        }
+    }
+
+    /// Returns the index of the texel closest to the given direction.
+    ///
+    /// # Arguments
+    /// * `size` - The size of the quadratic map.
+    /// * `dir` - The direction to return the index for.
+    pub(crate) fn index_from_dir(&self, size: usize, dir: Vec3) -> usize {
+        let p = self.encode(dir) * size as f32 - Vec2::new(0.5, 0.5);
+
+        let (x, y) = match self {
+            // the nearest texel must belong to the same face as the direction, unless the map
+            // is too small for the face to contain any texel center
+            SphereParameterization::CubeMap => {
+                let (face, _) = cube_map_face(dir);
+                let (x_range, y_range) = cube_map_face_texels(size, face);
+                let clamp = |v: f32, (begin, end): (isize, isize)| {
+                    let v = v.round() as isize;
+                    if begin <= end {
+                        v.clamp(begin, end)
+                    } else {
+                        v
+                    }
+                };
+
+                (clamp(p.x, x_range), clamp(p.y, y_range))
+            }
+            _ => (p.x.round() as isize, p.y.round() as isize),
+        };
+
+        let n = size as isize - 1;
+        y.clamp(0, n) as usize * size + x.clamp(0, n) as usize
+    }
+
+    /// Returns the index of the texel at the given texel position in the neighborhood of the
+    /// given anchor. Positions beyond the seams of the parameterization are wrapped onto the
+    /// adjacent texels on the sphere.
+    ///
+    /// # Arguments
+    /// * `size` - The size of the quadratic map.
+    /// * `anchor` - The continuous texel position the neighborhood is located around.
+    /// * `x` - The horizontal texel position.
+    /// * `y` - The vertical texel position.
+    pub(crate) fn neighbor_index(&self, size: usize, anchor: Vec2, x: isize, y: isize) -> usize {
+        let n = size as isize;
+
+        let (x, y) = match self {
+            // crossing an edge mirrors the position along that edge
+            SphereParameterization::Octahedral | SphereParameterization::EqualArea => {
+                let (mut x, mut y) = (x, y);
+                while x < 0 || x >= n || y < 0 || y >= n {
+                    if x < 0 {
+                        x = -1 - x;
+                        y = n - 1 - y;
+                    } else if x >= n {
+                        x = 2 * n - 1 - x;
+                        y = n - 1 - y;
+                    } else if y < 0 {
+                        y = -1 - y;
+                        x = n - 1 - x;
+                    } else {
+                        y = 2 * n - 1 - y;
+                        x = n - 1 - x;
+                    }
+                }
+
+                (x, y)
+            }
+            // the edges are the horizon, beyond which the hemisphere is mirrored
+            SphereParameterization::HemiOctahedral => (mirror(x, n), mirror(y, n)),
+            SphereParameterization::CubeMap => {
+                let size_f = size as f32;
+                let anchor_uv = (anchor + Vec2::new(0.5, 0.5)) / size_f;
+                let (face, _) = cube_map_face_from_uv(anchor_uv.map(|v| v.clamp(0.0, 1.0)));
+                let (x_range, y_range) = cube_map_face_texels(size, face);
+
+                if x < x_range.0 || x > x_range.1 || y < y_range.0 || y > y_range.1 {
+                    // extrapolate the face plane to find the texel on the adjacent face
+                    let uv = Vec2::new(x as f32 + 0.5, y as f32 + 0.5) / size_f;
+                    let dir = cube_map_dir(face, cube_map_face_coords(face, uv));
+
+                    return self.index_from_dir(size, dir);
+                }
+
+                (x, y)
+            }
+        };
+
+        y as usize * size + x as usize
+    }
+
+    /// Returns the solid angle in steradians covered by the texel at the given position.
+    ///
+    /// # Arguments
+    /// * `size` - The size of the quadratic map.
+    /// * `x` - The horizontal texel position.
+    /// * `y` - The vertical texel position.
+    pub(crate) fn texel_solid_angle(&self, size: usize, x: usize, y: usize) -> f32 {
+        let size_f = size as f32;
+
+        // the corners of a cube map texel are computed on the face of the texel center
+        let face = match self {
+            SphereParameterization::CubeMap => {
+                let center = Vec2::new(x as f32 + 0.5, y as f32 + 0.5) / size_f;
+                Some(cube_map_face_from_uv(center).0)
+            }
+            _ => None,
+        };
+
+        let corner = |dx: usize, dy: usize| {
+            let uv = Vec2::new((x + dx) as f32, (y + dy) as f32) / size_f;
+            match face {
+                Some(face) => cube_map_dir(face, cube_map_face_coords(face, uv)),
+                None => self.decode(uv),
+            }
+        };
+
+        let c = [corner(0, 0), corner(1, 0), corner(1, 1), corner(0, 1)];
+
+        spherical_triangle_area(&c[0], &c[1], &c[2]) + spherical_triangle_area(&c[0], &c[2], &c[3])
+    }
+
+    /// Returns the id used for serializing the parameterization.
+    pub(crate) fn to_id(self) -> u8 {
+        match self {
+            SphereParameterization::Octahedral => 0,
+            SphereParameterization::HemiOctahedral => 1,
+            SphereParameterization::EqualArea => 2,
+            SphereParameterization::CubeMap => 3,
+        }
+    }
+
+    /// Returns the parameterization for the given serialized id.
+    ///
+    /// # Arguments
+    /// * `id` - The serialized id of the parameterization.
+    pub(crate) fn from_id(id: u8) -> Result<Self> {
+        match id {
+            0 => Ok(SphereParameterization::Octahedral),
+            1 => Ok(SphereParameterization::HemiOctahedral),
+            2 => Ok(SphereParameterization::EqualArea),
+            3 => Ok(SphereParameterization::CubeMap),
+            _ => Err(Error::IO(format!("Unknown sphere parameterization {}", id))),
+        }
+    }
+
+    /// Returns the name used for the parameterization in text based formats.
+    pub fn name(self) -> &'static str {
+        match self {
+            SphereParameterization::Octahedral => "octahedral",
+            SphereParameterization::HemiOctahedral => "hemi_octahedral",
+            SphereParameterization::EqualArea => "equal_area",
+            SphereParameterization::CubeMap => "cube_map",
+        }
+    }
+
+    /// Returns the parameterization for the given name, see [`Self::name`].
+    ///
+    /// # Arguments
+    /// * `name` - The name of the parameterization.
+    pub fn from_name(name: &str) -> Result<Self> {
+        [
+            SphereParameterization::Octahedral,
+            SphereParameterization::HemiOctahedral,
+            SphereParameterization::EqualArea,
+            SphereParameterization::CubeMap,
+        ]
+        .into_iter()
+        .find(|p| p.name() == name)
+        .ok_or_else(|| Error::IO(format!("Unknown sphere parameterization {}", name)))
+    }
+}
+
+/// Mirrors the given texel position at the borders of the range [0, n).
+///
+/// # Arguments
+/// * `x` - The texel position to mirror.
+/// * `n` - The number of texels.
+fn mirror(x: isize, n: isize) -> isize {
+    let mut x = x;
+    while x < 0 || x >= n {
+        x = if x < 0 { -1 - x } else { 2 * n - 1 - x };
+    }
+
+    x
+}
+
+/// Returns the area of the spherical triangle spanned by the given unit vectors, see
+/// Van Oosterom and Strackee, "The Solid Angle of a Plane Triangle".
+///
+/// # Arguments
+/// * `a` - The first corner of the triangle.
+/// * `b` - The second corner of the triangle.
+/// * `c` - The third corner of the triangle.
+fn spherical_triangle_area(a: &Vec3, b: &Vec3, c: &Vec3) -> f32 {
+    let numerator = a.dot(&b.cross(c)).abs();
+    let denominator = 1.0 + a.dot(b) + b.dot(c) + c.dot(a);
+
+    2.0 * numerator.atan2(denominator)
+}
+
+/// Maps the upper hemisphere onto the square rotated by 45 degrees.
+///
+/// # Arguments
+/// * `dir` - The direction to map.
+fn encode_hemi_octahedron(dir: Vec3) -> Vec2 {
+    let dir = dir.normalize();
+    let abs_sum = dir.x.abs() + dir.y.abs() + dir.z.abs();
+
+    let x = dir.x / abs_sum;
+    let y = dir.y / abs_sum;
+
+    let uv = Vec2::new(x + y, x - y) * 0.5 + Vec2::new(0.5, 0.5);
+
+    uv.map(|v| v.clamp(0.0, 1.0))
+}
+
+/// Maps the square rotated by 45 degrees back onto the upper hemisphere.
+///
+/// # Arguments
+/// * `uv` - The normalized map position.
+fn decode_hemi_octahedron(uv: Vec2) -> Vec3 {
+    let uv = uv * 2.0 - Vec2::new(1.0, 1.0);
+
+    let x = (uv.x + uv.y) * 0.5;
+    let y = (uv.x - uv.y) * 0.5;
+    let z = 1.0 - x.abs() - y.abs();
+
+    Vec3::new(x, y, z).normalize()
+}
+
+/// Maps the given direction onto the square using the equal-area mapping of Clarberg, "Fast
+/// Equal-Area Mapping of the (Hemi)Sphere using SIMD".
+///
+/// # Arguments
+/// * `dir` - The direction to map.
+fn encode_equal_area(dir: Vec3) -> Vec2 {
+    let dir = dir.normalize();
+    let (x, y, z) = (dir.x.abs(), dir.y.abs(), dir.z.abs());
+
+    let r = (1.0 - z).max(0.0).sqrt();
+
+    let a = x.max(y);
+    let b = x.min(y);
+    let b = if a == 0.0 { 0.0 } else { b / a };
+
+    let mut phi = b.atan() / (2.0 * FRAC_PI_4);
+    if x < y {
+        phi = 1.0 - phi;
+    }
+
+    let mut v = phi * r;
+    let mut u = r - v;
+
+    if dir.z < 0.0 {
+        std::mem::swap(&mut u, &mut v);
+        u = 1.0 - u;
+        v = 1.0 - v;
+    }
+
+    let u = u.copysign(dir.x);
+    let v = v.copysign(dir.y);
+
+    let uv = Vec2::new(u, v) * 0.5 + Vec2::new(0.5, 0.5);
+
+    uv.map(|v| v.clamp(0.0, 1.0))
+}
+
+/// Maps the given normalized map position back onto the sphere using the equal-area mapping.
+///
+/// # Arguments
+/// * `uv` - The normalized map position.
+fn decode_equal_area(uv: Vec2) -> Vec3 {
+    let u = uv.x * 2.0 - 1.0;
+    let v = uv.y * 2.0 - 1.0;
+    let (up, vp) = (u.abs(), v.abs());
+
+    let signed_distance = 1.0 - (up + vp);
+    let r = 1.0 - signed_distance.abs();
+
+    let phi = if r == 0.0 { 1.0 } else { (vp - up) / r + 1.0 } * FRAC_PI_4;
+    let z = (1.0 - r * r).copysign(signed_distance);
+
+    let cos_phi = phi.cos().copysign(u);
+    let sin_phi = phi.sin().copysign(v);
+    let s = r * (2.0 - r * r).max(0.0).sqrt();
+
+    Vec3::new(cos_phi * s, sin_phi * s, z).normalize()
+}
+
+/// Returns the cube map face for the given direction together with the coordinates in the
+/// range [-1, 1] on that face.
+///
+/// # Arguments
+/// * `dir` - The direction to map.
+fn cube_map_face(dir: Vec3) -> (usize, Vec2) {
+    let (ax, ay, az) = (dir.x.abs(), dir.y.abs(), dir.z.abs());
+
+    if ax >= ay && ax >= az {
+        if dir.x >= 0.0 {
+            (0, Vec2::new(-dir.z, -dir.y) / ax)
+        } else {
+            (1, Vec2::new(dir.z, -dir.y) / ax)
+        }
+    } else if ay >= az {
+        if dir.y >= 0.0 {
+            (2, Vec2::new(dir.x, dir.z) / ay)
+        } else {
+            (3, Vec2::new(dir.x, -dir.z) / ay)
+        }
+    } else if dir.z >= 0.0 {
+        (4, Vec2::new(dir.x, -dir.y) / az)
+    } else {
+        (5, Vec2::new(-dir.x, -dir.y) / az)
+    }
+}
+
+/// Returns the direction for the given coordinates on the given cube map face. The coordinates
+/// may exceed the range [-1, 1], in which case the face plane is extrapolated.
+///
+/// # Arguments
+/// * `face` - The index of the cube map face.
+/// * `st` - The coordinates on the face.
+fn cube_map_dir(face: usize, st: Vec2) -> Vec3 {
+    let (s, t) = (st.x, st.y);
+
+    let dir = match face {
+        0 => Vec3::new(1.0, -t, -s),
+        1 => Vec3::new(-1.0, -t, s),
+        2 => Vec3::new(s, 1.0, t),
+        3 => Vec3::new(s, -1.0, -t),
+        4 => Vec3::new(s, -t, 1.0),
+        _ => Vec3::new(-s, -t, -1.0),
+    };
+
+    dir.normalize()
+}
+
+/// Returns the normalized map position for the given coordinates on the given cube map face.
+///
+/// # Arguments
+/// * `face` - The index of the cube map face.
+/// * `st` - The coordinates on the face in the range [-1, 1].
+fn cube_map_uv(face: usize, st: Vec2) -> Vec2 {
+    let column = (face % CUBE_MAP_COLUMNS) as f32;
+    let row = (face / CUBE_MAP_COLUMNS) as f32;
+
+    // keep the position inside of the face, s.t. it is mapped back onto the same face
+    let local = |v: f32| (v * 0.5 + 0.5).clamp(0.0, 1.0 - CUBE_MAP_FACE_EPSILON);
+
+    Vec2::new(
+        (column + local(st.x)) / CUBE_MAP_COLUMNS as f32,
+        (row + local(st.y)) / CUBE_MAP_ROWS as f32,
+    )
+}
+
+/// Returns the coordinates on the given cube map face for the given normalized map position.
+/// Positions outside of the face result in coordinates outside of the range [-1, 1].
+///
+/// # Arguments
+/// * `face` - The index of the cube map face.
+/// * `uv` - The normalized map position.
+fn cube_map_face_coords(face: usize, uv: Vec2) -> Vec2 {
+    let column = (face % CUBE_MAP_COLUMNS) as f32;
+    let row = (face / CUBE_MAP_COLUMNS) as f32;
+
+    Vec2::new(
+        (uv.x * CUBE_MAP_COLUMNS as f32 - column) * 2.0 - 1.0,
+        (uv.y * CUBE_MAP_ROWS as f32 - row) * 2.0 - 1.0,
+    )
+}
+
+/// Returns the cube map face containing the given normalized map position together with the
+/// coordinates on that face.
+///
+/// # Arguments
+/// * `uv` - The normalized map position.
+fn cube_map_face_from_uv(uv: Vec2) -> (usize, Vec2) {
+    let column = ((uv.x * CUBE_MAP_COLUMNS as f32) as usize).min(CUBE_MAP_COLUMNS - 1);
+    let row = ((uv.y * CUBE_MAP_ROWS as f32) as usize).min(CUBE_MAP_ROWS - 1);
+    let face = row * CUBE_MAP_COLUMNS + column;
+
+    (face, cube_map_face_coords(face, uv))
+}
+
+/// Returns the inclusive ranges of the texel positions whose centers lie on the given face.
+///
+/// # Arguments
+/// * `size` - The size of the quadratic map.
+/// * `face` - The index of the cube map face.
+fn cube_map_face_texels(size: usize, face: usize) -> ((isize, isize), (isize, isize)) {
+    let range = |cell: usize, num_cells: usize| {
+        let begin = ((cell * size) as f32 / num_cells as f32 - 0.5).ceil() as isize;
+        let end = (((cell + 1) * size) as f32 / num_cells as f32 - 0.5).ceil() as isize - 1;
+
+        (begin, end)
+    };
+
+    (
+        range(face % CUBE_MAP_COLUMNS, CUBE_MAP_COLUMNS),
+        range(face / CUBE_MAP_COLUMNS, CUBE_MAP_ROWS),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use nalgebra_glm::{dot, normalize};
+
+    use super::*;
+
+    const PARAMETERIZATIONS: [SphereParameterization; 4] = [
+        SphereParameterization::Octahedral,
+        SphereParameterization::HemiOctahedral,
+        SphereParameterization::EqualArea,
+        SphereParameterization::CubeMap,
+    ];
+
+    fn directions() -> Vec<Vec3> {
+        let num = 24;
+        let pi = std::f32::consts::PI;
+
+        let mut dirs = Vec::new();
+        for i in 0..=num {
+            let theta = i as f32 / num as f32 * pi;
+            for j in 0..num {
+                let phi = j as f32 / num as f32 * 2.0 * pi;
+                dirs.push(Vec3::new(
+                    theta.sin() * phi.cos(),
+                    theta.sin() * phi.sin(),
+                    theta.cos(),
+                ));
+            }
+        }
+
+        dirs
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        for parameterization in PARAMETERIZATIONS {
+            for dir in directions() {
+                let uv = parameterization.encode(dir);
+                assert!(
+                    (0.0..=1.0).contains(&uv.x) && (0.0..=1.0).contains(&uv.y),
+                    "{:?} {} {}",
+                    parameterization,
+                    dir,
+                    uv
+                );
+
+                // the hemi-octahedral mapping mirrors the lower hemisphere
+                let expected = if parameterization == SphereParameterization::HemiOctahedral {
+                    Vec3::new(dir.x, dir.y, dir.z.abs())
+                } else {
+                    dir
+                };
+
+                let dir2 = parameterization.decode(uv);
+                assert!(
+                    (dir2 - expected).norm() < 1e-4,
+                    "{:?}: {} != {}",
+                    parameterization,
+                    dir2,
+                    expected
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_equal_area() {
+        // all texels cover approximately the same solid angle, i.e., uniformly distributed
+        // directions are uniformly distributed over the texels
+        let size = 8;
+        let mut counts = vec![0usize; size * size];
+
+        let num = 400;
+        for i in 0..num {
+            let z = 1.0 - 2.0 * (i as f32 + 0.5) / num as f32;
+            for j in 0..num {
+                let phi = (j as f32 + 0.5) / num as f32 * 2.0 * std::f32::consts::PI;
+                let r = (1.0 - z * z).sqrt();
+                let dir = Vec3::new(r * phi.cos(), r * phi.sin(), z);
+
+                counts[SphereParameterization::EqualArea.index_from_dir(size, dir)] += 1;
+            }
+        }
+
+        let expected = (num * num) as f32 / (size * size) as f32;
+        for count in counts {
+            assert!((count as f32 / expected - 1.0).abs() < 0.05);
+        }
+    }
+
+    #[test]
+    fn test_neighbor_index() {
+        let size = 24;
+        let n = size as isize;
+
+        for parameterization in PARAMETERIZATIONS {
+            // the neighbors must be located close to the anchor on the sphere
+            let max_angle = 6.0 * std::f32::consts::PI / n as f32;
+
+            for y in 0..n {
+                for x in 0..n {
+                    let anchor = Vec2::new(x as f32, y as f32);
+                    let d0 = parameterization.decode((anchor + Vec2::new(0.5, 0.5)) / size as f32);
+
+                    for (dx, dy) in [(-1, 0), (1, 0), (0, -1), (0, 1), (-1, -1), (1, 1)] {
+                        let index = parameterization.neighbor_index(size, anchor, x + dx, y + dy);
+                        assert!(index < size * size);
+
+                        let uv =
+                            Vec2::new((index % size) as f32 + 0.5, (index / size) as f32 + 0.5)
+                                / size as f32;
+                        let d1 = parameterization.decode(uv);
+
+                        let angle = dot(&d0, &d1).clamp(-1.0, 1.0).acos();
+                        assert!(
+                            angle <= max_angle,
+                            "{:?} ({}, {}) + ({}, {}): {}",
+                            parameterization,
+                            x,
+                            y,
+                            dx,
+                            dy,
+                            angle
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_texel_solid_angle() {
+        let size = 32;
+
+        for parameterization in PARAMETERIZATIONS {
+            let total: f32 = (0..size * size)
+                .map(|i| parameterization.texel_solid_angle(size, i % size, i / size))
+                .sum();
+
+            // the hemi-octahedral mapping only covers the upper hemisphere
+            let expected = if parameterization == SphereParameterization::HemiOctahedral {
+                2.0 * std::f32::consts::PI
+            } else {
+                4.0 * std::f32::consts::PI
+            };
+
+            assert!(
+                (total / expected - 1.0).abs() < 0.01,
+                "{:?}: {}",
+                parameterization,
+                total
+            );
+        }
+    }
+
+    /// Returns the maximum and the mean angle between uniformly distributed directions and the
+    /// center of their nearest texel.
+    fn nearest_texel_error(parameterization: SphereParameterization, size: usize) -> (f32, f32) {
+        let num = 200;
+        let mut max = 0f32;
+        let mut sum = 0f32;
+
+        for i in 0..num {
+            let z = 1.0 - 2.0 * (i as f32 + 0.5) / num as f32;
+            for j in 0..num {
+                let phi = (j as f32 + 0.5) / num as f32 * 2.0 * std::f32::consts::PI;
+                let r = (1.0 - z * z).sqrt();
+                let dir = Vec3::new(r * phi.cos(), r * phi.sin(), z);
+
+                let index = parameterization.index_from_dir(size, dir);
+                let uv = Vec2::new((index % size) as f32 + 0.5, (index / size) as f32 + 0.5)
+                    / size as f32;
+                let angle = dot(&dir, &parameterization.decode(uv))
+                    .clamp(-1.0, 1.0)
+                    .acos();
+
+                max = max.max(angle);
+                sum += angle;
+            }
+        }
+
+        (max, sum / (num * num) as f32)
+    }
+
+    #[test]
+    fn test_cube_map_sampling_error() {
+        // despite the anisotropic texels, the cube map is as accurate as the octahedral mapping
+        for size in [24, 64] {
+            let (max_octahedral, mean_octahedral) =
+                nearest_texel_error(SphereParameterization::Octahedral, size);
+            let (max_cube_map, mean_cube_map) =
+                nearest_texel_error(SphereParameterization::CubeMap, size);
+
+            assert!(
+                max_cube_map <= max_octahedral,
+                "{} {}",
+                max_cube_map,
+                max_octahedral
+            );
+            assert!(
+                mean_cube_map <= 1.05 * mean_octahedral,
+                "{} {}",
+                mean_cube_map,
+                mean_octahedral
+            );
+        }
+    }
+
+    #[test]
+    fn test_index_from_dir_cube_map() {
+        let size = 25;
+        let cube_map = SphereParameterization::CubeMap;
+
+        // the texel next to the face border still belongs to the face of the direction
+        for dir in [
+            Vec3::new(1.0, 0.0, -0.999),
+            Vec3::new(-1.0, 0.999, 0.0),
+            Vec3::new(0.0, -1.0, 0.999),
+        ] {
+            let dir = normalize(&dir);
+            let index = cube_map.index_from_dir(size, dir);
+
+            let uv =
+                Vec2::new((index % size) as f32 + 0.5, (index / size) as f32 + 0.5) / size as f32;
+            assert!(dot(&dir, &cube_map.decode(uv)) > 0.9);
+        }
+    }
+}
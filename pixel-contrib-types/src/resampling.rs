@@ -0,0 +1,172 @@
+use crate::{
+    PixelContribColorMapDescriptor, PixelContributionMap, PixelContributionMaps, SamplingMode,
+};
+
+/// The maximal number of subsamples per axis used for integrating over a texel.
+const MAX_SUBSAMPLES: usize = 16;
+
+impl PixelContributionMap {
+    /// Creates a new map with the given descriptor by resampling this map. Each value of the new
+    /// map is the average of this map over the area of the sphere covered by the texel, i.e., the
+    /// texel is divided into subtexels which are sampled bilinearly and weighted by their solid
+    /// angle. The number of subtexels is chosen s.t. each texel of this map is covered by at
+    /// least one subtexel. The camera angle of the descriptor is kept as given.
+    ///
+    /// # Arguments
+    /// * `descriptor` - The descriptor of the map to create.
+    pub fn resample_to(&self, descriptor: PixelContribColorMapDescriptor) -> PixelContributionMap {
+        let size = descriptor.size();
+        let subsamples = self
+            .descriptor
+            .size()
+            .div_ceil(size.max(1))
+            .clamp(1, MAX_SUBSAMPLES);
+
+        // the subtexels of a texel (x, y) are the texels of the fine descriptor in the range
+        // [x * subsamples, (x + 1) * subsamples) x [y * subsamples, (y + 1) * subsamples)
+        let fine = descriptor.with_size(size * subsamples);
+
+        let mut map = PixelContributionMap::new(descriptor);
+        map.pixel_contrib
+            .iter_mut()
+            .enumerate()
+            .for_each(|(index, p)| {
+                let (x, y) = (index % size, index / size);
+
+                let mut sum = 0f32;
+                let mut total_weight = 0f32;
+                for sy in y * subsamples..(y + 1) * subsamples {
+                    for sx in x * subsamples..(x + 1) * subsamples {
+                        let sub_index = sy * fine.size() + sx;
+                        let dir = fine.camera_dir_from_index(sub_index);
+                        let weight = fine.solid_angle_from_index(sub_index);
+
+                        sum += weight
+                            * self.sample_pixel_contrib_for_camera_dir(dir, SamplingMode::Bilinear);
+                        total_weight += weight;
+                    }
+                }
+
+                *p = if total_weight > 0.0 {
+                    sum / total_weight
+                } else {
+                    self.get_pixel_contrib_for_camera_dir(descriptor.camera_dir_from_index(index))
+                };
+            });
+
+        map
+    }
+
+    /// Creates a new map with the given size by resampling this map, see [`Self::resample_to`].
+    /// The camera angle, the frustum and the parameterization are preserved.
+    ///
+    /// # Arguments
+    /// * `size` - The size of the map to create.
+    pub fn resample(&self, size: usize) -> PixelContributionMap {
+        self.resample_to(self.descriptor.with_size(size))
+    }
+}
+
+impl PixelContributionMaps {
+    /// Creates new maps with the given size by resampling each map, see
+    /// [`PixelContributionMap::resample`]. The metadata and the angle interpolator are preserved.
+    ///
+    /// # Arguments
+    /// * `size` - The size of the maps to create.
+    pub fn resample(&self, size: usize) -> PixelContributionMaps {
+        let mut result = self.clone();
+        result.maps = self.maps.iter().map(|m| m.resample(size)).collect();
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use nalgebra_glm::Vec3;
+
+    use super::*;
+    use crate::SphereParameterization;
+
+    fn create_map(
+        size: usize,
+        parameterization: SphereParameterization,
+        f: impl Fn(Vec3) -> f32,
+    ) -> PixelContributionMap {
+        let descriptor =
+            PixelContribColorMapDescriptor::new_with_parameterization(size, 0.5, parameterization);
+
+        let mut map = PixelContributionMap::new(descriptor);
+        for (i, v) in map.pixel_contrib.iter_mut().enumerate() {
+            *v = f(descriptor.camera_dir_from_index(i));
+        }
+
+        map
+    }
+
+    #[test]
+    fn test_resample_preserves_mean() {
+        // a function with a sharp feature, s.t. point sampling would not preserve the mean
+        let f = |dir: Vec3| if dir.z > 0.9 { 1.0 } else { 0.1 };
+
+        for parameterization in [
+            SphereParameterization::Octahedral,
+            SphereParameterization::HemiOctahedral,
+            SphereParameterization::EqualArea,
+        ] {
+            let map = create_map(128, parameterization, f);
+            let mean = map.statistics().mean;
+
+            for size in [32, 17, 8] {
+                let resampled = map.resample(size);
+                assert_eq!(resampled.descriptor.size(), size);
+                assert_eq!(resampled.descriptor.camera_angle(), 0.5);
+                assert_eq!(resampled.descriptor.parameterization(), parameterization);
+
+                let resampled_mean = resampled.statistics().mean;
+                assert!(
+                    (mean - resampled_mean).abs() < 0.01,
+                    "{:?} size={} {} != {}",
+                    parameterization,
+                    size,
+                    mean,
+                    resampled_mean
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_resample_smooth_function() {
+        let f = |dir: Vec3| 0.5 + 0.3 * dir.z + 0.1 * dir.x;
+
+        let map = create_map(64, SphereParameterization::Octahedral, f);
+        for (size, parameterization) in [
+            (16, SphereParameterization::Octahedral),
+            (128, SphereParameterization::Octahedral),
+            (32, SphereParameterization::EqualArea),
+            (48, SphereParameterization::CubeMap),
+        ] {
+            let descriptor = PixelContribColorMapDescriptor::new_with_parameterization(
+                size,
+                1.0,
+                parameterization,
+            );
+            let resampled = map.resample_to(descriptor);
+            assert_eq!(resampled.descriptor, descriptor);
+
+            let reference = create_map(size, parameterization, f);
+            let metrics = reference.error_metrics(&resampled);
+            assert!(
+                metrics.max_error < 0.03,
+                "{:?} size={} {}",
+                parameterization,
+                size,
+                metrics.max_error
+            );
+        }
+
+        let maps = PixelContributionMaps::from_maps(vec![map]).resample(8);
+        assert_eq!(maps.get_maps()[0].descriptor.size(), 8);
+    }
+}
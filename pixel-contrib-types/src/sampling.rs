@@ -0,0 +1,256 @@
+use nalgebra_glm::{Vec2, Vec3};
+
+use crate::PixelContribColorMapDescriptor;
+
+/// The filter used for looking up the pixel contribution of a camera direction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum SamplingMode {
+    /// Returns the value of the nearest texel.
+    #[default]
+    Nearest,
+
+    /// Interpolates bilinearly between the four surrounding texels.
+    Bilinear,
+
+    /// Interpolates between the 4x4 surrounding texels using Catmull-Rom splines. The result is
+    /// clamped to the range of the involved texels to avoid overshooting.
+    Bicubic,
+}
+
+impl SamplingMode {
+    /// Samples the map for the given camera direction vector. The neighborhood of the texels is
+    /// wrapped across the seams of the parameterization, see
+    /// [`PixelContribColorMapDescriptor::neighbor_index`].
+    ///
+    /// # Arguments
+    /// * `descriptor` - The descriptor of the map to sample.
+    /// * `dir` - The camera direction vector to the object.
+    /// * `value_at` - Returns the value of the map for the given index.
+    pub fn sample<F>(
+        &self,
+        descriptor: &PixelContribColorMapDescriptor,
+        dir: Vec3,
+        value_at: F,
+    ) -> f32
+    where
+        F: Fn(usize) -> f32,
+    {
+        match self {
+            SamplingMode::Nearest => value_at(descriptor.index_from_camera_dir(dir)),
+            SamplingMode::Bilinear => {
+                let (p, x, y, fx, fy) = split_texel(descriptor, dir);
+                let fetch =
+                    |dx: isize, dy: isize| value_at(descriptor.neighbor_index(p, x + dx, y + dy));
+
+                let v0 = fetch(0, 0) * (1.0 - fx) + fetch(1, 0) * fx;
+                let v1 = fetch(0, 1) * (1.0 - fx) + fetch(1, 1) * fx;
+
+                v0 * (1.0 - fy) + v1 * fy
+            }
+            SamplingMode::Bicubic => {
+                let (p, x, y, fx, fy) = split_texel(descriptor, dir);
+                let wx = catmull_rom_weights(fx);
+                let wy = catmull_rom_weights(fy);
+
+                let mut result = 0f32;
+                let mut min = f32::MAX;
+                let mut max = f32::MIN;
+                for (j, wy) in wy.iter().enumerate() {
+                    for (i, wx) in wx.iter().enumerate() {
+                        let index =
+                            descriptor.neighbor_index(p, x + i as isize - 1, y + j as isize - 1);
+                        let value = value_at(index);
+
+                        result += value * wx * wy;
+                        min = min.min(value);
+                        max = max.max(value);
+                    }
+                }
+
+                result.clamp(min, max)
+            }
+        }
+    }
+}
+
+/// Returns the continuous texel position of the given camera direction together with the
+/// integer texel position of the upper left texel and the fractional offsets to it.
+///
+/// # Arguments
+/// * `descriptor` - The descriptor of the map to sample.
+/// * `dir` - The camera direction vector to the object.
+fn split_texel(
+    descriptor: &PixelContribColorMapDescriptor,
+    dir: Vec3,
+) -> (Vec2, isize, isize, f32, f32) {
+    let p = descriptor.texel_from_camera_dir(dir);
+    let (x, y) = (p.x.floor(), p.y.floor());
+
+    (p, x as isize, y as isize, p.x - x, p.y - y)
+}
+
+/// Returns the Catmull-Rom weights of the four texels around the given fractional offset.
+///
+/// # Arguments
+/// * `t` - The fractional offset between the second and third texel.
+fn catmull_rom_weights(t: f32) -> [f32; 4] {
+    let t2 = t * t;
+    let t3 = t2 * t;
+
+    [
+        0.5 * (-t3 + 2.0 * t2 - t),
+        0.5 * (3.0 * t3 - 5.0 * t2 + 2.0),
+        0.5 * (-3.0 * t3 + 4.0 * t2 + t),
+        0.5 * (t3 - t2),
+    ]
+}
+
+#[cfg(test)]
+mod test {
+    use nalgebra_glm::{dot, normalize};
+
+    use crate::SphereParameterization;
+
+    use super::*;
+
+    fn create_values(descriptor: &PixelContribColorMapDescriptor) -> Vec<f32> {
+        (0..descriptor.num_values())
+            .map(|i| smooth_function(descriptor.camera_dir_from_index(i)))
+            .collect()
+    }
+
+    fn smooth_function(dir: Vec3) -> f32 {
+        0.5 + 0.3 * dir.x - 0.1 * dir.y + 0.2 * dir.z * dir.x
+    }
+
+    #[test]
+    fn test_neighbor_index_octahedral() {
+        let descriptor = PixelContribColorMapDescriptor::new(32, 0.0);
+        let n = descriptor.size() as isize;
+        let max_angle = 4.0 * std::f32::consts::PI / n as f32;
+
+        for y in -2..n + 2 {
+            for x in -2..n + 2 {
+                let (cx, cy) = (x.clamp(0, n - 1), y.clamp(0, n - 1));
+                let anchor = Vec2::new(cx as f32, cy as f32);
+
+                let index = descriptor.neighbor_index(anchor, x, y);
+                assert!(index < descriptor.num_values());
+
+                // the wrapped texel must be close to the neighboring texel inside the map
+                let inner = descriptor.neighbor_index(anchor, cx, cy);
+                let d0 = descriptor.camera_dir_from_index(index);
+                let d1 = descriptor.camera_dir_from_index(inner);
+
+                let angle = dot(&d0, &d1).clamp(-1.0, 1.0).acos();
+                assert!(angle <= max_angle, "({}, {}): {}", x, y, angle);
+            }
+        }
+
+        // the corners are wrapped onto the diagonally opposite corners
+        let corner = Vec2::new(0.0, 0.0);
+        assert_eq!(
+            descriptor.neighbor_index(corner, -1, -1),
+            descriptor.num_values() - 1
+        );
+        let corner = Vec2::new(n as f32 - 1.0, n as f32 - 1.0);
+        assert_eq!(descriptor.neighbor_index(corner, n, n), 0);
+    }
+
+    #[test]
+    fn test_sampling_parameterizations() {
+        for parameterization in [
+            SphereParameterization::Octahedral,
+            SphereParameterization::HemiOctahedral,
+            SphereParameterization::EqualArea,
+            SphereParameterization::CubeMap,
+        ] {
+            let descriptor = PixelContribColorMapDescriptor::new_with_parameterization(
+                48,
+                0.0,
+                parameterization,
+            );
+            let values = create_values(&descriptor);
+            let value_at = |i: usize| values[i];
+
+            let mut errors = [0f32; 2];
+            for i in 0..5000 {
+                let t = i as f32 * 0.37;
+                let s = (t * 0.13).sin();
+                let dir = Vec3::new(t.cos() * s, t.sin() * s, (t * 0.13).cos());
+
+                // the hemi-octahedral map only stores the upper hemisphere
+                let dir = if parameterization == SphereParameterization::HemiOctahedral {
+                    Vec3::new(dir.x, dir.y, dir.z.abs())
+                } else {
+                    dir
+                };
+
+                let expected = smooth_function(dir);
+                for (mode, error) in [SamplingMode::Nearest, SamplingMode::Bilinear]
+                    .iter()
+                    .zip(errors.iter_mut())
+                {
+                    let v = mode.sample(&descriptor, dir, value_at);
+                    *error = error.max((v - expected).abs());
+                }
+            }
+
+            assert!(
+                errors[1] < errors[0],
+                "{:?}: {:?}",
+                parameterization,
+                errors
+            );
+        }
+    }
+
+    #[test]
+    fn test_sampling_modes() {
+        let descriptor = PixelContribColorMapDescriptor::new(64, 0.0);
+        let values = create_values(&descriptor);
+        let value_at = |i: usize| values[i];
+
+        // all modes reproduce the values at the texel centers
+        for i in [0, 17, 1000, descriptor.num_values() - 1] {
+            let dir = descriptor.camera_dir_from_index(i);
+            for mode in [
+                SamplingMode::Nearest,
+                SamplingMode::Bilinear,
+                SamplingMode::Bicubic,
+            ] {
+                let v = mode.sample(&descriptor, dir, value_at);
+                assert!((v - values[i]).abs() < 1e-4);
+            }
+        }
+
+        // the filtered lookups approximate the function better, also close to the seams of the
+        // lower hemisphere
+        let mut errors = [0f32; 3];
+        for i in 0..2000 {
+            let t = i as f32 * 0.01;
+            let dir = normalize(&Vec3::new(
+                t.cos(),
+                (t * 1.7).sin() * 0.05,
+                -0.3 - t.sin().abs(),
+            ));
+            let expected = smooth_function(dir);
+
+            for (mode, error) in [
+                SamplingMode::Nearest,
+                SamplingMode::Bilinear,
+                SamplingMode::Bicubic,
+            ]
+            .iter()
+            .zip(errors.iter_mut())
+            {
+                let v = mode.sample(&descriptor, dir, value_at);
+                *error = error.max((v - expected).abs());
+            }
+        }
+
+        assert!(errors[1] < 0.5 * errors[0], "{:?}", errors);
+        assert!(errors[1] < 5.5e-3, "{:?}", errors);
+        assert!(errors[2] < 5e-3, "{:?}", errors);
+    }
+}
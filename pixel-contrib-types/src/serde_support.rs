@@ -0,0 +1,338 @@
+use std::fmt;
+
+use serde::{
+    de::{self, SeqAccess, Visitor},
+    ser::SerializeStruct,
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+use crate::{
+    mipmap, CameraFrustum, PixelContribColorMapDescriptor, PixelContributionMap,
+    PixelContributionMaps, PixelContributionMetadata, SphereParameterization,
+};
+
+/// Serde support for the descriptor and the map types. Requires the `serde` feature.
+///
+/// The values of the maps are serialized as bytes of little-endian 32-bit floats, which keeps
+/// binary formats like bincode or MessagePack compact. The number of values and the mip chain
+/// are validated against the size of the map when deserializing.
+impl Serialize for PixelContribColorMapDescriptor {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("PixelContribColorMapDescriptor", 5)?;
+        s.serialize_field("size", &self.size())?;
+        s.serialize_field("camera_angle", &self.camera_angle())?;
+        s.serialize_field("parameterization", &self.parameterization())?;
+        s.serialize_field("frustum", &Some(self.frustum()))?;
+        s.serialize_field("distance", &self.distance())?;
+        s.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for PixelContribColorMapDescriptor {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(rename = "PixelContribColorMapDescriptor")]
+        struct RawDescriptor {
+            size: usize,
+            camera_angle: f32,
+            #[serde(default)]
+            parameterization: SphereParameterization,
+            #[serde(default)]
+            frustum: Option<CameraFrustum>,
+            #[serde(default)]
+            distance: f32,
+        }
+
+        let raw = RawDescriptor::deserialize(deserializer)?;
+        if !raw.camera_angle.is_finite() || raw.camera_angle < 0.0 {
+            return Err(de::Error::custom(format!(
+                "invalid camera angle {}",
+                raw.camera_angle
+            )));
+        }
+
+        if !raw.distance.is_finite() || (raw.distance != 0.0 && raw.distance <= 1.0) {
+            return Err(de::Error::custom(format!(
+                "invalid camera distance {}",
+                raw.distance
+            )));
+        }
+
+        let descriptor =
+            Self::new_with_parameterization(raw.size, raw.camera_angle, raw.parameterization)
+                .with_distance(raw.distance);
+
+        // descriptors without a frustum use the quadratic symmetric frustum of the camera angle
+        match raw.frustum {
+            Some(frustum) if !frustum.is_valid() => {
+                Err(de::Error::custom("invalid camera frustum"))
+            }
+            Some(frustum) => Ok(descriptor.with_frustum(frustum)),
+            None => Ok(descriptor),
+        }
+    }
+}
+
+impl Serialize for PixelContributionMap {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("PixelContributionMap", 3)?;
+        s.serialize_field("descriptor", &self.descriptor)?;
+        s.serialize_field("values", &Values(&self.pixel_contrib))?;
+        s.serialize_field("mip_levels", &self.mip_levels)?;
+        s.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for PixelContributionMap {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(rename = "PixelContributionMap")]
+        struct RawMap {
+            descriptor: PixelContribColorMapDescriptor,
+            values: ValueBuf,
+            #[serde(default)]
+            mip_levels: Vec<PixelContributionMap>,
+        }
+
+        let raw = RawMap::deserialize(deserializer)?;
+        let descriptor = raw.descriptor;
+        let pixel_contrib = raw.values.0;
+        if pixel_contrib.len() != descriptor.num_values() {
+            return Err(de::Error::custom(format!(
+                "expected {} values for map size {}, but got {}",
+                descriptor.num_values(),
+                descriptor.size(),
+                pixel_contrib.len()
+            )));
+        }
+
+        // the mip chain must consist of the successively halved levels of the map
+        let num_levels = mipmap::mip_sizes(descriptor.size()).count();
+        if raw.mip_levels.len() > num_levels {
+            return Err(de::Error::custom(format!(
+                "invalid number of mip levels {} for map size {}",
+                raw.mip_levels.len(),
+                descriptor.size()
+            )));
+        }
+
+        for (level, size) in raw
+            .mip_levels
+            .iter()
+            .zip(mipmap::mip_sizes(descriptor.size()))
+        {
+            let expected = descriptor.with_size(size);
+            if level.descriptor != expected || !level.mip_levels.is_empty() {
+                return Err(de::Error::custom(format!(
+                    "invalid mip level of size {} for map size {}",
+                    level.descriptor.size(),
+                    descriptor.size()
+                )));
+            }
+        }
+
+        Ok(Self {
+            descriptor,
+            pixel_contrib,
+            mip_levels: raw.mip_levels,
+        })
+    }
+}
+
+impl Serialize for PixelContributionMaps {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("PixelContributionMaps", 2)?;
+        s.serialize_field("metadata", &self.metadata)?;
+        s.serialize_field("maps", &self.maps)?;
+        s.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for PixelContributionMaps {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(rename = "PixelContributionMaps")]
+        struct RawMaps {
+            #[serde(default)]
+            metadata: PixelContributionMetadata,
+            maps: Vec<PixelContributionMap>,
+        }
+
+        let raw = RawMaps::deserialize(deserializer)?;
+
+        let mut result = Self::from_maps(raw.maps);
+        result.metadata = raw.metadata;
+
+        Ok(result)
+    }
+}
+
+/// The values of a map serialized as bytes of little-endian 32-bit floats.
+struct Values<'a>(&'a [f32]);
+
+impl Serialize for Values<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let bytes: Vec<u8> = self.0.iter().flat_map(|v| v.to_le_bytes()).collect();
+        serializer.serialize_bytes(&bytes)
+    }
+}
+
+/// The deserialized values of a map. Accepts byte buffers as well as sequences of bytes, which
+/// is how self-describing formats like JSON represent byte buffers.
+struct ValueBuf(Vec<f32>);
+
+impl<'de> Deserialize<'de> for ValueBuf {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_bytes(ValueBufVisitor)
+    }
+}
+
+struct ValueBufVisitor;
+
+impl ValueBufVisitor {
+    /// Decodes the given bytes into little-endian 32-bit floats.
+    ///
+    /// # Arguments
+    /// * `bytes` - The bytes to decode.
+    fn decode<E: de::Error>(bytes: &[u8]) -> Result<ValueBuf, E> {
+        if !bytes.len().is_multiple_of(4) {
+            return Err(E::custom(format!(
+                "the number of value bytes {} is not a multiple of 4",
+                bytes.len()
+            )));
+        }
+
+        Ok(ValueBuf(
+            bytes
+                .chunks_exact(4)
+                .map(|v| f32::from_le_bytes([v[0], v[1], v[2], v[3]]))
+                .collect(),
+        ))
+    }
+}
+
+impl<'de> Visitor<'de> for ValueBufVisitor {
+    type Value = ValueBuf;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("bytes of little-endian 32-bit floats")
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+        Self::decode(v)
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> Result<Self::Value, E> {
+        Self::decode(&v)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or_default().min(1 << 20));
+        while let Some(b) = seq.next_element::<u8>()? {
+            bytes.push(b);
+        }
+
+        Self::decode(&bytes)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn create_maps() -> PixelContributionMaps {
+        let mut maps = Vec::new();
+        for (i, parameterization) in [
+            SphereParameterization::Octahedral,
+            SphereParameterization::CubeMap,
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let descriptor = PixelContribColorMapDescriptor::new_with_parameterization(
+                7 + i * 3,
+                1.2 - i as f32,
+                parameterization,
+            );
+
+            let mut map = PixelContributionMap::new(descriptor);
+            map.pixel_contrib
+                .iter_mut()
+                .enumerate()
+                .for_each(|(j, p)| *p = (j as f32 * 0.17).cos().abs());
+            map.generate_mip_chain();
+            maps.push(map);
+        }
+
+        let mut maps = PixelContributionMaps::from_maps(maps);
+        maps.metadata_mut().set_source_model("models/duck.glb");
+        maps.metadata_mut().set_frame_size(256);
+
+        maps
+    }
+
+    #[test]
+    fn test_serde_round_trip() {
+        let maps = create_maps();
+
+        let data = bincode::serialize(&maps).unwrap();
+        let maps2: PixelContributionMaps = bincode::deserialize(&data).unwrap();
+        assert!(maps == maps2);
+
+        // the values are stored as bytes, s.t. the overhead is small
+        let num_values: usize = maps
+            .maps
+            .iter()
+            .flat_map(|m| std::iter::once(m).chain(m.mip_levels.iter()))
+            .map(|m| m.pixel_contrib.len())
+            .sum();
+        assert!(data.len() < num_values * 4 + 768);
+
+        let json = serde_json::to_string(&maps).unwrap();
+        let maps2: PixelContributionMaps = serde_json::from_str(&json).unwrap();
+        assert!(maps == maps2);
+
+        let descriptor = maps.maps[0].descriptor;
+        let json = serde_json::to_string(&descriptor).unwrap();
+        assert!(json.contains("\"parameterization\":\"cube_map\""));
+        let descriptor2: PixelContribColorMapDescriptor = serde_json::from_str(&json).unwrap();
+        assert_eq!(descriptor, descriptor2);
+
+        let frustum = CameraFrustum::off_axis(0.2, 0.6, 0.3, 0.4);
+        let descriptor =
+            PixelContribColorMapDescriptor::new_with_frustum(16, frustum, Default::default())
+                .with_distance(1.5);
+        let json = serde_json::to_string(&descriptor).unwrap();
+        let descriptor2: PixelContribColorMapDescriptor = serde_json::from_str(&json).unwrap();
+        assert_eq!(descriptor, descriptor2);
+    }
+
+    #[test]
+    fn test_serde_validation() {
+        let mut map = PixelContributionMap::new(PixelContribColorMapDescriptor::new(4, 0.5));
+        map.pixel_contrib.pop();
+        let data = bincode::serialize(&map).unwrap();
+        assert!(bincode::deserialize::<PixelContributionMap>(&data).is_err());
+
+        // value bytes which are not a multiple of 4
+        let json = r#"{"descriptor":{"size":1,"camera_angle":0.5},"values":[0,0,0]}"#;
+        assert!(serde_json::from_str::<PixelContributionMap>(json).is_err());
+
+        let json = r#"{"descriptor":{"size":1,"camera_angle":0.5},"values":[0,0,128,63]}"#;
+        let map: PixelContributionMap = serde_json::from_str(json).unwrap();
+        assert_eq!(map.pixel_contrib, vec![1.0]);
+
+        let json = r#"{"size":1,"camera_angle":-1.0}"#;
+        assert!(serde_json::from_str::<PixelContribColorMapDescriptor>(json).is_err());
+
+        let json = r#"{"size":1,"camera_angle":0.5,"distance":0.5}"#;
+        assert!(serde_json::from_str::<PixelContribColorMapDescriptor>(json).is_err());
+
+        // mip levels with the wrong size
+        let mut map = PixelContributionMap::new(PixelContribColorMapDescriptor::new(8, 0.5));
+        map.generate_mip_chain();
+        map.mip_levels.swap(0, 1);
+        let data = bincode::serialize(&map).unwrap();
+        assert!(bincode::deserialize::<PixelContributionMap>(&data).is_err());
+    }
+}
@@ -0,0 +1,671 @@
+use std::{
+    f64::consts::PI,
+    io::{BufReader, Read, Write},
+    path::Path,
+    sync::Arc,
+};
+
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use nalgebra_glm::Vec3;
+
+use crate::{
+    interpolation, validation, AngleInterpolator, CameraFrustum, Error,
+    PixelContribColorMapDescriptor, PixelContributionMap, PixelContributionMaps, ReadOptions,
+    Result, SphereParameterization, TangentAngleInterpolator, PIXEL_CONTRIBUTION_MAP_VERSION,
+};
+
+/// The current version of the spherical harmonics file format. The descriptors of the
+/// approximated maps are stored in the layout of the current pixel contribution map file format.
+const SPHERICAL_HARMONICS_VERSION: u32 = 1;
+const SPHERICAL_HARMONICS_IDENTIFIER: [u8; 4] = *b"PCSH";
+
+/// The maximal number of bands supported for the projection.
+pub const MAX_SPHERICAL_HARMONICS_BANDS: usize = 32;
+
+/// The error of the spherical harmonics approximation w.r.t the source map.
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub struct SphericalHarmonicsFitError {
+    /// The root mean squared error weighted by the solid angle of each value.
+    pub rmse: f32,
+
+    /// The maximal absolute error over all values.
+    pub max_error: f32,
+}
+
+/// The approximation of a pixel contribution map by real spherical harmonics.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SphericalHarmonicsMap {
+    /// The descriptor of the approximated map.
+    descriptor: PixelContribColorMapDescriptor,
+
+    /// The number of bands, i.e., the coefficients of the bands 0..bands are stored.
+    bands: usize,
+
+    /// The coefficients ordered by band, i.e., the coefficient of band l and order m is stored
+    /// at index l * (l + 1) + m.
+    coefficients: Vec<f32>,
+}
+
+impl SphericalHarmonicsMap {
+    /// Creates a new approximation from the given coefficients.
+    ///
+    /// # Arguments
+    /// * `descriptor` - The descriptor of the approximated map.
+    /// * `coefficients` - The coefficients ordered by band. The number of coefficients must be
+    ///   the square of the number of bands.
+    pub fn new(descriptor: PixelContribColorMapDescriptor, coefficients: Vec<f32>) -> Result<Self> {
+        let bands = (coefficients.len() as f64).sqrt() as usize;
+        if bands == 0 || bands * bands != coefficients.len() {
+            return Err(Error::InvalidArgument(format!(
+                "Invalid number of spherical harmonics coefficients {}",
+                coefficients.len()
+            )));
+        }
+
+        if bands > MAX_SPHERICAL_HARMONICS_BANDS {
+            return Err(Error::InvalidArgument(format!(
+                "At most {} bands are supported, but got {}",
+                MAX_SPHERICAL_HARMONICS_BANDS, bands
+            )));
+        }
+
+        Ok(Self {
+            descriptor,
+            bands,
+            coefficients,
+        })
+    }
+
+    /// Projects the given pixel contribution map onto spherical harmonics with the given number
+    /// of bands. Each value of the map is weighted by the solid angle it covers. Maps of the
+    /// hemi-octahedral parameterization are projected as function mirrored at the horizon.
+    ///
+    /// # Arguments
+    /// * `map` - The pixel contribution map to approximate.
+    /// * `bands` - The number of bands used for the approximation.
+    pub fn fit(map: &PixelContributionMap, bands: usize) -> Result<Self> {
+        if bands == 0 || bands > MAX_SPHERICAL_HARMONICS_BANDS {
+            return Err(Error::InvalidArgument(format!(
+                "The number of bands must be in the range [1, {}], but got {}",
+                MAX_SPHERICAL_HARMONICS_BANDS, bands
+            )));
+        }
+
+        let descriptor = &map.descriptor;
+        let mirrored = descriptor.parameterization() == SphereParameterization::HemiOctahedral;
+
+        let mut coefficients = vec![0f32; bands * bands];
+        let mut basis = vec![0f32; bands * bands];
+
+        let mut project = |dir: Vec3, weight: f32| {
+            evaluate_basis(bands, dir, &mut basis);
+            for (c, y) in coefficients.iter_mut().zip(basis.iter()) {
+                *c += y * weight;
+            }
+        };
+
+        for (index, value) in map.pixel_contrib.iter().enumerate() {
+            let dir = descriptor.camera_dir_from_index(index);
+            let weight = value * descriptor.solid_angle_from_index(index);
+
+            project(dir, weight);
+            if mirrored {
+                project(Vec3::new(dir.x, dir.y, -dir.z), weight);
+            }
+        }
+
+        Ok(Self {
+            descriptor: *descriptor,
+            bands,
+            coefficients,
+        })
+    }
+
+    /// Returns the descriptor of the approximated map.
+    #[inline]
+    pub fn descriptor(&self) -> &PixelContribColorMapDescriptor {
+        &self.descriptor
+    }
+
+    /// Returns the camera angle of the approximated map in radians.
+    #[inline]
+    pub fn camera_angle(&self) -> f32 {
+        self.descriptor.camera_angle()
+    }
+
+    /// Returns the number of bands of the approximation.
+    #[inline]
+    pub fn bands(&self) -> usize {
+        self.bands
+    }
+
+    /// Returns the coefficients ordered by band.
+    #[inline]
+    pub fn coefficients(&self) -> &[f32] {
+        &self.coefficients
+    }
+
+    /// Evaluates the approximated pixel contribution for the given camera direction vector.
+    /// Note that the approximation may ring, i.e., the result may leave the range [0, 1].
+    ///
+    /// # Arguments
+    /// * `dir` - The camera direction vector to the object.
+    pub fn get_pixel_contrib_for_camera_dir(&self, dir: Vec3) -> f32 {
+        let mut basis = vec![0f32; self.coefficients.len()];
+        evaluate_basis(self.bands, dir, &mut basis);
+
+        self.coefficients
+            .iter()
+            .zip(basis.iter())
+            .map(|(c, y)| c * y)
+            .sum()
+    }
+
+    /// Computes the error of the approximation w.r.t the given source map.
+    ///
+    /// # Arguments
+    /// * `map` - The source map the approximation has been fitted to.
+    pub fn fit_error(&self, map: &PixelContributionMap) -> SphericalHarmonicsFitError {
+        let descriptor = &map.descriptor;
+
+        let mut sum_sqr_error = 0f32;
+        let mut sum_weights = 0f32;
+        let mut max_error = 0f32;
+        for (index, value) in map.pixel_contrib.iter().enumerate() {
+            let dir = descriptor.camera_dir_from_index(index);
+            let weight = descriptor.solid_angle_from_index(index);
+            let error = (self.get_pixel_contrib_for_camera_dir(dir) - value).abs();
+
+            sum_sqr_error += error * error * weight;
+            sum_weights += weight;
+            max_error = max_error.max(error);
+        }
+
+        let rmse = if sum_weights > 0.0 {
+            (sum_sqr_error / sum_weights).sqrt()
+        } else {
+            0.0
+        };
+
+        SphericalHarmonicsFitError { rmse, max_error }
+    }
+
+    /// Evaluates the approximation for each value of a new map with the given descriptor.
+    ///
+    /// # Arguments
+    /// * `descriptor` - The descriptor of the map to create.
+    pub fn to_map(&self, descriptor: PixelContribColorMapDescriptor) -> PixelContributionMap {
+        let mut map = PixelContributionMap::new(descriptor);
+        map.pixel_contrib
+            .iter_mut()
+            .enumerate()
+            .for_each(|(index, p)| {
+                *p = self.get_pixel_contrib_for_camera_dir(descriptor.camera_dir_from_index(index))
+            });
+
+        map
+    }
+}
+
+/// The spherical harmonics approximations of the pixel contribution maps for different camera
+/// configurations. This is a compact alternative to [`PixelContributionMaps`] with only a few
+/// dozen floats per map, which is queried the same way.
+#[derive(Clone)]
+pub struct SphericalHarmonicsMaps {
+    maps: Vec<SphericalHarmonicsMap>,
+
+    /// The interpolator used for camera angles between the angles of the maps.
+    interpolator: Arc<dyn AngleInterpolator>,
+}
+
+impl PartialEq for SphericalHarmonicsMaps {
+    fn eq(&self, other: &Self) -> bool {
+        self.maps == other.maps
+    }
+}
+
+impl std::fmt::Debug for SphericalHarmonicsMaps {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SphericalHarmonicsMaps")
+            .field("maps", &self.maps)
+            .field("interpolator", &self.interpolator.name())
+            .finish()
+    }
+}
+
+impl Default for SphericalHarmonicsMaps {
+    fn default() -> Self {
+        Self::from_maps(Vec::new())
+    }
+}
+
+impl SphericalHarmonicsMaps {
+    /// Creates a new object from the given approximations.
+    ///
+    /// # Arguments
+    /// * `maps` - The spherical harmonics approximations.
+    pub fn from_maps(mut maps: Vec<SphericalHarmonicsMap>) -> Self {
+        maps.sort_by(|m1, m2| m1.descriptor.cmp_camera(&m2.descriptor));
+
+        Self {
+            maps,
+            interpolator: Arc::new(TangentAngleInterpolator),
+        }
+    }
+
+    /// Projects all given pixel contribution maps onto spherical harmonics with the given number
+    /// of bands. The approximations keep the angle interpolator of the given maps.
+    ///
+    /// # Arguments
+    /// * `maps` - The pixel contribution maps to approximate.
+    /// * `bands` - The number of bands used for the approximation.
+    pub fn fit(maps: &PixelContributionMaps, bands: usize) -> Result<Self> {
+        let sh_maps = maps
+            .get_maps()
+            .iter()
+            .map(|m| SphericalHarmonicsMap::fit(m, bands))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            interpolator: maps.interpolator.clone(),
+            ..Self::from_maps(sh_maps)
+        })
+    }
+
+    /// Returns the approximations sorted in ascending order w.r.t their camera angles and, for
+    /// equal angles, their camera distances.
+    #[inline]
+    pub fn get_maps(&self) -> &[SphericalHarmonicsMap] {
+        &self.maps
+    }
+
+    /// Returns the interpolator used for camera angles between the angles of the maps.
+    #[inline]
+    pub fn angle_interpolator(&self) -> &dyn AngleInterpolator {
+        self.interpolator.as_ref()
+    }
+
+    /// Sets the interpolator used for camera angles between the angles of the maps.
+    /// By default, the [`TangentAngleInterpolator`] is used.
+    ///
+    /// # Arguments
+    /// * `interpolator` - The new angle interpolator.
+    pub fn set_angle_interpolator<I: AngleInterpolator + 'static>(&mut self, interpolator: I) {
+        self.interpolator = Arc::new(interpolator);
+    }
+
+    /// Returns the approximated pixel contribution for the given camera direction vector. The
+    /// camera is assumed to have a quadratic symmetric frustum with the given angle as field of
+    /// view, see [`PixelContributionMaps::sample_pixel_contrib_for_camera_dir`].
+    ///
+    /// # Arguments
+    /// * `dir` - The camera direction vector to the object.
+    /// * `angle` - The angle of the camera to return the contribution values for.
+    pub fn get_pixel_contrib_for_camera_dir(&self, dir: Vec3, angle: f32) -> f32 {
+        self.get_pixel_contrib_for_camera(dir, &CameraFrustum::square(angle), angle)
+    }
+
+    /// Returns the approximated pixel contribution for the given camera direction vector seen by
+    /// a camera with the given frustum. The approximations are selected and interpolated like
+    /// the maps, see [`PixelContributionMaps::sample_pixel_contrib_for_camera`].
+    ///
+    /// # Arguments
+    /// * `dir` - The camera direction vector to the object.
+    /// * `frustum` - The frustum of the camera.
+    /// * `sphere_angle` - The angle under which the bounding sphere is seen from the camera.
+    pub fn get_pixel_contrib_for_camera(
+        &self,
+        dir: Vec3,
+        frustum: &CameraFrustum,
+        sphere_angle: f32,
+    ) -> f32 {
+        interpolation::interpolate_camera(
+            self.interpolator.as_ref(),
+            self.maps.len(),
+            &|i| self.maps[i].descriptor,
+            frustum,
+            sphere_angle,
+            &|i| self.maps[i].get_pixel_contrib_for_camera_dir(dir),
+        )
+    }
+
+    /// Writes the approximations to the given writer as binary file.
+    ///
+    /// # Arguments
+    /// * `writer` - The writer to which the approximations should be written.
+    pub fn write_writer<W: Write>(&self, writer: &mut W) -> Result<()> {
+        writer.write_all(&SPHERICAL_HARMONICS_IDENTIFIER)?;
+        writer.write_u32::<LittleEndian>(SPHERICAL_HARMONICS_VERSION)?;
+
+        writer.write_u32::<LittleEndian>(self.maps.len() as u32)?;
+        for map in self.maps.iter() {
+            map.descriptor.write_writer(writer)?;
+            writer.write_u32::<LittleEndian>(map.bands as u32)?;
+
+            for c in map.coefficients.iter() {
+                writer.write_f32::<LittleEndian>(*c)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reads the approximations from the given path.
+    ///
+    /// # Arguments
+    /// * `path` - The path from which the approximations should be read.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file = std::fs::File::open(path)?;
+        Self::from_reader(&mut BufReader::new(file))
+    }
+
+    /// Reads the approximations from the given reader.
+    ///
+    /// # Arguments
+    /// * `reader` - The reader from which the approximations should be read.
+    pub fn from_reader<R: Read>(reader: &mut R) -> Result<Self> {
+        let mut identifier = [0u8; 4];
+        reader.read_exact(&mut identifier)?;
+        if identifier != SPHERICAL_HARMONICS_IDENTIFIER {
+            return Err(Error::InvalidIdentifier);
+        }
+
+        let version = reader.read_u32::<LittleEndian>()?;
+        if version != SPHERICAL_HARMONICS_VERSION {
+            return Err(Error::UnsupportedVersion(version));
+        }
+
+        let num_maps = reader.read_u32::<LittleEndian>()? as usize;
+
+        let mut maps = Vec::new();
+        for _ in 0..num_maps {
+            let descriptor = PixelContribColorMapDescriptor::from_reader(
+                reader,
+                PIXEL_CONTRIBUTION_MAP_VERSION,
+            )?;
+            ReadOptions::default().check_descriptor(&descriptor)?;
+
+            let bands = reader.read_u32::<LittleEndian>()? as usize;
+            if bands == 0 || bands > MAX_SPHERICAL_HARMONICS_BANDS {
+                return Err(Error::IO(format!("Invalid number of bands {}", bands)));
+            }
+
+            let mut coefficients = vec![0f32; bands * bands];
+            reader.read_f32_into::<LittleEndian>(&mut coefficients)?;
+
+            maps.push(SphericalHarmonicsMap {
+                descriptor,
+                bands,
+                coefficients,
+            });
+        }
+
+        let sh_maps = Self::from_maps(maps);
+        validation::check_unique_angles(sh_maps.maps.iter().map(|m| &m.descriptor))?;
+
+        Ok(sh_maps)
+    }
+}
+
+/// Evaluates the real spherical harmonics basis functions of the given number of bands for the
+/// given direction.
+///
+/// # Arguments
+/// * `bands` - The number of bands.
+/// * `dir` - The direction to evaluate the basis functions for.
+/// * `out` - The output for the bands * bands basis function values.
+fn evaluate_basis(bands: usize, dir: Vec3, out: &mut [f32]) {
+    let dir = dir.normalize();
+    let cos_theta = dir.z.clamp(-1.0, 1.0) as f64;
+    let phi = (dir.y as f64).atan2(dir.x as f64);
+
+    for l in 0..bands {
+        for m in 0..=l {
+            let k = normalization(l, m);
+            let p = associated_legendre(l, m, cos_theta);
+
+            if m == 0 {
+                out[l * (l + 1)] = (k * p) as f32;
+            } else {
+                let v = std::f64::consts::SQRT_2 * k * p;
+                out[l * (l + 1) + m] = (v * (m as f64 * phi).cos()) as f32;
+                out[l * (l + 1) - m] = (v * (m as f64 * phi).sin()) as f32;
+            }
+        }
+    }
+}
+
+/// Returns the normalization constant of the spherical harmonic of band l and order m.
+///
+/// # Arguments
+/// * `l` - The band.
+/// * `m` - The absolute order.
+fn normalization(l: usize, m: usize) -> f64 {
+    // (l - m)! / (l + m)!
+    let ratio = ((l - m + 1)..=(l + m)).fold(1f64, |r, i| r / i as f64);
+
+    ((2 * l + 1) as f64 / (4.0 * PI) * ratio).sqrt()
+}
+
+/// Evaluates the associated Legendre polynomial of band l and order m at x using the standard
+/// recurrence relations.
+///
+/// # Arguments
+/// * `l` - The band.
+/// * `m` - The order with m <= l.
+/// * `x` - The position in the range [-1, 1].
+fn associated_legendre(l: usize, m: usize, x: f64) -> f64 {
+    let mut pmm = 1f64;
+    if m > 0 {
+        let somx2 = ((1.0 - x) * (1.0 + x)).sqrt();
+        let mut fact = 1f64;
+        for _ in 0..m {
+            pmm *= -fact * somx2;
+            fact += 2.0;
+        }
+    }
+
+    if l == m {
+        return pmm;
+    }
+
+    let mut pmmp1 = x * (2 * m + 1) as f64 * pmm;
+    if l == m + 1 {
+        return pmmp1;
+    }
+
+    let mut pll = 0f64;
+    for ll in (m + 2)..=l {
+        pll = ((2 * ll - 1) as f64 * x * pmmp1 - (ll + m - 1) as f64 * pmm) / (ll - m) as f64;
+        pmm = pmmp1;
+        pmmp1 = pll;
+    }
+
+    pll
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+
+    fn create_map(
+        parameterization: SphereParameterization,
+        f: impl Fn(Vec3) -> f32,
+    ) -> PixelContributionMap {
+        let descriptor =
+            PixelContribColorMapDescriptor::new_with_parameterization(64, 0.5, parameterization);
+
+        let mut map = PixelContributionMap::new(descriptor);
+        map.pixel_contrib
+            .iter_mut()
+            .enumerate()
+            .for_each(|(i, p)| *p = f(descriptor.camera_dir_from_index(i)));
+
+        map
+    }
+
+    #[test]
+    fn test_basis_orthonormal() {
+        // integrate the products of the basis functions over the sphere
+        let bands = 4;
+        let descriptor = PixelContribColorMapDescriptor::new_with_parameterization(
+            64,
+            0.0,
+            SphereParameterization::EqualArea,
+        );
+
+        let n = bands * bands;
+        let mut products = vec![0f32; n * n];
+        let mut basis = vec![0f32; n];
+        for index in 0..descriptor.num_values() {
+            let weight = descriptor.solid_angle_from_index(index);
+            evaluate_basis(bands, descriptor.camera_dir_from_index(index), &mut basis);
+
+            for i in 0..n {
+                for j in 0..n {
+                    products[i * n + j] += basis[i] * basis[j] * weight;
+                }
+            }
+        }
+
+        for i in 0..n {
+            for j in 0..n {
+                let expected = if i == j { 1.0 } else { 0.0 };
+                assert!((products[i * n + j] - expected).abs() < 1e-2);
+            }
+        }
+    }
+
+    #[test]
+    fn test_fit_smooth_function() {
+        // a function of the first two bands must be represented almost exactly
+        let f = |dir: Vec3| 0.5 + 0.2 * dir.x - 0.1 * dir.z;
+
+        for parameterization in [
+            SphereParameterization::Octahedral,
+            SphereParameterization::EqualArea,
+            SphereParameterization::CubeMap,
+        ] {
+            let map = create_map(parameterization, f);
+            let sh = SphericalHarmonicsMap::fit(&map, 2).unwrap();
+            assert_eq!(sh.coefficients().len(), 4);
+
+            let error = sh.fit_error(&map);
+            assert!(error.rmse < 1e-2, "{:?}: {:?}", parameterization, error);
+            assert!(
+                error.max_error < 2e-2,
+                "{:?}: {:?}",
+                parameterization,
+                error
+            );
+        }
+
+        // more bands reduce the error of a sharper function
+        let f = |dir: Vec3| dir.x.abs();
+        let map = create_map(SphereParameterization::Octahedral, f);
+        let error2 = SphericalHarmonicsMap::fit(&map, 2).unwrap().fit_error(&map);
+        let error6 = SphericalHarmonicsMap::fit(&map, 6).unwrap().fit_error(&map);
+        assert!(error6.rmse < error2.rmse);
+
+        assert!(SphericalHarmonicsMap::fit(&map, 0).is_err());
+    }
+
+    #[test]
+    fn test_serialization() {
+        let data = include_bytes!("../../test_data/contrib_maps/duck_contrib_map.bin");
+        let maps = PixelContributionMaps::from_reader(&mut Cursor::new(data)).unwrap();
+        let maps = PixelContributionMaps::from_maps(maps.get_maps()[..2].to_vec());
+
+        let sh_maps = SphericalHarmonicsMaps::fit(&maps, 5).unwrap();
+        assert_eq!(sh_maps.get_maps().len(), maps.get_maps().len());
+
+        let mut buf = Vec::new();
+        sh_maps.write_writer(&mut buf).unwrap();
+        // the descriptor of the current map version has 33 bytes
+        assert_eq!(buf.len(), 12 + maps.get_maps().len() * (33 + 4 + 25 * 4));
+
+        let sh_maps2 = SphericalHarmonicsMaps::from_reader(&mut buf.as_slice()).unwrap();
+        assert_eq!(sh_maps, sh_maps2);
+
+        for (map, sh) in maps.get_maps().iter().zip(sh_maps2.get_maps()) {
+            let error = sh.fit_error(map);
+            assert!(error.rmse < 0.1, "{:?}", error);
+
+            let dir = Vec3::new(0.3, -0.5, 0.8);
+            let angle = map.descriptor.camera_angle();
+            assert_eq!(
+                sh_maps2.get_pixel_contrib_for_camera_dir(dir, angle),
+                sh.get_pixel_contrib_for_camera_dir(dir)
+            );
+        }
+
+        assert!(SphericalHarmonicsMaps::from_reader(&mut &buf[..buf.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_lookup_multiple_distances() {
+        let map = |descriptor: PixelContribColorMapDescriptor, value: f32| {
+            let mut map = PixelContributionMap::new(descriptor);
+            map.pixel_contrib.iter_mut().for_each(|p| *p = value);
+            map
+        };
+
+        let fitted = |angle: f32| PixelContribColorMapDescriptor::new(16, angle);
+        let wide = PixelContribColorMapDescriptor::new_with_frustum(
+            16,
+            CameraFrustum::perspective(0.5, 16.0 / 9.0),
+            SphereParameterization::Octahedral,
+        );
+
+        let mut maps = PixelContributionMaps::from_maps(vec![
+            map(fitted(0.5), 0.2),
+            map(fitted(1.0), 0.4),
+            map(fitted(0.5).with_distance(2.0), 0.6),
+            map(fitted(1.0).with_distance(2.0), 0.8),
+            map(wide, 0.9),
+        ]);
+        maps.set_angle_interpolator(crate::LinearAngleInterpolator);
+
+        // the descriptors survive the serialization, the interpolator is taken from the maps
+        let sh_maps = SphericalHarmonicsMaps::fit(&maps, 1).unwrap();
+        assert_eq!(sh_maps.angle_interpolator().name(), "Linear");
+
+        let mut buf = Vec::new();
+        sh_maps.write_writer(&mut buf).unwrap();
+        let mut sh_maps2 = SphericalHarmonicsMaps::from_reader(&mut buf.as_slice()).unwrap();
+        assert_eq!(sh_maps, sh_maps2);
+        sh_maps2.set_angle_interpolator(crate::LinearAngleInterpolator);
+
+        // constant maps are approximated exactly, thus, the lookups must match the maps
+        let dir = Vec3::new(0.3, -0.5, 0.8);
+        let near_angle = 2.0 * 0.5f32.asin();
+        for (frustum, sphere_angle) in [
+            (CameraFrustum::square(0.5), near_angle),
+            (CameraFrustum::square(0.75), near_angle),
+            (CameraFrustum::square(1.0), 0.8),
+            (CameraFrustum::square(1.0), 2.5),
+            (CameraFrustum::square(1.0), 0.1),
+            (CameraFrustum::perspective(0.5, 16.0 / 9.0), 0.5),
+        ] {
+            let expected = maps.sample_pixel_contrib_for_camera(
+                dir,
+                &frustum,
+                sphere_angle,
+                crate::SamplingMode::Nearest,
+            );
+
+            for sh in [&sh_maps, &sh_maps2] {
+                let p = sh.get_pixel_contrib_for_camera(dir, &frustum, sphere_angle);
+                assert!((p - expected).abs() < 1e-3, "{} != {}", p, expected);
+            }
+        }
+
+        for angle in [0.5, 0.75, 1.0] {
+            let expected = maps.get_pixel_contrib_for_camera_dir(dir, angle);
+            let p = sh_maps.get_pixel_contrib_for_camera_dir(dir, angle);
+            assert!((p - expected).abs() < 1e-3, "{} != {}", p, expected);
+        }
+    }
+}
@@ -0,0 +1,195 @@
+use nalgebra_glm::Vec3;
+
+use crate::PixelContributionMap;
+
+/// Statistical summary of the values of a pixel contribution map. All averages are weighted by
+/// the solid angle covered by each value, s.t. they represent averages over the sphere of camera
+/// directions instead of averages over the texels of the map.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PixelContributionStatistics {
+    /// The camera angle of the summarized map in radians.
+    pub camera_angle: f32,
+
+    /// The minimal pixel contribution value.
+    pub min: f32,
+
+    /// The camera direction with the minimal pixel contribution value.
+    pub argmin: Vec3,
+
+    /// The maximal pixel contribution value.
+    pub max: f32,
+
+    /// The camera direction with the maximal pixel contribution value.
+    pub argmax: Vec3,
+
+    /// The mean of the values weighted by the solid angle, i.e., the mean coverage over the
+    /// sphere of camera directions.
+    pub mean: f32,
+
+    /// The standard deviation of the values weighted by the solid angle.
+    pub std_dev: f32,
+
+    /// The total solid angle covered by the map in steradians.
+    pub solid_angle: f32,
+}
+
+impl PixelContributionStatistics {
+    /// Computes the statistical summary for the given map.
+    ///
+    /// # Arguments
+    /// * `map` - The map to summarize.
+    pub fn from_map(map: &PixelContributionMap) -> Self {
+        let descriptor = &map.descriptor;
+        if map.pixel_contrib.is_empty() {
+            return Self {
+                camera_angle: descriptor.camera_angle(),
+                min: 0.0,
+                argmin: Vec3::zeros(),
+                max: 0.0,
+                argmax: Vec3::zeros(),
+                mean: 0.0,
+                std_dev: 0.0,
+                solid_angle: 0.0,
+            };
+        }
+
+        let mut min = (f32::MAX, 0);
+        let mut max = (f32::MIN, 0);
+        let mut sum = 0f64;
+        let mut sum_sqr = 0f64;
+        let mut total_weight = 0f64;
+        for (i, v) in map.pixel_contrib.iter().enumerate() {
+            if *v < min.0 {
+                min = (*v, i);
+            }
+            if *v > max.0 {
+                max = (*v, i);
+            }
+
+            let w = descriptor.solid_angle_from_index(i) as f64;
+            sum += w * *v as f64;
+            sum_sqr += w * (*v as f64) * (*v as f64);
+            total_weight += w;
+        }
+
+        let (mean, variance) = if total_weight > 0.0 {
+            let mean = sum / total_weight;
+            (mean, (sum_sqr / total_weight - mean * mean).max(0.0))
+        } else {
+            (0.0, 0.0)
+        };
+
+        Self {
+            camera_angle: descriptor.camera_angle(),
+            min: min.0,
+            argmin: descriptor.camera_dir_from_index(min.1),
+            max: max.0,
+            argmax: descriptor.camera_dir_from_index(max.1),
+            mean: mean as f32,
+            std_dev: variance.sqrt() as f32,
+            solid_angle: total_weight as f32,
+        }
+    }
+}
+
+/// Returns the solid-angle-weighted percentile of the values of the given map, i.e., the
+/// smallest value s.t. the given percentage of the sphere of camera directions has a pixel
+/// contribution less than or equal to it.
+///
+/// # Arguments
+/// * `map` - The map whose values are queried.
+/// * `percentile` - The percentile in the range [0, 100]. Values outside are clamped.
+pub(crate) fn weighted_percentile(map: &PixelContributionMap, percentile: f32) -> f32 {
+    let mut values: Vec<(f32, f32)> = map
+        .pixel_contrib
+        .iter()
+        .enumerate()
+        .map(|(i, v)| (*v, map.descriptor.solid_angle_from_index(i)))
+        .collect();
+    values.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let total_weight: f64 = values.iter().map(|(_, w)| *w as f64).sum();
+    let threshold = total_weight * (percentile.clamp(0.0, 100.0) as f64 / 100.0);
+
+    let mut accumulated = 0f64;
+    for (v, w) in values.iter() {
+        accumulated += *w as f64;
+        if accumulated >= threshold {
+            return *v;
+        }
+    }
+
+    values.last().map_or(0.0, |(v, _)| *v)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{PixelContribColorMapDescriptor, SphereParameterization};
+
+    fn create_map(
+        size: usize,
+        parameterization: SphereParameterization,
+        f: impl Fn(Vec3) -> f32,
+    ) -> PixelContributionMap {
+        let descriptor =
+            PixelContribColorMapDescriptor::new_with_parameterization(size, 0.5, parameterization);
+
+        let mut map = PixelContributionMap::new(descriptor);
+        for (i, v) in map.pixel_contrib.iter_mut().enumerate() {
+            *v = f(descriptor.camera_dir_from_index(i));
+        }
+
+        map
+    }
+
+    #[test]
+    fn test_statistics_constant() {
+        let map = create_map(16, SphereParameterization::Octahedral, |_| 0.25);
+        let stats = PixelContributionStatistics::from_map(&map);
+
+        assert_eq!(stats.camera_angle, 0.5);
+        assert_eq!(stats.min, 0.25);
+        assert_eq!(stats.max, 0.25);
+        assert!((stats.mean - 0.25).abs() < 1e-6);
+        assert!(stats.std_dev < 1e-4);
+        assert!((stats.solid_angle - 4.0 * std::f32::consts::PI).abs() < 1e-3);
+
+        assert_eq!(weighted_percentile(&map, 0.0), 0.25);
+        assert_eq!(weighted_percentile(&map, 100.0), 0.25);
+    }
+
+    #[test]
+    fn test_statistics_weighted_by_solid_angle() {
+        let f = |dir: Vec3| 0.5 + 0.5 * dir.z;
+
+        for parameterization in [
+            SphereParameterization::Octahedral,
+            SphereParameterization::EqualArea,
+            SphereParameterization::CubeMap,
+        ] {
+            let map = create_map(64, parameterization, f);
+            let stats = PixelContributionStatistics::from_map(&map);
+
+            // the mean of z over the sphere is 0 and its variance is 1/3
+            assert!((stats.mean - 0.5).abs() < 5e-3, "{:?}", parameterization);
+            let std_dev = 0.5 / 3f32.sqrt();
+            assert!(
+                (stats.std_dev - std_dev).abs() < 1e-2,
+                "{:?}",
+                parameterization
+            );
+
+            // the extrema are located at the poles
+            assert!(stats.argmax.z > 0.99, "{:?}", parameterization);
+            assert!(stats.argmin.z < -0.99, "{:?}", parameterization);
+            assert_eq!(f(stats.argmax), stats.max);
+
+            // z is uniformly distributed over the sphere, s.t. the percentiles are linear
+            for p in [10.0, 25.0, 50.0, 75.0, 90.0] {
+                let v = weighted_percentile(&map, p);
+                assert!((v - p / 100.0).abs() < 0.02, "{:?} {}", parameterization, p);
+            }
+        }
+    }
+}
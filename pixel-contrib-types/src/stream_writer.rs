@@ -0,0 +1,182 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Seek, SeekFrom, Write},
+    path::Path,
+};
+
+use byteorder::WriteBytesExt;
+
+use crate::{
+    Error, MapEncoding, PixelContribColorMapDescriptor, PixelContributionMap,
+    PixelContributionMapHeader, PixelContributionMetadata, Result,
+};
+
+/// Writes pixel contribution maps incrementally, one finished map at a time, using the same
+/// file format as [`crate::PixelContributionMaps::write_writer`].
+///
+/// The header and the metadata are written on creation. The number of maps is patched after
+/// each written map, s.t. the written data remains a valid file containing all maps written so
+/// far, even if [`Self::finalize`] is never called.
+pub struct PixelContributionMapsWriter<W: Write + Seek> {
+    writer: W,
+
+    /// The encoding and compression used for storing the values.
+    encoding: MapEncoding,
+
+    /// The position of the number of maps within the written data.
+    num_maps_position: u64,
+
+    /// The descriptors of the maps written so far.
+    descriptors: Vec<PixelContribColorMapDescriptor>,
+}
+
+impl PixelContributionMapsWriter<BufWriter<File>> {
+    /// Creates the file at the given path and writes the header and the metadata.
+    ///
+    /// # Arguments
+    /// * `path` - The path of the file to create.
+    /// * `metadata` - The metadata of the maps.
+    /// * `encoding` - The encoding and compression used for storing the values.
+    pub fn create<P: AsRef<Path>>(
+        path: P,
+        metadata: &PixelContributionMetadata,
+        encoding: MapEncoding,
+    ) -> Result<Self> {
+        let file = File::create(path)?;
+        Self::new(BufWriter::new(file), metadata, encoding)
+    }
+}
+
+impl<W: Write + Seek> PixelContributionMapsWriter<W> {
+    /// Creates a new writer and writes the header and the metadata to the given writer.
+    ///
+    /// # Arguments
+    /// * `writer` - The writer to which the maps should be written.
+    /// * `metadata` - The metadata of the maps.
+    /// * `encoding` - The encoding and compression used for storing the values.
+    pub fn new(
+        mut writer: W,
+        metadata: &PixelContributionMetadata,
+        encoding: MapEncoding,
+    ) -> Result<Self> {
+        PixelContributionMapHeader::default().write_writer(&mut writer)?;
+        metadata.write_writer(&mut writer)?;
+
+        let num_maps_position = writer.stream_position()?;
+        writer.write_u32::<byteorder::LittleEndian>(0)?;
+        writer.flush()?;
+
+        Ok(Self {
+            writer,
+            encoding,
+            num_maps_position,
+            descriptors: Vec::new(),
+        })
+    }
+
+    /// Returns the number of maps written so far.
+    #[inline]
+    pub fn num_maps(&self) -> usize {
+        self.descriptors.len()
+    }
+
+    /// Appends the given map and updates the number of maps. The data is flushed afterwards.
+    ///
+    /// # Arguments
+    /// * `map` - The map to append. Its camera angle must differ from the previous maps with a
+    ///   frustum of the same shape and the same camera distance.
+    pub fn write_map(&mut self, map: &PixelContributionMap) -> Result<()> {
+        let descriptor = map.descriptor;
+        if self.descriptors.iter().any(|d| {
+            d.camera_angle() == descriptor.camera_angle()
+                && d.frustum().has_same_shape(&descriptor.frustum())
+                && d.distance() == descriptor.distance()
+        }) {
+            return Err(Error::DuplicateCameraAngle(descriptor.camera_angle()));
+        }
+
+        map.write_record(&mut self.writer, self.encoding)?;
+        self.descriptors.push(descriptor);
+
+        self.write_num_maps()
+    }
+
+    /// Updates the number of maps and returns the underlying writer.
+    pub fn finalize(mut self) -> Result<W> {
+        self.write_num_maps()?;
+
+        Ok(self.writer)
+    }
+
+    /// Patches the number of maps and moves back to the end of the written data.
+    fn write_num_maps(&mut self) -> Result<()> {
+        let end = self.writer.stream_position()?;
+
+        self.writer.seek(SeekFrom::Start(self.num_maps_position))?;
+        self.writer
+            .write_u32::<byteorder::LittleEndian>(self.descriptors.len() as u32)?;
+        self.writer.seek(SeekFrom::Start(end))?;
+        self.writer.flush()?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use crate::PixelContributionMaps;
+
+    use super::*;
+
+    fn create_map(angle: f32) -> PixelContributionMap {
+        let mut map = PixelContributionMap::new(PixelContribColorMapDescriptor::new(8, angle));
+        map.pixel_contrib
+            .iter_mut()
+            .enumerate()
+            .for_each(|(i, p)| *p = (i as f32 * 0.1 + angle).sin().abs());
+
+        map
+    }
+
+    #[test]
+    fn test_stream_writer() {
+        let mut metadata = PixelContributionMetadata::new();
+        metadata.set_source_model("models/duck.glb");
+
+        let mut writer = PixelContributionMapsWriter::new(
+            Cursor::new(Vec::new()),
+            &metadata,
+            MapEncoding::default(),
+        )
+        .unwrap();
+
+        // the file is readable after each written map
+        let maps: Vec<_> = [1.2, 0.0, 0.6].into_iter().map(create_map).collect();
+        for (i, map) in maps.iter().enumerate() {
+            writer.write_map(map).unwrap();
+            assert_eq!(writer.num_maps(), i + 1);
+
+            let data = writer.writer.get_ref();
+            let maps2 = PixelContributionMaps::from_reader(&mut data.as_slice()).unwrap();
+            assert_eq!(maps2.get_maps().len(), i + 1);
+            assert_eq!(maps2.metadata(), &metadata);
+        }
+
+        assert!(matches!(
+            writer.write_map(&create_map(0.6)),
+            Err(Error::DuplicateCameraAngle(_))
+        ));
+
+        let data = writer.finalize().unwrap().into_inner();
+
+        // the written file contains the same maps as writing all maps at once
+        let mut expected = PixelContributionMaps::from_maps(Vec::new());
+        expected.metadata = metadata;
+        maps.into_iter().for_each(|m| expected.add_map(m));
+
+        let maps2 = PixelContributionMaps::from_reader(&mut data.as_slice()).unwrap();
+        assert!(maps2 == expected);
+    }
+}
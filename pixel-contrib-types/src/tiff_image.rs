@@ -0,0 +1,298 @@
+use std::{
+    io::{BufReader, BufWriter, Read, Seek, Write},
+    path::Path,
+};
+
+use tiff::{
+    decoder::{Decoder, DecodingResult},
+    encoder::{colortype::Gray32Float, TiffEncoder},
+    tags::Tag,
+    TiffError,
+};
+
+use crate::{
+    CameraFrustum, Error, PixelContribColorMapDescriptor, PixelContributionMap,
+    PixelContributionMaps, PixelContributionMetadata, Result, SphereParameterization,
+};
+
+/// The first line of the image description of each page.
+const TIFF_DESCRIPTION_HEADER: &str = "PCMP";
+
+/// The key of the camera angle in the image description.
+const TIFF_CAMERA_ANGLE: &str = "camera_angle";
+
+/// The key of the parameterization in the image description.
+const TIFF_PARAMETERIZATION: &str = "parameterization";
+
+/// The key of the camera frustum in the image description.
+const TIFF_FRUSTUM: &str = "frustum";
+
+/// The key of the camera distance in the image description.
+const TIFF_DISTANCE: &str = "distance";
+
+/// The prefix of the keys of the metadata entries in the image description.
+const TIFF_METADATA_PREFIX: &str = "metadata.";
+
+/// Export and import of TIFF images. Requires the `tiff` feature.
+///
+/// Each map is stored as separate page with a single 32-bit float channel. The camera angle,
+/// the parameterization, the camera frustum, the camera distance and, on the first page, the metadata entries are stored as `key=value`
+/// lines in the image description of the page. Non-ASCII characters are escaped.
+impl PixelContributionMaps {
+    /// Writes the maps as multi-page TIFF image to the given writer.
+    ///
+    /// # Arguments
+    /// * `writer` - The writer to which the image should be written.
+    pub fn write_tiff_writer<W: Write + Seek>(&self, writer: W) -> Result<()> {
+        let mut encoder = TiffEncoder::new(writer).map_err(tiff_error)?;
+
+        for (i, map) in self.maps.iter().enumerate() {
+            let descriptor = &map.descriptor;
+
+            let mut description = vec![
+                TIFF_DESCRIPTION_HEADER.to_string(),
+                format!("{}={}", TIFF_CAMERA_ANGLE, descriptor.camera_angle()),
+                format!(
+                    "{}={}",
+                    TIFF_PARAMETERIZATION,
+                    descriptor.parameterization().name()
+                ),
+                format!("{}={}", TIFF_FRUSTUM, descriptor.frustum().to_text()),
+                format!("{}={}", TIFF_DISTANCE, descriptor.distance()),
+            ];
+            if i == 0 {
+                description.extend(self.metadata.iter().map(|(k, v)| {
+                    // the key must not contain the separator of key and value
+                    let key = k.escape_default().to_string().replace('=', "\\u{3d}");
+                    format!("{}{}={}", TIFF_METADATA_PREFIX, key, v.escape_default())
+                }));
+            }
+
+            let size = descriptor.size() as u32;
+            let mut image = encoder
+                .new_image::<Gray32Float>(size, size)
+                .map_err(tiff_error)?;
+            image
+                .encoder()
+                .write_tag(Tag::ImageDescription, description.join("\n").as_str())
+                .map_err(tiff_error)?;
+            image.write_data(&map.pixel_contrib).map_err(tiff_error)?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the maps as multi-page TIFF image to the given path.
+    ///
+    /// # Arguments
+    /// * `path` - The path to which the image should be written.
+    pub fn write_tiff_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let file = std::fs::File::create(path)?;
+        self.write_tiff_writer(BufWriter::new(file))
+    }
+
+    /// Reads the maps from the TIFF image provided by the given reader. Each page must have a
+    /// single 32-bit float channel and an image description with the camera angle.
+    ///
+    /// # Arguments
+    /// * `reader` - The reader from which the image should be read.
+    pub fn from_tiff_reader<R: Read + Seek>(reader: R) -> Result<Self> {
+        let mut decoder = Decoder::new(reader).map_err(tiff_error)?;
+
+        let mut maps = Vec::new();
+        let mut metadata = PixelContributionMetadata::new();
+        loop {
+            let (width, height) = decoder.dimensions().map_err(tiff_error)?;
+            if width != height {
+                return Err(Error::IO(format!(
+                    "Expected a quadratic page, but got {}x{}",
+                    width, height
+                )));
+            }
+
+            let description = decoder
+                .get_tag_ascii_string(Tag::ImageDescription)
+                .map_err(tiff_error)?;
+            let mut lines = description.lines();
+            if lines.next() != Some(TIFF_DESCRIPTION_HEADER) {
+                return Err(Error::IO("Invalid TIFF image description".to_string()));
+            }
+
+            let mut camera_angle = None;
+            let mut parameterization = SphereParameterization::default();
+            let mut frustum = None;
+            let mut distance = 0.0;
+            for line in lines {
+                let (key, value) = line
+                    .split_once('=')
+                    .ok_or_else(|| Error::IO(format!("Invalid description line '{}'", line)))?;
+
+                if key == TIFF_CAMERA_ANGLE {
+                    camera_angle = Some(
+                        value
+                            .parse::<f32>()
+                            .map_err(|_| Error::IO(format!("Invalid camera angle '{}'", value)))?,
+                    );
+                } else if key == TIFF_PARAMETERIZATION {
+                    parameterization = SphereParameterization::from_name(value)?;
+                } else if key == TIFF_FRUSTUM {
+                    frustum = Some(CameraFrustum::from_text(value)?);
+                } else if key == TIFF_DISTANCE {
+                    distance = value
+                        .parse::<f32>()
+                        .map_err(|_| Error::IO(format!("Invalid camera distance '{}'", value)))?;
+                } else if let Some(key) = key.strip_prefix(TIFF_METADATA_PREFIX) {
+                    metadata.set(unescape(key)?, unescape(value)?);
+                }
+            }
+
+            let camera_angle = camera_angle
+                .ok_or_else(|| Error::IO("Missing camera angle of page".to_string()))?;
+            let values = match decoder.read_image().map_err(tiff_error)? {
+                DecodingResult::F32(values) => values,
+                _ => {
+                    return Err(Error::IO(
+                        "Expected a page with 32-bit float values".to_string(),
+                    ))
+                }
+            };
+
+            let mut descriptor = PixelContribColorMapDescriptor::new_with_parameterization(
+                width as usize,
+                camera_angle,
+                parameterization,
+            )
+            .with_distance(distance);
+            if let Some(frustum) = frustum {
+                descriptor = descriptor.with_frustum(frustum);
+            }
+            if values.len() != descriptor.num_values() {
+                return Err(Error::IO(format!(
+                    "Expected {} values, but got {}",
+                    descriptor.num_values(),
+                    values.len()
+                )));
+            }
+
+            let mut map = PixelContributionMap::new(descriptor);
+            map.pixel_contrib = values;
+            maps.push(map);
+
+            if !decoder.more_images() {
+                break;
+            }
+            decoder.next_image().map_err(tiff_error)?;
+        }
+
+        let mut result = Self::from_maps(maps);
+        result.metadata = metadata;
+
+        Ok(result)
+    }
+
+    /// Reads the maps from the TIFF image at the given path.
+    ///
+    /// # Arguments
+    /// * `path` - The path from which the image should be read.
+    pub fn from_tiff_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file = std::fs::File::open(path)?;
+        Self::from_tiff_reader(BufReader::new(file))
+    }
+}
+
+/// Reverts the escaping of [`str::escape_default`].
+///
+/// # Arguments
+/// * `s` - The escaped string.
+fn unescape(s: &str) -> Result<String> {
+    let invalid = || Error::IO(format!("Invalid escape sequence in '{}'", s));
+
+    let mut result = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+
+        let c = match chars.next().ok_or_else(invalid)? {
+            't' => '\t',
+            'r' => '\r',
+            'n' => '\n',
+            'u' => {
+                let code: String = chars
+                    .by_ref()
+                    .skip_while(|c| *c == '{')
+                    .take_while(|c| *c != '}')
+                    .collect();
+
+                u32::from_str_radix(&code, 16)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .ok_or_else(invalid)?
+            }
+            c => c,
+        };
+
+        result.push(c);
+    }
+
+    Ok(result)
+}
+
+/// Converts the given TIFF error into an error of this crate.
+///
+/// # Arguments
+/// * `err` - The TIFF error to convert.
+fn tiff_error(err: TiffError) -> Error {
+    match err {
+        TiffError::IoError(err) => err.into(),
+        err => Error::IO(format!("Invalid TIFF image: {}", err)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn test_tiff_round_trip() {
+        let mut maps = Vec::new();
+        for (i, parameterization) in [
+            SphereParameterization::Octahedral,
+            SphereParameterization::EqualArea,
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let descriptor = PixelContribColorMapDescriptor::new_with_parameterization(
+                9 + i * 4,
+                0.7 + i as f32 * 0.123,
+                parameterization,
+            )
+            .with_distance(i as f32 * 1.25);
+
+            let mut map = PixelContributionMap::new(descriptor);
+            map.pixel_contrib
+                .iter_mut()
+                .enumerate()
+                .for_each(|(j, p)| *p = (j as f32 * 0.21).sin().abs());
+            maps.push(map);
+        }
+
+        let mut maps = PixelContributionMaps::from_maps(maps);
+        maps.metadata_mut().set_source_model("models/dück.glb");
+        maps.metadata_mut().set("custom=key", "line 1\nline 2\\");
+
+        let mut buf = Cursor::new(Vec::new());
+        maps.write_tiff_writer(&mut buf).unwrap();
+
+        buf.set_position(0);
+        let maps2 = PixelContributionMaps::from_tiff_reader(buf).unwrap();
+        assert!(maps == maps2);
+
+        assert!(PixelContributionMaps::from_tiff_reader(Cursor::new(vec![0u8; 32])).is_err());
+    }
+}
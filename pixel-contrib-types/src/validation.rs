@@ -0,0 +1,130 @@
+use crate::{Error, PixelContribColorMapDescriptor, Result};
+
+/// The default maximum size of the quadratic maps accepted when reading.
+pub const DEFAULT_MAX_MAP_SIZE: usize = 8192;
+
+/// The default maximum number of maps accepted when reading.
+pub const DEFAULT_MAX_NUM_MAPS: usize = 1024;
+
+/// The limits and checks applied when reading serialized pixel contribution maps.
+///
+/// The limits protect against corrupt or hostile files, which would otherwise request huge
+/// allocations, before any values have been read.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReadOptions {
+    /// The maximum size of each quadratic map, including the size of the map itself.
+    pub max_map_size: usize,
+
+    /// The maximum number of maps.
+    pub max_num_maps: usize,
+
+    /// If true, each value must be in the range [0, 1].
+    pub check_value_range: bool,
+}
+
+impl Default for ReadOptions {
+    fn default() -> Self {
+        Self {
+            max_map_size: DEFAULT_MAX_MAP_SIZE,
+            max_num_maps: DEFAULT_MAX_NUM_MAPS,
+            check_value_range: false,
+        }
+    }
+}
+
+impl ReadOptions {
+    /// Checks the number of maps against the configured maximum.
+    ///
+    /// # Arguments
+    /// * `num_maps` - The number of maps stated by the file.
+    pub(crate) fn check_num_maps(&self, num_maps: usize) -> Result<()> {
+        if num_maps > self.max_num_maps {
+            return Err(Error::TooManyMaps(num_maps, self.max_num_maps));
+        }
+
+        Ok(())
+    }
+
+    /// Checks the size, the camera angle, the frustum and the camera distance of the given
+    /// descriptor.
+    ///
+    /// # Arguments
+    /// * `descriptor` - The descriptor read from the file.
+    pub(crate) fn check_descriptor(
+        &self,
+        descriptor: &PixelContribColorMapDescriptor,
+    ) -> Result<()> {
+        let size = descriptor.size();
+        if size == 0 || size > self.max_map_size {
+            return Err(Error::InvalidMapSize(size, self.max_map_size));
+        }
+
+        let angle = descriptor.camera_angle();
+        if !angle.is_finite() || angle < 0.0 {
+            return Err(Error::InvalidCameraAngle(angle));
+        }
+
+        if !descriptor.frustum().is_valid() {
+            return Err(Error::InvalidFrustum);
+        }
+
+        let distance = descriptor.distance();
+        if !distance.is_finite() || (distance != 0.0 && distance <= 1.0) {
+            return Err(Error::InvalidCameraDistance(distance));
+        }
+
+        Ok(())
+    }
+
+    /// Checks that all values are in the range [0, 1], if configured.
+    ///
+    /// # Arguments
+    /// * `map` - The index of the map in the file.
+    /// * `values` - The values of the map.
+    pub(crate) fn check_values<I: IntoIterator<Item = f32>>(
+        &self,
+        map: usize,
+        values: I,
+    ) -> Result<()> {
+        if !self.check_value_range {
+            return Ok(());
+        }
+
+        match values
+            .into_iter()
+            .enumerate()
+            .find(|(_, v)| !(0.0..=1.0).contains(v))
+        {
+            Some((index, value)) => Err(Error::ValueOutOfRange(map, index, value)),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Checks that no two of the given descriptors, which are sorted by their camera angle, have
+/// equal camera angles, frusta of the same shape and equal camera distances.
+///
+/// # Arguments
+/// * `descriptors` - The descriptors in ascending order of their camera angles.
+pub(crate) fn check_unique_angles<'a, I>(descriptors: I) -> Result<()>
+where
+    I: IntoIterator<Item = &'a PixelContribColorMapDescriptor>,
+{
+    // the frusta of the previous descriptors with the same camera angle
+    let mut previous: Vec<&PixelContribColorMapDescriptor> = Vec::new();
+    for descriptor in descriptors {
+        let angle = descriptor.camera_angle();
+        previous.retain(|d| d.camera_angle() == angle);
+
+        if previous.iter().any(|d| {
+            d.frustum().has_same_shape(&descriptor.frustum())
+                && d.distance() == descriptor.distance()
+        }) {
+            return Err(Error::DuplicateCameraAngle(angle));
+        }
+
+        previous.push(descriptor);
+    }
+
+    Ok(())
+}
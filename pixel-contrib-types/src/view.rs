@@ -0,0 +1,457 @@
+use std::sync::Arc;
+
+use byteorder::{ByteOrder, LittleEndian, ReadBytesExt};
+use nalgebra_glm::Vec3;
+
+use crate::{
+    interpolation, mipmap::mip_sizes, validation, AngleInterpolator, CameraFrustum, Compression,
+    Error, PixelContribColorMapDescriptor, PixelContributionMap, PixelContributionMapHeader,
+    PixelContributionMaps, PixelContributionMetadata, ReadOptions, Result, SamplingMode,
+    TangentAngleInterpolator, ValueEncoding,
+};
+
+/// A borrowed view onto a single pixel contribution map whose values are read directly from the
+/// underlying bytes without copying them.
+#[derive(Clone, Copy)]
+pub struct PixelContributionMapView<'a> {
+    pub descriptor: PixelContribColorMapDescriptor,
+
+    /// The index of the map in the serialized data.
+    index: usize,
+
+    /// The raw little-endian 32-bit float values of the map.
+    values: &'a [u8],
+
+    /// The serialized map record the checksum has been computed for.
+    record: &'a [u8],
+
+    /// The stored checksum of the map record, if the file version provides one.
+    checksum: Option<u32>,
+}
+
+impl<'a> PixelContributionMapView<'a> {
+    /// Returns the number of pixel contribution values.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.values.len() / 4
+    }
+
+    /// Returns true if the map has no values.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Returns the pixel contribution value at the given index.
+    ///
+    /// # Arguments
+    /// * `index` - The index of the pixel contribution value.
+    #[inline]
+    pub fn value(&self, index: usize) -> f32 {
+        LittleEndian::read_f32(&self.values[index * 4..index * 4 + 4])
+    }
+
+    /// Returns an iterator over all pixel contribution values.
+    pub fn iter(&self) -> impl Iterator<Item = f32> + 'a {
+        self.values.chunks_exact(4).map(LittleEndian::read_f32)
+    }
+
+    /// Returns the pixel contribution values as float slice, if the underlying bytes are suitably
+    /// aligned and the platform is little-endian. Otherwise, None is returned and the values
+    /// must be accessed via [`Self::value`] or [`Self::iter`].
+    pub fn as_f32_slice(&self) -> Option<&'a [f32]> {
+        if cfg!(target_endian = "big") {
+            return None;
+        }
+
+        // SAFETY: Every bit pattern is a valid f32 and the prefix/suffix check ensures that the
+        // whole slice is properly aligned.
+        let (prefix, values, suffix) = unsafe { self.values.align_to::<f32>() };
+        if prefix.is_empty() && suffix.is_empty() {
+            Some(values)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the pixel contribution for the given camera direction vector.
+    ///
+    /// # Arguments
+    /// * `dir` - The camera direction vector to the object.
+    pub fn get_pixel_contrib_for_camera_dir(&self, dir: Vec3) -> f32 {
+        let index = self.descriptor.index_from_camera_dir(dir);
+        self.value(index)
+    }
+
+    /// Returns the pixel contribution for the given camera direction vector using the given
+    /// filter.
+    ///
+    /// # Arguments
+    /// * `dir` - The camera direction vector to the object.
+    /// * `mode` - The filter used for the lookup.
+    pub fn sample_pixel_contrib_for_camera_dir(&self, dir: Vec3, mode: SamplingMode) -> f32 {
+        mode.sample(&self.descriptor, dir, |i| self.value(i))
+    }
+
+    /// Checks the stored checksum of the map, if available.
+    pub fn verify_checksum(&self) -> Result<()> {
+        match self.checksum {
+            Some(checksum) if crc32fast::hash(self.record) != checksum => {
+                Err(Error::ChecksumMismatch(self.index))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Copies the values into an owned pixel contribution map.
+    pub fn to_map(&self) -> PixelContributionMap {
+        PixelContributionMap {
+            descriptor: self.descriptor,
+            pixel_contrib: self.iter().collect(),
+            mip_levels: Vec::new(),
+        }
+    }
+}
+
+/// A borrowed view onto serialized pixel contribution maps, e.g., a memory-mapped
+/// `contrib_maps.bin` file. Only the header and the descriptors are parsed, the values are read
+/// directly from the underlying bytes. Therefore, only maps stored as uncompressed 32-bit floats
+/// are supported. Stored mip chains are skipped.
+#[derive(Clone)]
+pub struct PixelContributionMapsView<'a> {
+    maps: Vec<PixelContributionMapView<'a>>,
+    metadata: PixelContributionMetadata,
+    interpolator: Arc<dyn AngleInterpolator>,
+}
+
+impl<'a> PixelContributionMapsView<'a> {
+    /// Creates a new view onto the given serialized pixel contribution maps using the default
+    /// limits, see [`ReadOptions`]. The header and the layout of the maps are validated, but not
+    /// the checksums. See [`Self::verify_checksums`] for validating the checksums.
+    ///
+    /// # Arguments
+    /// * `data` - The serialized pixel contribution maps.
+    pub fn from_bytes(data: &'a [u8]) -> Result<Self> {
+        Self::from_bytes_with_options(data, &ReadOptions::default())
+    }
+
+    /// Creates a new view onto the given serialized pixel contribution maps using the given
+    /// limits and checks. See [`Self::from_bytes`] for details.
+    ///
+    /// # Arguments
+    /// * `data` - The serialized pixel contribution maps.
+    /// * `options` - The limits and checks applied when reading.
+    pub fn from_bytes_with_options(data: &'a [u8], options: &ReadOptions) -> Result<Self> {
+        let mut data = data;
+
+        // Read the header and check if it is valid
+        let mut header = PixelContributionMapHeader::default();
+        header.identifier.copy_from_slice(take(&mut data, 4)?);
+        header.version = data.read_u32::<LittleEndian>()?;
+        header.check()?;
+
+        // Read the metadata, which is not available in version 1
+        let metadata = if header.version >= 2 {
+            PixelContributionMetadata::from_reader(&mut data)?
+        } else {
+            PixelContributionMetadata::new()
+        };
+
+        // Read the number of pixel contribution maps
+        let num_maps = data.read_u32::<LittleEndian>()? as usize;
+        options.check_num_maps(num_maps)?;
+
+        let mut maps = Vec::with_capacity(num_maps.min(data.len()));
+        for i in 0..num_maps {
+            let map = Self::read_map(&mut data, header.version, i, options)?;
+            options.check_values(i, map.iter())?;
+            maps.push(map);
+        }
+
+        maps.sort_by(|m1, m2| m1.descriptor.cmp_camera(&m2.descriptor));
+        validation::check_unique_angles(maps.iter().map(|m| &m.descriptor))?;
+
+        Ok(Self {
+            maps,
+            metadata,
+            interpolator: Arc::new(TangentAngleInterpolator),
+        })
+    }
+
+    /// Returns the views onto the individual maps sorted in ascending order w.r.t their camera
+    /// angles.
+    #[inline]
+    pub fn get_maps(&self) -> &[PixelContributionMapView<'a>] {
+        &self.maps
+    }
+
+    /// Returns a reference to the metadata of the pixel contribution maps.
+    #[inline]
+    pub fn metadata(&self) -> &PixelContributionMetadata {
+        &self.metadata
+    }
+
+    /// Checks the stored checksums of all maps.
+    pub fn verify_checksums(&self) -> Result<()> {
+        self.maps.iter().try_for_each(|m| m.verify_checksum())
+    }
+
+    /// Returns the interpolator used for camera angles between the angles of the maps.
+    #[inline]
+    pub fn angle_interpolator(&self) -> &dyn AngleInterpolator {
+        self.interpolator.as_ref()
+    }
+
+    /// Sets the interpolator used for camera angles between the angles of the maps.
+    /// By default, the [`TangentAngleInterpolator`] is used.
+    ///
+    /// # Arguments
+    /// * `interpolator` - The new angle interpolator.
+    pub fn set_angle_interpolator<I: AngleInterpolator + 'static>(&mut self, interpolator: I) {
+        self.interpolator = Arc::new(interpolator);
+    }
+
+    /// Returns the pixel contribution for the given camera direction vector.
+    ///
+    /// # Arguments
+    /// * `dir` - The camera direction vector to the object.
+    /// * `angle` - The angle of the camera to return the contribution values for.
+    pub fn get_pixel_contrib_for_camera_dir(&self, dir: Vec3, angle: f32) -> f32 {
+        self.sample_pixel_contrib_for_camera_dir(dir, angle, SamplingMode::Nearest)
+    }
+
+    /// Returns the pixel contribution for the given camera direction vector using the given
+    /// filter for the lookup in the maps. The camera is assumed to have a quadratic symmetric
+    /// frustum with the given angle as field of view, see
+    /// [`PixelContributionMaps::sample_pixel_contrib_for_camera_dir`].
+    ///
+    /// # Arguments
+    /// * `dir` - The camera direction vector to the object.
+    /// * `angle` - The angle of the camera to return the contribution values for.
+    /// * `mode` - The filter used for the lookup in the maps.
+    pub fn sample_pixel_contrib_for_camera_dir(
+        &self,
+        dir: Vec3,
+        angle: f32,
+        mode: SamplingMode,
+    ) -> f32 {
+        self.sample_pixel_contrib_for_camera(dir, &CameraFrustum::square(angle), angle, mode)
+    }
+
+    /// Returns the pixel contribution for the given camera direction vector seen by a camera
+    /// with the given frustum using the given filter for the lookup in the maps, see
+    /// [`PixelContributionMaps::sample_pixel_contrib_for_camera`].
+    ///
+    /// # Arguments
+    /// * `dir` - The camera direction vector to the object.
+    /// * `frustum` - The frustum of the camera.
+    /// * `sphere_angle` - The angle under which the bounding sphere is seen from the camera.
+    /// * `mode` - The filter used for the lookup in the maps.
+    pub fn sample_pixel_contrib_for_camera(
+        &self,
+        dir: Vec3,
+        frustum: &CameraFrustum,
+        sphere_angle: f32,
+        mode: SamplingMode,
+    ) -> f32 {
+        interpolation::interpolate_camera(
+            self.interpolator.as_ref(),
+            self.maps.len(),
+            &|i| self.maps[i].descriptor,
+            frustum,
+            sphere_angle,
+            &|i| self.maps[i].sample_pixel_contrib_for_camera_dir(dir, mode),
+        )
+    }
+
+    /// Copies the values into owned pixel contribution maps.
+    pub fn to_maps(&self) -> PixelContributionMaps {
+        let mut maps =
+            PixelContributionMaps::from_maps(self.maps.iter().map(|m| m.to_map()).collect());
+        maps.metadata = self.metadata.clone();
+        maps.interpolator = self.interpolator.clone();
+
+        maps
+    }
+
+    /// Reads the view onto a single map and advances the given data.
+    ///
+    /// # Arguments
+    /// * `data` - The remaining serialized data starting at the map.
+    /// * `version` - The version of the file format.
+    /// * `index` - The index of the map in the serialized data.
+    /// * `options` - The limits and checks applied when reading.
+    fn read_map(
+        data: &mut &'a [u8],
+        version: u32,
+        index: usize,
+        options: &ReadOptions,
+    ) -> Result<PixelContributionMapView<'a>> {
+        let start = *data;
+
+        let descriptor = PixelContribColorMapDescriptor::from_reader(data, version)?;
+        options.check_descriptor(&descriptor)?;
+        let map_size = descriptor.size();
+
+        let num_bytes = descriptor.num_values() * 4;
+
+        // Read the values, which are stored without an encoding in version 1
+        if version >= 2 {
+            let values = ValueEncoding::from_id(data.read_u8()?)?;
+            let compression = Compression::from_id(data.read_u8()?)?;
+            if values != ValueEncoding::F32 || compression != Compression::None {
+                return Err(Error::InvalidArgument(format!(
+                    "Map with encoding {:?} and compression {:?} cannot be viewed without decoding",
+                    values, compression
+                )));
+            }
+
+            // skip the value range, which is only used for quantization
+            take(data, 8)?;
+
+            let len = data.read_u32::<LittleEndian>()? as usize;
+            if len != num_bytes {
+                return Err(Error::IO(format!(
+                    "Expected {} bytes of values, but got {}",
+                    num_bytes, len
+                )));
+            }
+        }
+
+        let values = take(data, num_bytes)?;
+
+        // Skip the mip chain, which is not available in version 1. The mip levels are not part
+        // of the view, but they are covered by the checksum of the record.
+        if version >= 2 {
+            let num_levels = data.read_u8()? as usize;
+            if num_levels > mip_sizes(map_size).count() {
+                return Err(Error::InvalidMipLevels(num_levels, map_size));
+            }
+
+            for _ in 0..num_levels {
+                take(data, 8)?;
+                let len = data.read_u32::<LittleEndian>()? as usize;
+                take(data, len)?;
+            }
+        }
+
+        let record = &start[..start.len() - data.len()];
+
+        // Read the checksum, which is not available in version 1
+        let checksum = if version >= 2 {
+            Some(data.read_u32::<LittleEndian>()?)
+        } else {
+            None
+        };
+
+        Ok(PixelContributionMapView {
+            descriptor,
+            index,
+            values,
+            record,
+            checksum,
+        })
+    }
+}
+
+/// Splits off the given number of bytes from the front of the data.
+///
+/// # Arguments
+/// * `data` - The data to split the bytes from.
+/// * `n` - The number of bytes to split off.
+fn take<'a>(data: &mut &'a [u8], n: usize) -> Result<&'a [u8]> {
+    if data.len() < n {
+        return Err(Error::IO("Unexpected end of data".to_string()));
+    }
+
+    let (head, tail) = data.split_at(n);
+    *data = tail;
+
+    Ok(head)
+}
+
+/// Pixel contribution maps stored in a memory-mapped file. Requires the `mmap` feature.
+#[cfg(feature = "mmap")]
+pub struct MappedPixelContributionMaps {
+    mmap: memmap2::Mmap,
+}
+
+#[cfg(feature = "mmap")]
+impl MappedPixelContributionMaps {
+    /// Memory-maps the pixel contribution maps file at the given path.
+    ///
+    /// # Arguments
+    /// * `path` - The path of the pixel contribution maps file.
+    pub fn open<P: AsRef<std::path::Path>>(path: P) -> Result<Self> {
+        let file = std::fs::File::open(path)?;
+
+        // SAFETY: The file must not be modified while being mapped, which is the same
+        // requirement as for any other memory-mapped asset.
+        let mmap = unsafe { memmap2::Mmap::map(&file)? };
+
+        Ok(Self { mmap })
+    }
+
+    /// Returns a view onto the memory-mapped pixel contribution maps.
+    pub fn view(&self) -> Result<PixelContributionMapsView<'_>> {
+        PixelContributionMapsView::from_bytes(&self.mmap)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_view_matches_owned_maps() {
+        let data = include_bytes!("../../test_data/contrib_maps/duck_contrib_map.bin");
+        let maps = PixelContributionMaps::from_reader(&mut data.as_slice()).unwrap();
+
+        // test the original version 1 file as well as the current version
+        let mut buf = Vec::new();
+        maps.write_writer(&mut buf).unwrap();
+
+        for data in [data.as_slice(), buf.as_slice()] {
+            let view = PixelContributionMapsView::from_bytes(data).unwrap();
+            view.verify_checksums().unwrap();
+            assert!(view.to_maps().maps == maps.maps);
+
+            let angle = (maps.maps[0].descriptor.camera_angle()
+                + maps.maps[1].descriptor.camera_angle())
+                / 2f32;
+            for i in 0..100 {
+                let t = i as f32 / 100f32 * std::f32::consts::TAU;
+                let dir = Vec3::new(t.cos(), t.sin(), (3f32 * t).sin());
+
+                assert_eq!(
+                    view.get_pixel_contrib_for_camera_dir(dir, angle),
+                    maps.get_pixel_contrib_for_camera_dir(dir, angle)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_view_rejects_invalid_data() {
+        let descriptor = PixelContribColorMapDescriptor::new(4, 0.5);
+        let maps = PixelContributionMaps::from_maps(vec![PixelContributionMap::new(descriptor)]);
+
+        let mut buf = Vec::new();
+        maps.write_writer(&mut buf).unwrap();
+
+        // truncated data
+        assert!(PixelContributionMapsView::from_bytes(&buf[..buf.len() - 5]).is_err());
+
+        // corrupted value
+        let n = buf.len();
+        buf[n - 8] ^= 0x01;
+        let view = PixelContributionMapsView::from_bytes(&buf).unwrap();
+        assert!(view.verify_checksums().is_err());
+
+        // encoded values cannot be viewed
+        let mut buf = Vec::new();
+        let encoding = crate::MapEncoding::new(ValueEncoding::F16, Compression::None);
+        maps.write_writer_with_encoding(&mut buf, encoding).unwrap();
+        assert!(PixelContributionMapsView::from_bytes(&buf).is_err());
+    }
+}
@@ -0,0 +1,352 @@
+use rayon::prelude::*;
+
+/// The distance in texels between the rendered texels of the initial coarse grid.
+const COARSE_GRID_STEP: usize = 8;
+
+/// A rectangular cell of texels of a map. Only the values at its corners are rendered, all
+/// other texels are either interpolated or covered by the refined sub-cells.
+#[derive(Clone, Copy, Debug)]
+struct Cell {
+    /// The index of the map the cell belongs to.
+    map: usize,
+
+    /// The column of the left corners.
+    x0: usize,
+
+    /// The column of the right corners.
+    x1: usize,
+
+    /// The row of the top corners.
+    y0: usize,
+
+    /// The row of the bottom corners.
+    y1: usize,
+}
+
+impl Cell {
+    /// Returns the indices of the four corners of the cell.
+    ///
+    /// # Arguments
+    /// * `size` - The size of the quadratic maps.
+    fn corners(&self, size: usize) -> [usize; 4] {
+        let offset = self.map * size * size;
+        [
+            offset + self.y0 * size + self.x0,
+            offset + self.y0 * size + self.x1,
+            offset + self.y1 * size + self.x0,
+            offset + self.y1 * size + self.x1,
+        ]
+    }
+
+    /// Returns true if all texels of the cell are corners.
+    #[inline]
+    fn is_minimal(&self) -> bool {
+        self.x1 - self.x0 <= 1 && self.y1 - self.y0 <= 1
+    }
+
+    /// Splits the cell at its center into up to four sub-cells. Only dimensions with interior
+    /// texels are split.
+    fn split(&self) -> Vec<Cell> {
+        let split = |c0: usize, c1: usize| {
+            if c1 - c0 > 1 {
+                vec![(c0, (c0 + c1) / 2), ((c0 + c1) / 2, c1)]
+            } else {
+                vec![(c0, c1)]
+            }
+        };
+
+        let xs = split(self.x0, self.x1);
+        let ys = split(self.y0, self.y1);
+
+        ys.iter()
+            .flat_map(|&(y0, y1)| {
+                xs.iter().map(move |&(x0, x1)| Cell {
+                    map: self.map,
+                    x0,
+                    x1,
+                    y0,
+                    y1,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Samples the values of the given number of quadratic maps adaptively. The texels of a coarse
+/// grid are rendered first. Each cell of the grid, whose corner values differ by more than the
+/// tolerance, is refined by rendering the texels at its center lines, until all texels have
+/// either been rendered or lie in a cell with small variation. The remaining texels are
+/// bilinearly interpolated from the corners of their cell.
+///
+/// Each texel consists of one value per channel, e.g., one per object of the scene. A cell is
+/// refined if the corner values of any channel differ by more than the tolerance.
+///
+/// Returns the values and the variances of all maps for each channel, stored one after the
+/// other, and the number of rendered texels.
+///
+/// The progress is advanced once for each texel, when it is rendered or interpolated for the
+/// first time, s.t. it sums up to the number of texels of all maps. A texel, which has been
+/// interpolated and is rendered later on by a refined neighboring cell, is not counted again.
+///
+/// # Arguments
+/// * `num_maps` - The number of maps to sample.
+/// * `size` - The size of the quadratic maps.
+/// * `num_channels` - The number of values of each texel.
+/// * `tolerance` - The maximal difference between the corner values of a cell, s.t. its interior
+///   is interpolated instead of being rendered.
+/// * `render` - Renders the values and their variances of all channels of the texel with the
+///   given index of the map with the given index.
+/// * `advance` - Advances the progress by the given number of texels.
+pub(crate) fn sample_adaptive<F, A>(
+    num_maps: usize,
+    size: usize,
+    num_channels: usize,
+    tolerance: f32,
+    render: F,
+    advance: A,
+) -> (Vec<Vec<f32>>, Vec<Vec<f32>>, usize)
+where
+    F: Fn(usize, usize) -> Vec<(f32, f32)> + Sync,
+    A: Fn(usize) + Sync,
+{
+    let num_values_per_map = size * size;
+    let num_values = num_maps * num_values_per_map;
+    let mut values = vec![vec![0f32; num_values]; num_channels];
+    let mut variances = values.clone();
+    let mut rendered = vec![false; num_values];
+    let mut interpolated = vec![false; num_values];
+    if size == 0 {
+        return (values, variances, 0);
+    }
+
+    // the coarse grid always includes the last row and column
+    let mut grid: Vec<usize> = (0..size - 1).step_by(COARSE_GRID_STEP).collect();
+    grid.push(size - 1);
+
+    let mut pending: Vec<usize> = (0..num_maps)
+        .flat_map(|map| {
+            let grid = &grid;
+            grid.iter().flat_map(move |&y| {
+                grid.iter()
+                    .map(move |&x| map * num_values_per_map + y * size + x)
+            })
+        })
+        .collect();
+
+    let mut cells: Vec<Cell> = (0..num_maps)
+        .flat_map(|map| {
+            let grid = &grid;
+            grid.windows(2).flat_map(move |ys| {
+                grid.windows(2).map(move |xs| Cell {
+                    map,
+                    x0: xs[0],
+                    x1: xs[1],
+                    y0: ys[0],
+                    y1: ys[1],
+                })
+            })
+        })
+        .collect();
+
+    let mut num_rendered = 0;
+    loop {
+        // render all texels which have not been rendered yet
+        pending.sort_unstable();
+        pending.dedup();
+        pending.retain(|i| !rendered[*i]);
+
+        let new_values: Vec<Vec<(f32, f32)>> = pending
+            .par_iter()
+            .map(|&i| {
+                if !interpolated[i] {
+                    advance(1);
+                }
+
+                render(i / num_values_per_map, i % num_values_per_map)
+            })
+            .collect();
+        for (&i, channels) in pending.iter().zip(new_values) {
+            debug_assert_eq!(channels.len(), num_channels);
+            for (channel, (value, variance)) in channels.into_iter().enumerate() {
+                values[channel][i] = value;
+                variances[channel][i] = variance;
+            }
+            rendered[i] = true;
+        }
+        num_rendered += pending.len();
+        pending.clear();
+
+        if cells.is_empty() {
+            break;
+        }
+
+        let mut refined = Vec::new();
+        for cell in cells.into_iter().filter(|c| !c.is_minimal()) {
+            let indices = cell.corners(size);
+            let varies = values.iter().any(|values| {
+                let corners = indices.map(|i| values[i]);
+                let min = corners.iter().copied().fold(f32::MAX, f32::min);
+                let max = corners.iter().copied().fold(f32::MIN, f32::max);
+
+                max - min > tolerance
+            });
+
+            if varies {
+                for sub_cell in cell.split() {
+                    pending.extend(sub_cell.corners(size));
+                    refined.push(sub_cell);
+                }
+            } else {
+                advance(mark_interpolated(&cell, size, &rendered, &mut interpolated));
+                for values in values.iter_mut().chain(variances.iter_mut()) {
+                    let corners = indices.map(|i| values[i]);
+                    interpolate_cell(&cell, size, corners, values, &rendered);
+                }
+            }
+        }
+
+        cells = refined;
+    }
+
+    (values, variances, num_rendered)
+}
+
+/// Marks the texels of the given cell, which have not been rendered, as interpolated. Returns
+/// the number of texels, which have neither been rendered nor interpolated before.
+///
+/// # Arguments
+/// * `cell` - The cell to interpolate.
+/// * `size` - The size of the quadratic maps.
+/// * `rendered` - Whether the value of each texel has been rendered.
+/// * `interpolated` - Whether the value of each texel has been interpolated.
+fn mark_interpolated(
+    cell: &Cell,
+    size: usize,
+    rendered: &[bool],
+    interpolated: &mut [bool],
+) -> usize {
+    let offset = cell.map * size * size;
+    let mut num_new = 0;
+    for y in cell.y0..=cell.y1 {
+        for index in (cell.x0..=cell.x1).map(|x| offset + y * size + x) {
+            if !rendered[index] && !interpolated[index] {
+                interpolated[index] = true;
+                num_new += 1;
+            }
+        }
+    }
+
+    num_new
+}
+
+/// Bilinearly interpolates the texels of the given cell, which have not been rendered.
+///
+/// # Arguments
+/// * `cell` - The cell to interpolate.
+/// * `size` - The size of the quadratic maps.
+/// * `corners` - The values at the corners of the cell, see [`Cell::corners`].
+/// * `values` - The values of all maps.
+/// * `rendered` - Whether the value of each texel has been rendered.
+fn interpolate_cell(
+    cell: &Cell,
+    size: usize,
+    corners: [f32; 4],
+    values: &mut [f32],
+    rendered: &[bool],
+) {
+    let offset = cell.map * size * size;
+    let width = (cell.x1 - cell.x0) as f32;
+    let height = (cell.y1 - cell.y0) as f32;
+
+    for y in cell.y0..=cell.y1 {
+        let fy = (y - cell.y0) as f32 / height;
+        for x in cell.x0..=cell.x1 {
+            let index = offset + y * size + x;
+            if rendered[index] {
+                continue;
+            }
+
+            let fx = (x - cell.x0) as f32 / width;
+            let top = corners[0] * (1f32 - fx) + corners[1] * fx;
+            let bottom = corners[2] * (1f32 - fx) + corners[3] * fx;
+            values[index] = top * (1f32 - fy) + bottom * fy;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    #[test]
+    fn test_sample_adaptive() {
+        let size = 33;
+
+        // a plane is interpolated exactly from the coarse grid
+        let plane = |_: usize, i: usize| ((i % size) + 2 * (i / size)) as f32 / 100f32;
+        let (values, _, num_rendered) =
+            sample_adaptive(2, size, 1, 1f32, |m, i| vec![(plane(m, i), 0f32)], |_| {});
+        assert_eq!(num_rendered, 2 * 5 * 5);
+        for (i, v) in values[0].iter().enumerate() {
+            assert!((v - plane(0, i % (size * size))).abs() < 1e-5);
+        }
+
+        // a step is refined along the edge, but not in the flat regions
+        let step = |map: usize, i: usize| if i % size > 10 + map { 1f32 } else { 0f32 };
+        let progress = AtomicUsize::new(0);
+        let (values, variances, num_rendered) = sample_adaptive(
+            2,
+            size,
+            1,
+            0.1,
+            |m, i| vec![(step(m, i), step(m, i) * 0.1)],
+            |n| {
+                progress.fetch_add(n, Ordering::Relaxed);
+            },
+        );
+        assert!(num_rendered < size * size);
+        for (i, v) in values[0].iter().enumerate() {
+            let (map, index) = (i / (size * size), i % (size * size));
+            assert_eq!(*v, step(map, index));
+            assert!((variances[0][i] - step(map, index) * 0.1).abs() < 1e-6);
+        }
+
+        // the progress counts each rendered or interpolated texel exactly once
+        assert_eq!(progress.load(Ordering::Relaxed), 2 * size * size);
+
+        // a negative tolerance renders all texels
+        let (_, _, num_rendered) =
+            sample_adaptive(1, size, 1, -1f32, |m, i| vec![(plane(m, i), 0f32)], |_| {});
+        assert_eq!(num_rendered, size * size);
+
+        let (values, variances, num_rendered) =
+            sample_adaptive(1, 1, 1, 0.1, |_, _| vec![(0.5, 0.25)], |_| {});
+        assert_eq!(
+            (values, variances, num_rendered),
+            (vec![vec![0.5]], vec![vec![0.25]], 1)
+        );
+    }
+
+    #[test]
+    fn test_sample_adaptive_channels() {
+        let size = 33;
+
+        // the step of the second channel is refined, although the first channel is flat
+        let step = |i: usize| if i % size > 10 { 1f32 } else { 0f32 };
+        let (values, _, num_rendered) = sample_adaptive(
+            1,
+            size,
+            2,
+            0.1,
+            |_, i| vec![(0.5, 0f32), (step(i), 0f32)],
+            |_| {},
+        );
+        assert!(num_rendered > 5 * 5);
+        assert!(values[0].iter().all(|v| *v == 0.5));
+        for (i, v) in values[1].iter().enumerate() {
+            assert_eq!(*v, step(i));
+        }
+    }
+}
@@ -0,0 +1,606 @@
+use std::{
+    fs::{self, File},
+    io::{BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use log::{info, warn};
+use rasterizer::Scene;
+
+use crate::{
+    CameraConfig, ContributionChannels, DirectionSampling, Error, PixelContributionOptions, Result,
+    SphereFitting,
+};
+
+/// The magic bytes at the beginning of each checkpoint file.
+const CHECKPOINT_MAGIC: &[u8; 8] = b"PXCCKPT\0";
+
+/// The version of the checkpoint file format.
+const CHECKPOINT_VERSION: u32 = 1;
+
+/// The options for periodically writing the texels computed so far to a checkpoint file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CheckpointOptions {
+    /// The path of the checkpoint file.
+    pub path: PathBuf,
+
+    /// The minimal time between two written checkpoints.
+    pub interval: Duration,
+}
+
+/// The computed texels of all maps together with the hash of the scene and the options they
+/// have been computed for.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Checkpoint {
+    /// The hash of the scene and the options, see [`compute_hash`].
+    hash: u64,
+
+    /// The number of values of each texel.
+    num_channels: usize,
+
+    /// Whether each texel has been computed.
+    completed: Vec<bool>,
+
+    /// The values and the variances of all channels of each texel, one texel after the other.
+    values: Vec<(f32, f32)>,
+}
+
+impl Checkpoint {
+    /// Creates a new checkpoint without any computed texels.
+    ///
+    /// # Arguments
+    /// * `hash` - The hash of the scene and the options.
+    /// * `num_texels` - The number of texels of all maps.
+    /// * `num_channels` - The number of values of each texel.
+    pub fn new(hash: u64, num_texels: usize, num_channels: usize) -> Self {
+        Self {
+            hash,
+            num_channels,
+            completed: vec![false; num_texels],
+            values: vec![(0f32, 0f32); num_texels * num_channels],
+        }
+    }
+
+    /// Returns the number of texels, which have been computed.
+    pub fn num_completed(&self) -> usize {
+        self.completed.iter().filter(|c| **c).count()
+    }
+
+    /// Returns the values and the variances of the given texel, if it has been computed.
+    ///
+    /// # Arguments
+    /// * `texel` - The index of the texel over all maps.
+    pub fn get(&self, texel: usize) -> Option<&[(f32, f32)]> {
+        if self.completed[texel] {
+            let start = texel * self.num_channels;
+            Some(&self.values[start..start + self.num_channels])
+        } else {
+            None
+        }
+    }
+
+    /// Stores the values and the variances of the given texel.
+    ///
+    /// # Arguments
+    /// * `texel` - The index of the texel over all maps.
+    /// * `values` - The values and the variances of all channels of the texel.
+    pub fn set(&mut self, texel: usize, values: &[(f32, f32)]) {
+        let start = texel * self.num_channels;
+        self.values[start..start + self.num_channels].copy_from_slice(values);
+        self.completed[texel] = true;
+    }
+
+    /// Writes the checkpoint to the given path. The checkpoint is written to a temporary file
+    /// first, which then replaces the given file, s.t. an interrupted write keeps the previous
+    /// checkpoint intact.
+    ///
+    /// # Arguments
+    /// * `path` - The path of the checkpoint file.
+    pub fn write_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        let mut tmp_path = path.as_os_str().to_owned();
+        tmp_path.push(".tmp");
+
+        let mut writer = BufWriter::new(File::create(&tmp_path)?);
+        self.write_writer(&mut writer)?;
+        writer.flush()?;
+        drop(writer);
+
+        fs::rename(&tmp_path, path)?;
+
+        Ok(())
+    }
+
+    /// Reads the checkpoint from the given path. Returns [`Error::CheckpointMismatch`], if the
+    /// checkpoint has not been computed for the given hash and number of texels and channels.
+    ///
+    /// # Arguments
+    /// * `path` - The path of the checkpoint file.
+    /// * `hash` - The expected hash of the scene and the options.
+    /// * `num_texels` - The expected number of texels of all maps.
+    /// * `num_channels` - The expected number of values of each texel.
+    pub fn from_file<P: AsRef<Path>>(
+        path: P,
+        hash: u64,
+        num_texels: usize,
+        num_channels: usize,
+    ) -> Result<Self> {
+        let mut reader = BufReader::new(File::open(path)?);
+        Self::from_reader(&mut reader, hash, num_texels, num_channels)
+    }
+
+    /// Writes the checkpoint to the given writer.
+    ///
+    /// # Arguments
+    /// * `writer` - The writer to which the checkpoint should be written.
+    fn write_writer<W: Write>(&self, writer: &mut W) -> Result<()> {
+        writer.write_all(CHECKPOINT_MAGIC)?;
+        writer.write_u32::<LittleEndian>(CHECKPOINT_VERSION)?;
+        writer.write_u64::<LittleEndian>(self.hash)?;
+        writer.write_u64::<LittleEndian>(self.completed.len() as u64)?;
+        writer.write_u32::<LittleEndian>(self.num_channels as u32)?;
+
+        for (texel, completed) in self.completed.iter().enumerate() {
+            writer.write_u8(*completed as u8)?;
+            if let Some(values) = self.get(texel) {
+                for (value, variance) in values {
+                    writer.write_f32::<LittleEndian>(*value)?;
+                    writer.write_f32::<LittleEndian>(*variance)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reads the checkpoint from the given reader, see [`Self::from_file`].
+    ///
+    /// # Arguments
+    /// * `reader` - The reader from which the checkpoint should be read.
+    /// * `hash` - The expected hash of the scene and the options.
+    /// * `num_texels` - The expected number of texels of all maps.
+    /// * `num_channels` - The expected number of values of each texel.
+    fn from_reader<R: Read>(
+        reader: &mut R,
+        hash: u64,
+        num_texels: usize,
+        num_channels: usize,
+    ) -> Result<Self> {
+        let mut magic = [0u8; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != CHECKPOINT_MAGIC {
+            return Err(Error::IO("Not a checkpoint file".to_string()));
+        }
+
+        let version = reader.read_u32::<LittleEndian>()?;
+        if version != CHECKPOINT_VERSION {
+            return Err(Error::IO(format!(
+                "Unsupported checkpoint version {}",
+                version
+            )));
+        }
+
+        // the header is checked before allocating the texels, s.t. a corrupt header cannot
+        // request more memory than the computation itself
+        let file_hash = reader.read_u64::<LittleEndian>()?;
+        let file_num_texels = reader.read_u64::<LittleEndian>()?;
+        let file_num_channels = reader.read_u32::<LittleEndian>()?;
+        if file_hash != hash
+            || file_num_texels != num_texels as u64
+            || file_num_channels as usize != num_channels
+        {
+            return Err(Error::CheckpointMismatch);
+        }
+
+        let mut checkpoint = Self::new(hash, num_texels, num_channels);
+        let mut texel_values = vec![(0f32, 0f32); num_channels];
+        for texel in 0..num_texels {
+            if reader.read_u8()? != 0 {
+                for (value, variance) in texel_values.iter_mut() {
+                    *value = reader.read_f32::<LittleEndian>()?;
+                    *variance = reader.read_f32::<LittleEndian>()?;
+                }
+
+                checkpoint.set(texel, &texel_values);
+            }
+        }
+
+        Ok(checkpoint)
+    }
+}
+
+/// Records the computed texels and periodically writes them to the checkpoint file. The texels
+/// are recorded without a global lock, s.t. the worker threads do not wait for each other or
+/// for a checkpoint being written.
+pub(crate) struct Checkpointer {
+    /// The hash of the scene and the options, see [`compute_hash`].
+    hash: u64,
+
+    /// The number of values of each texel.
+    num_channels: usize,
+
+    /// Whether each texel has been computed.
+    completed: Vec<AtomicBool>,
+
+    /// The bits of the value and of the variance of all channels of each texel, one texel after
+    /// the other, see [`pack_value`].
+    values: Vec<AtomicU64>,
+
+    /// The options for writing the checkpoints.
+    options: CheckpointOptions,
+
+    /// The time the checkpointer has been created.
+    start: Instant,
+
+    /// The milliseconds since `start`, at which the last checkpoint has been written.
+    last_write: AtomicU64,
+
+    /// Held while a checkpoint is written, s.t. at most one thread writes at a time.
+    writing: Mutex<()>,
+}
+
+impl Checkpointer {
+    /// Creates a new checkpointer, which continues from the given checkpoint.
+    ///
+    /// # Arguments
+    /// * `checkpoint` - The texels computed so far.
+    /// * `options` - The options for writing the checkpoints.
+    pub fn new(checkpoint: Checkpoint, options: CheckpointOptions) -> Self {
+        let values = checkpoint
+            .values
+            .iter()
+            .map(|(value, variance)| AtomicU64::new(pack_value(*value, *variance)))
+            .collect();
+
+        Self {
+            hash: checkpoint.hash,
+            num_channels: checkpoint.num_channels,
+            completed: checkpoint
+                .completed
+                .into_iter()
+                .map(AtomicBool::new)
+                .collect(),
+            values,
+            options,
+            start: Instant::now(),
+            last_write: AtomicU64::new(0),
+            writing: Mutex::new(()),
+        }
+    }
+
+    /// Records the values and the variances of the given texel and writes the checkpoint, if
+    /// the checkpoint interval has passed since the last write and no other thread is writing.
+    ///
+    /// # Arguments
+    /// * `texel` - The index of the texel over all maps.
+    /// * `values` - The values and the variances of all channels of the texel.
+    pub fn record(&self, texel: usize, values: &[(f32, f32)]) {
+        let start = texel * self.num_channels;
+        for (dst, (value, variance)) in self.values[start..start + self.num_channels]
+            .iter()
+            .zip(values)
+        {
+            dst.store(pack_value(*value, *variance), Ordering::Relaxed);
+        }
+
+        // publishes the values to the threads, which see the texel as completed
+        self.completed[texel].store(true, Ordering::Release);
+
+        if self.is_write_due() {
+            if let Ok(_writing) = self.writing.try_lock() {
+                // another thread may have written the checkpoint in the meantime
+                if self.is_write_due() {
+                    self.write_snapshot();
+                }
+            }
+        }
+    }
+
+    /// Writes the checkpoint, after any write in progress has finished. A failed write is
+    /// logged, but does not abort the computation.
+    pub fn write(&self) {
+        let _writing = self.writing.lock().unwrap();
+        self.write_snapshot();
+    }
+
+    /// Returns the texels recorded so far.
+    pub fn snapshot(&self) -> Checkpoint {
+        let mut checkpoint = Checkpoint::new(self.hash, self.completed.len(), self.num_channels);
+        let mut texel_values = vec![(0f32, 0f32); self.num_channels];
+        for (texel, completed) in self.completed.iter().enumerate() {
+            if completed.load(Ordering::Acquire) {
+                let start = texel * self.num_channels;
+                for (dst, value) in texel_values
+                    .iter_mut()
+                    .zip(&self.values[start..start + self.num_channels])
+                {
+                    *dst = unpack_value(value.load(Ordering::Relaxed));
+                }
+
+                checkpoint.set(texel, &texel_values);
+            }
+        }
+
+        checkpoint
+    }
+
+    /// Returns true if the checkpoint interval has passed since the last write.
+    fn is_write_due(&self) -> bool {
+        let elapsed = self.start.elapsed().as_millis() as u64;
+        elapsed.saturating_sub(self.last_write.load(Ordering::Relaxed))
+            >= self.options.interval.as_millis() as u64
+    }
+
+    /// Writes a snapshot of the recorded texels. Must only be called while holding `writing`.
+    fn write_snapshot(&self) {
+        let checkpoint = self.snapshot();
+        let path = &self.options.path;
+        match checkpoint.write_file(path) {
+            Ok(()) => info!(
+                "Wrote checkpoint with {} texels to '{}'",
+                checkpoint.num_completed(),
+                path.to_string_lossy()
+            ),
+            Err(err) => warn!(
+                "Failed writing checkpoint '{}': {}",
+                path.to_string_lossy(),
+                err
+            ),
+        }
+
+        self.last_write
+            .store(self.start.elapsed().as_millis() as u64, Ordering::Relaxed);
+    }
+}
+
+/// Packs the bits of the given value and variance into a single integer, s.t. both can be
+/// stored atomically.
+///
+/// # Arguments
+/// * `value` - The value to pack.
+/// * `variance` - The variance to pack.
+#[inline]
+fn pack_value(value: f32, variance: f32) -> u64 {
+    ((value.to_bits() as u64) << 32) | variance.to_bits() as u64
+}
+
+/// Unpacks the value and the variance packed by [`pack_value`].
+///
+/// # Arguments
+/// * `bits` - The packed value and variance.
+#[inline]
+fn unpack_value(bits: u64) -> (f32, f32) {
+    (
+        f32::from_bits((bits >> 32) as u32),
+        f32::from_bits(bits as u32),
+    )
+}
+
+/// The FNV-1a hash, which, unlike the hasher of the standard library, is stable across
+/// platforms and compiler versions.
+struct Fnv1a(u64);
+
+impl Fnv1a {
+    /// Creates a new hasher with the FNV offset basis.
+    fn new() -> Self {
+        Self(0xcbf29ce484222325)
+    }
+
+    /// Adds the given bytes to the hash.
+    ///
+    /// # Arguments
+    /// * `bytes` - The bytes to add.
+    fn write(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.0 ^= *b as u64;
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        }
+    }
+
+    /// Adds the little-endian bytes of the given integer to the hash.
+    ///
+    /// # Arguments
+    /// * `value` - The integer to add.
+    fn write_u64(&mut self, value: u64) {
+        self.write(&value.to_le_bytes());
+    }
+
+    /// Adds the little-endian bytes of the given float to the hash.
+    ///
+    /// # Arguments
+    /// * `value` - The float to add.
+    fn write_f32(&mut self, value: f32) {
+        self.write(&value.to_le_bytes());
+    }
+}
+
+/// Computes the hash of the scene and of all options, which influence the computed values. The
+/// number of threads is excluded, s.t. a computation can be resumed with different threads.
+///
+/// # Arguments
+/// * `scene` - The scene for which the maps are computed.
+/// * `options` - The options for the pixel contribution calculation.
+/// * `map_configs` - The camera configs and camera distances for which the maps are computed.
+/// * `channels` - Determines how the rendered pixels are split into channels.
+pub(crate) fn compute_hash(
+    scene: &Scene,
+    options: &PixelContributionOptions,
+    map_configs: &[(CameraConfig, f32)],
+    channels: ContributionChannels,
+) -> u64 {
+    let mut h = Fnv1a::new();
+
+    for geometry in scene.get_geometries() {
+        h.write_u64(geometry.positions.len() as u64);
+        geometry
+            .positions
+            .iter()
+            .flat_map(|p| p.iter())
+            .for_each(|v| h.write_f32(*v));
+
+        h.write_u64(geometry.triangles.len() as u64);
+        geometry
+            .triangles
+            .iter()
+            .flatten()
+            .for_each(|i| h.write(&i.to_le_bytes()));
+    }
+
+    for instance in scene.get_instances() {
+        h.write_u64(instance.geometry_index as u64);
+        instance.transform.iter().for_each(|v| h.write_f32(*v));
+    }
+
+    h.write_u64(options.render_options.frame_size as u64);
+    h.write_u64(options.contrib_map_size as u64);
+    h.write_u64(options.samples_per_texel.max(1) as u64);
+
+    h.write(format!("{:?}", options.parameterization).as_bytes());
+
+    match options.sphere_fitting {
+        SphereFitting::Algorithm(algorithm) => {
+            h.write_u64(0);
+            h.write(format!("{:?}", algorithm).as_bytes());
+        }
+        SphereFitting::Sphere(sphere) => {
+            h.write_u64(1);
+            sphere.center.iter().for_each(|v| h.write_f32(*v));
+            h.write_f32(sphere.radius);
+        }
+    }
+
+    match options.sampling {
+        DirectionSampling::Full => h.write_u64(0),
+        DirectionSampling::Adaptive { tolerance } => {
+            h.write_u64(1);
+            h.write_f32(tolerance);
+        }
+    }
+
+    h.write_u64(map_configs.len() as u64);
+    for (camera_config, distance) in map_configs {
+        h.write_f32(*distance);
+        match camera_config {
+            CameraConfig::Orthographic => h.write_u64(0),
+            CameraConfig::Perspective { fovy } => {
+                h.write_u64(1);
+                h.write_f32(*fovy);
+            }
+            CameraConfig::Frustum(frustum) => {
+                let (left, right) = frustum.horizontal_half_angles();
+                let (bottom, top) = frustum.vertical_half_angles();
+
+                h.write_u64(2);
+                h.write_u64(frustum.is_orthographic() as u64);
+                [frustum.aspect_ratio(), left, right, bottom, top]
+                    .into_iter()
+                    .for_each(|v| h.write_f32(v));
+            }
+        }
+    }
+
+    h.write_u64(match channels {
+        ContributionChannels::Assembly => 0,
+        ContributionChannels::AssemblyAndObjects => 1,
+    });
+
+    h.0
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_checkpoint_serialization() {
+        let mut checkpoint = Checkpoint::new(0x1234, 5, 2);
+        checkpoint.set(1, &[(0.5, 0.1), (0.25, 0.0)]);
+        checkpoint.set(4, &[(1.0, 0.0), (0.0, 0.2)]);
+
+        let mut data = Vec::new();
+        checkpoint.write_writer(&mut data).unwrap();
+
+        let checkpoint2 = Checkpoint::from_reader(&mut data.as_slice(), 0x1234, 5, 2).unwrap();
+        assert_eq!(checkpoint, checkpoint2);
+        assert_eq!(checkpoint2.num_completed(), 2);
+        assert_eq!(checkpoint2.get(0), None);
+        assert_eq!(
+            checkpoint2.get(4),
+            Some([(1.0, 0.0), (0.0, 0.2)].as_slice())
+        );
+
+        // checkpoints of other computations are rejected
+        for (hash, num_texels, num_channels) in [(0x1235, 5, 2), (0x1234, 6, 2), (0x1234, 5, 1)] {
+            assert!(matches!(
+                Checkpoint::from_reader(&mut data.as_slice(), hash, num_texels, num_channels),
+                Err(Error::CheckpointMismatch)
+            ));
+        }
+
+        // truncated files are rejected
+        data.truncate(data.len() - 1);
+        assert!(Checkpoint::from_reader(&mut data.as_slice(), 0x1234, 5, 2).is_err());
+
+        data[0] = b'X';
+        assert!(Checkpoint::from_reader(&mut data.as_slice(), 0x1234, 5, 2).is_err());
+    }
+
+    #[test]
+    fn test_checkpoint_corrupt_header() {
+        // a header requesting a huge number of channels is rejected before allocating them
+        let mut data = Vec::new();
+        data.extend_from_slice(CHECKPOINT_MAGIC);
+        data.write_u32::<LittleEndian>(CHECKPOINT_VERSION).unwrap();
+        data.write_u64::<LittleEndian>(0x1234).unwrap();
+        data.write_u64::<LittleEndian>(1).unwrap();
+        data.write_u32::<LittleEndian>(u32::MAX).unwrap();
+        data.push(0);
+
+        assert!(matches!(
+            Checkpoint::from_reader(&mut data.as_slice(), 0x1234, 1, 1),
+            Err(Error::CheckpointMismatch)
+        ));
+    }
+
+    #[test]
+    fn test_checkpointer_record() {
+        use rayon::prelude::*;
+
+        let mut checkpoint = Checkpoint::new(0x1234, 1000, 2);
+        checkpoint.set(3, &[(0.5, 0.25), (1.0, 0.0)]);
+
+        let checkpointer = Checkpointer::new(
+            checkpoint.clone(),
+            CheckpointOptions {
+                path: std::env::temp_dir().join("pixel_contrib_unused_checkpoint.bin"),
+                interval: Duration::from_secs(3600),
+            },
+        );
+        assert_eq!(checkpointer.snapshot(), checkpoint);
+
+        // the texels are recorded concurrently
+        (0..1000)
+            .into_par_iter()
+            .filter(|t| t % 2 == 0)
+            .for_each(|texel| {
+                let values = [(texel as f32, -1.5), (0.0, texel as f32 * 0.5)];
+                checkpointer.record(texel, &values);
+            });
+
+        let snapshot = checkpointer.snapshot();
+        assert_eq!(snapshot.num_completed(), 501);
+        assert_eq!(snapshot.get(3), Some([(0.5, 0.25), (1.0, 0.0)].as_slice()));
+        for texel in (0..1000).step_by(2) {
+            assert_eq!(
+                snapshot.get(texel),
+                Some([(texel as f32, -1.5), (0.0, texel as f32 * 0.5)].as_slice())
+            );
+        }
+    }
+}
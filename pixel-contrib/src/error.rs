@@ -15,6 +15,12 @@ quick_error! {
         InvalidArgument(err: String) {
             display("{}", err)
         }
+        Cancelled {
+            display("The computation has been cancelled")
+        }
+        CheckpointMismatch {
+            display("The checkpoint has been computed for a different scene or different options")
+        }
     }
 }
 
@@ -36,6 +42,7 @@ impl From<TypeError> for Error {
             TypeError::IO(err) => Error::IO(err),
             TypeError::Internal(err) => Error::Internal(err),
             TypeError::InvalidArgument(err) => Error::InvalidArgument(err),
+            err => Error::IO(err.to_string()),
         }
     }
 }
@@ -0,0 +1,76 @@
+use nalgebra_glm::Vec2;
+
+/// Returns the positions of the given number of samples inside a texel, see
+/// [`pixel_contrib_types::PixelContribColorMapDescriptor::camera_dir_from_index_with_offset`].
+/// The texel is divided into a grid of strata and each sample is randomly jittered inside its
+/// stratum. The jitter is derived from the seed, s.t. the samples are reproducible. A single
+/// sample is located at the texel center.
+///
+/// # Arguments
+/// * `num_samples` - The number of samples.
+/// * `seed` - The seed for the jitter, e.g., the index of the texel.
+pub(crate) fn stratified_offsets(num_samples: usize, seed: u64) -> Vec<Vec2> {
+    if num_samples <= 1 {
+        return vec![Vec2::new(0.5, 0.5)];
+    }
+
+    let columns = (num_samples as f32).sqrt().ceil() as usize;
+    let rows = num_samples.div_ceil(columns);
+
+    let mut state = seed;
+    (0..num_samples)
+        .map(|i| {
+            let (x, y) = (i % columns, i / columns);
+            let jitter_x = random_unit(&mut state);
+            let jitter_y = random_unit(&mut state);
+
+            Vec2::new(
+                (x as f32 + jitter_x) / columns as f32,
+                (y as f32 + jitter_y) / rows as f32,
+            )
+        })
+        .collect()
+}
+
+/// Returns a pseudo-random number in [0, 1) and advances the given state using SplitMix64.
+///
+/// # Arguments
+/// * `state` - The state of the generator.
+fn random_unit(state: &mut u64) -> f32 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^= z >> 31;
+
+    // use the upper 24 bits, which are exactly representable as f32
+    (z >> 40) as f32 / (1u64 << 24) as f32
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_stratified_offsets() {
+        assert_eq!(stratified_offsets(1, 42), vec![Vec2::new(0.5, 0.5)]);
+
+        // each sample lies in its own stratum
+        let offsets = stratified_offsets(9, 42);
+        assert_eq!(offsets.len(), 9);
+        for (i, o) in offsets.iter().enumerate() {
+            assert_eq!((o.x * 3.0) as usize, i % 3);
+            assert_eq!((o.y * 3.0) as usize, i / 3);
+        }
+
+        // the offsets are reproducible, but differ between seeds
+        assert_eq!(offsets, stratified_offsets(9, 42));
+        assert_ne!(offsets, stratified_offsets(9, 43));
+
+        let offsets = stratified_offsets(5, 7);
+        assert_eq!(offsets.len(), 5);
+        assert!(offsets
+            .iter()
+            .all(|o| (0.0..1.0).contains(&o.x) && (0.0..1.0).contains(&o.y)));
+    }
+}
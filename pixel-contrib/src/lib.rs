@@ -1,16 +1,26 @@
+mod adaptive;
+mod checkpoint;
 mod error;
+mod jitter;
 pub mod octahedron;
 mod pixel_contribution;
 mod pixel_contribution_map;
 pub mod polygon_2d;
 mod progress;
 pub mod screen_space;
+mod sink;
 mod view;
 
+pub use checkpoint::CheckpointOptions;
 pub use error::*;
-use pixel_contrib_types::{PixelContribColorMapDescriptor, PixelContributionMap};
+use pixel_contrib_types::{
+    CameraFrustum, PixelContribColorMapDescriptor, PixelContributionMap, PixelContributionMaps,
+    SphereParameterization,
+};
 pub use pixel_contribution::*;
 pub use pixel_contribution_map::*;
+pub use progress::{CancellationToken, NoProgress, ProgressReport, ProgressSink, TerminalProgress};
+pub use sink::MapSink;
 use thread_local::ThreadLocal;
 pub use view::*;
 
@@ -18,11 +28,15 @@ use std::sync::{Arc, Mutex};
 
 use log::info;
 use rasterizer::{
-    clamp, Histogram, RenderOptions, RenderStats, Renderer, RendererGeometry, Scene, StatsNode,
-    StatsNodeTrait,
+    clamp, BoundingSphere, BoundingSphereAlgorithm, Histogram, RenderOptions, RenderStats,
+    Renderer, RendererGeometry, Scene, StatsNode, StatsNodeTrait,
 };
 use rayon::prelude::*;
 
+/// The number of cells along each side of the grid, which is used for determining the fraction
+/// of the frame covered by a bounding sphere clipped by the frame.
+const SPHERE_COVERAGE_GRID_SIZE: usize = 512;
+
 /// The options for the camera configuration.
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum CameraConfig {
@@ -34,6 +48,9 @@ pub enum CameraConfig {
         /// The field of view in y-direction in radians.
         fovy: f32,
     },
+
+    /// A camera with the given, possibly non-square or off-axis, frustum.
+    Frustum(CameraFrustum),
 }
 
 impl CameraConfig {
@@ -44,7 +61,90 @@ impl CameraConfig {
         match self {
             CameraConfig::Orthographic => 0f32,
             CameraConfig::Perspective { fovy } => *fovy,
+            CameraConfig::Frustum(frustum) => frustum.sphere_angle(),
+        }
+    }
+
+    /// Returns true if the camera has no perspective.
+    #[inline]
+    pub fn is_orthographic(&self) -> bool {
+        match self {
+            CameraConfig::Orthographic => true,
+            CameraConfig::Perspective { .. } => false,
+            CameraConfig::Frustum(frustum) => frustum.is_orthographic(),
+        }
+    }
+
+    /// Returns the fraction of the frame covered by the bounding sphere fitted into the frustum.
+    pub fn sphere_coverage(&self) -> f32 {
+        use std::f32::consts::PI;
+
+        let frustum = match self {
+            CameraConfig::Orthographic | CameraConfig::Perspective { .. } => return PI / 4f32,
+            CameraConfig::Frustum(frustum) => frustum,
+        };
+
+        // the sphere touches the shorter sides of the frame
+        let aspect_ratio = frustum.aspect_ratio();
+        if frustum.is_orthographic() {
+            return PI / 4f32 * aspect_ratio.min(1f32 / aspect_ratio);
         }
+
+        // The silhouette of the sphere is the cone with the sphere angle around the sphere
+        // direction. Its intersection with the image plane at distance 1 is an ellipse.
+        let (left, right) = frustum.horizontal_half_angles();
+        let (bottom, top) = frustum.vertical_half_angles();
+        let frame_area = (left.tan() + right.tan()) * (bottom.tan() + top.tan());
+
+        let (sin_a, cos_a) = (frustum.sphere_angle() / 2f32).sin_cos();
+        let cos_t = -frustum.sphere_direction().z;
+        let denom = cos_t * cos_t - sin_a * sin_a;
+        let ellipse_area = PI * (sin_a * cos_a / denom) * (sin_a / denom.sqrt());
+
+        ellipse_area / frame_area
+    }
+
+    /// Returns the fraction of the frame covered by the bounding sphere, if the camera is placed at
+    /// the given distance from the center of the sphere, see [`View::new_from_sphere_at_distance`].
+    /// Close to the sphere, its silhouette is clipped by the frame. Therefore, the fraction is
+    /// determined by testing the directions through the cells of a regular grid on the frame.
+    ///
+    /// # Arguments
+    /// * `distance` - The distance as multiple of the sphere radius. A distance of 0 denotes the
+    ///   distance fitting the frustum, see [`Self::sphere_coverage`].
+    pub fn sphere_coverage_at_distance(&self, distance: f32) -> f32 {
+        if distance <= 0f32 || self.is_orthographic() {
+            return self.sphere_coverage();
+        }
+
+        let frustum = match self {
+            CameraConfig::Perspective { fovy } => CameraFrustum::perspective(*fovy, 1f32),
+            CameraConfig::Frustum(frustum) => *frustum,
+            CameraConfig::Orthographic => CameraFrustum::orthographic(1f32),
+        };
+
+        // the corners of the frame on the image plane at distance 1
+        let (left, right) = frustum.horizontal_half_angles();
+        let (bottom, top) = frustum.vertical_half_angles();
+        let (x0, x1) = (-left.tan(), right.tan());
+        let (y0, y1) = (-bottom.tan(), top.tan());
+
+        // the silhouette of the sphere is the cone with half the angle under which the sphere is
+        // seen around the sphere direction
+        let dir = frustum.sphere_direction();
+        let cos_angle = (1f32 - 1f32 / (distance * distance)).sqrt();
+
+        let n = SPHERE_COVERAGE_GRID_SIZE;
+        let num_covered = (0..n * n)
+            .filter(|i| {
+                let x = x0 + (x1 - x0) * ((i % n) as f32 + 0.5f32) / n as f32;
+                let y = y0 + (y1 - y0) * ((i / n) as f32 + 0.5f32) / n as f32;
+
+                nalgebra_glm::Vec3::new(x, y, -1f32).normalize().dot(&dir) >= cos_angle
+            })
+            .count();
+
+        num_covered as f32 / (n * n) as f32
     }
 }
 
@@ -55,6 +155,64 @@ impl ToString for CameraConfig {
             CameraConfig::Perspective { fovy } => {
                 format!("Perspective(fovy={} degree)", fovy.to_degrees())
             }
+            CameraConfig::Frustum(frustum) => {
+                let (left, right) = frustum.horizontal_half_angles();
+                let (bottom, top) = frustum.vertical_half_angles();
+                format!(
+                    "Frustum(aspect={}, left={} degree, right={} degree, bottom={} degree, top={} degree)",
+                    frustum.aspect_ratio(),
+                    left.to_degrees(),
+                    right.to_degrees(),
+                    bottom.to_degrees(),
+                    top.to_degrees()
+                )
+            }
+        }
+    }
+}
+
+/// The sampling of the camera directions, i.e., the texels of the map, which are rendered.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum DirectionSampling {
+    /// A frame is rendered for each texel of the map.
+    #[default]
+    Full,
+
+    /// A frame is rendered for each texel of a coarse grid and the grid is refined only where
+    /// the values vary. The remaining texels are interpolated.
+    Adaptive {
+        /// The maximal difference between the rendered values surrounding a region of texels,
+        /// s.t. the region is interpolated instead of being refined. Larger values save more
+        /// renders at the cost of accuracy.
+        tolerance: f32,
+    },
+}
+
+/// Determines the bounding sphere, to which the camera is fitted.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SphereFitting {
+    /// The sphere is fitted to the vertices of the scene using the given algorithm.
+    Algorithm(BoundingSphereAlgorithm),
+
+    /// The given sphere is used, e.g., to match the sphere used by an engine at runtime.
+    Sphere(BoundingSphere),
+}
+
+impl Default for SphereFitting {
+    fn default() -> Self {
+        Self::Algorithm(BoundingSphereAlgorithm::default())
+    }
+}
+
+impl SphereFitting {
+    /// Returns the bounding sphere of the given scene, to which the camera is fitted.
+    ///
+    /// # Arguments
+    /// * `scene` - The scene to fit the sphere to.
+    pub fn bounding_sphere(&self, scene: &Scene) -> BoundingSphere {
+        match *self {
+            SphereFitting::Algorithm(algorithm) => scene.compute_bounding_sphere_with(algorithm),
+            SphereFitting::Sphere(sphere) => sphere,
         }
     }
 }
@@ -72,9 +230,44 @@ pub struct PixelContributionOptions {
 
     /// The camera config to be used for calculating the pixel contribution.
     pub camera_config: CameraConfig,
+
+    /// The distances between the camera and the center of the bounding sphere as multiples of the
+    /// sphere radius, for which maps are computed in addition to the distance fitting the sphere
+    /// into the frustum. Each distance must be larger than 1. Orthographic cameras ignore the
+    /// distances.
+    pub camera_distances: Vec<f32>,
+
+    /// The parameterization of the camera directions onto the pixel contribution map.
+    pub parameterization: SphereParameterization,
+
+    /// The bounding sphere, to which the camera is fitted.
+    pub sphere_fitting: SphereFitting,
+
+    /// The sampling of the camera directions.
+    pub sampling: DirectionSampling,
+
+    /// The number of jittered camera directions rendered and averaged for each texel. The
+    /// directions are stratified inside the texel. A value of 1 renders the texel center only.
+    pub samples_per_texel: usize,
+
+    /// The options for periodically writing the computed texels to a checkpoint file, from
+    /// which an interrupted computation can be resumed, see [`resume_contribution_maps`].
+    pub checkpoint: Option<CheckpointOptions>,
+
+    /// The sink receiving the progress of the computation.
+    pub progress: Arc<dyn ProgressSink>,
+
+    /// The sink receiving each map as soon as it is finished, if any. The finished maps are
+    /// returned at the end of the computation as well.
+    pub map_sink: Option<Arc<dyn MapSink>>,
+
+    /// The token for cancelling the computation. A cancelled computation writes its checkpoint,
+    /// if configured, and returns [`Error::Cancelled`].
+    pub cancellation: CancellationToken,
 }
 
-/// Computes the pixel contribution map for the given scene.
+/// Computes the pixel contribution map for the given scene with the camera placed at the
+/// distance fitting the frustum. The camera distances of the options are ignored.
 ///
 /// # Arguments
 /// * `scene` - The scene for which the pixel contribution map should be computed.
@@ -86,7 +279,392 @@ pub fn compute_contribution_map<R>(
     stats: StatsNode,
     options: &PixelContributionOptions,
     render_stats: &mut RenderStats,
-) -> PixelContributionMap
+) -> Result<PixelContributionMap>
+where
+    R: Renderer,
+{
+    let mut maps = compute_channel_maps::<R>(
+        scene,
+        stats,
+        options,
+        &map_configs(&[options.camera_config], &[])?,
+        render_stats,
+        ContributionChannels::Assembly,
+        None,
+    )?;
+
+    let (mut maps, _) = maps.pop().unwrap();
+    Ok(maps.maps.pop().unwrap())
+}
+
+/// Computes the pixel contribution maps for the given scene and all given camera configs in a
+/// single pass. The geometry, the bounding sphere and the renderers are shared by all maps and
+/// all pairs of camera config and camera direction are scheduled as one parallel job. Besides the
+/// map at the distance fitting the frustum, a map is computed for each camera config at each of
+/// the [`PixelContributionOptions::camera_distances`].
+///
+/// # Arguments
+/// * `scene` - The scene for which the pixel contribution maps should be computed.
+/// * `stats` - The stats node to log the timing for the computation.
+/// * `options` - The options for the pixel contribution calculation. The camera config of the
+///   options is ignored in favor of `camera_configs`.
+/// * `camera_configs` - The camera configs for which a map should be computed each.
+/// * `render_stats` - The stats node to log the rendering stats.
+pub fn compute_contribution_maps<R>(
+    scene: &Scene,
+    stats: StatsNode,
+    options: &PixelContributionOptions,
+    camera_configs: &[CameraConfig],
+    render_stats: &mut RenderStats,
+) -> Result<PixelContributionMaps>
+where
+    R: Renderer,
+{
+    let (maps, _) = compute_contribution_maps_with_variance::<R>(
+        scene,
+        stats,
+        options,
+        camera_configs,
+        render_stats,
+    )?;
+
+    Ok(maps)
+}
+
+/// Computes the pixel contribution maps like [`compute_contribution_maps`] and additionally
+/// returns maps with the variance of the jittered samples of each texel, see
+/// [`PixelContributionOptions::samples_per_texel`]. The variance maps have the same descriptors
+/// as the pixel contribution maps.
+///
+/// # Arguments
+/// * `scene` - The scene for which the pixel contribution maps should be computed.
+/// * `stats` - The stats node to log the timing for the computation.
+/// * `options` - The options for the pixel contribution calculation. The camera config of the
+///   options is ignored in favor of `camera_configs`.
+/// * `camera_configs` - The camera configs for which a map should be computed each.
+/// * `render_stats` - The stats node to log the rendering stats.
+pub fn compute_contribution_maps_with_variance<R>(
+    scene: &Scene,
+    stats: StatsNode,
+    options: &PixelContributionOptions,
+    camera_configs: &[CameraConfig],
+    render_stats: &mut RenderStats,
+) -> Result<(PixelContributionMaps, PixelContributionMaps)>
+where
+    R: Renderer,
+{
+    let mut maps = compute_channel_maps::<R>(
+        scene,
+        stats,
+        options,
+        &map_configs(camera_configs, &options.camera_distances)?,
+        render_stats,
+        ContributionChannels::Assembly,
+        None,
+    )?;
+
+    Ok(maps.pop().unwrap())
+}
+
+/// Resumes the computation of the pixel contribution maps from the checkpoint file configured
+/// in [`PixelContributionOptions::checkpoint`]. The texels stored in the checkpoint are not
+/// rendered again. Returns the same maps as [`compute_contribution_maps`].
+///
+/// Returns an error, if the checkpoint cannot be read or if it has been computed for a different
+/// scene or different options.
+///
+/// # Arguments
+/// * `scene` - The scene for which the pixel contribution maps should be computed.
+/// * `stats` - The stats node to log the timing for the computation.
+/// * `options` - The options for the pixel contribution calculation. The camera config of the
+///   options is ignored in favor of `camera_configs`.
+/// * `camera_configs` - The camera configs for which a map should be computed each.
+/// * `render_stats` - The stats node to log the rendering stats.
+pub fn resume_contribution_maps<R>(
+    scene: &Scene,
+    stats: StatsNode,
+    options: &PixelContributionOptions,
+    camera_configs: &[CameraConfig],
+    render_stats: &mut RenderStats,
+) -> Result<PixelContributionMaps>
+where
+    R: Renderer,
+{
+    let (maps, _) = resume_contribution_maps_with_variance::<R>(
+        scene,
+        stats,
+        options,
+        camera_configs,
+        render_stats,
+    )?;
+
+    Ok(maps)
+}
+
+/// Resumes the computation of the pixel contribution maps and their variance maps from the
+/// checkpoint file like [`resume_contribution_maps`]. Returns the same maps as
+/// [`compute_contribution_maps_with_variance`].
+///
+/// # Arguments
+/// * `scene` - The scene for which the pixel contribution maps should be computed.
+/// * `stats` - The stats node to log the timing for the computation.
+/// * `options` - The options for the pixel contribution calculation. The camera config of the
+///   options is ignored in favor of `camera_configs`.
+/// * `camera_configs` - The camera configs for which a map should be computed each.
+/// * `render_stats` - The stats node to log the rendering stats.
+pub fn resume_contribution_maps_with_variance<R>(
+    scene: &Scene,
+    stats: StatsNode,
+    options: &PixelContributionOptions,
+    camera_configs: &[CameraConfig],
+    render_stats: &mut RenderStats,
+) -> Result<(PixelContributionMaps, PixelContributionMaps)>
+where
+    R: Renderer,
+{
+    let mut maps = resume_channel_maps::<R>(
+        scene,
+        stats,
+        options,
+        camera_configs,
+        render_stats,
+        ContributionChannels::Assembly,
+    )?;
+
+    Ok(maps.pop().unwrap())
+}
+
+/// Computes the pixel contribution maps and their variance maps like
+/// [`compute_contribution_maps_with_variance`] and the pixel contribution maps of each instance
+/// like [`compute_object_contribution_maps`] from the same renders, i.e., each view is rendered
+/// only once for all maps. The maps of the instances are returned in the order of the instances.
+///
+/// # Arguments
+/// * `scene` - The scene for which the pixel contribution maps should be computed.
+/// * `stats` - The stats node to log the timing for the computation.
+/// * `options` - The options for the pixel contribution calculation. The camera config of the
+///   options is ignored in favor of `camera_configs`.
+/// * `camera_configs` - The camera configs for which a map should be computed each.
+/// * `render_stats` - The stats node to log the rendering stats.
+pub fn compute_contribution_maps_with_objects<R>(
+    scene: &Scene,
+    stats: StatsNode,
+    options: &PixelContributionOptions,
+    camera_configs: &[CameraConfig],
+    render_stats: &mut RenderStats,
+) -> Result<(
+    PixelContributionMaps,
+    PixelContributionMaps,
+    Vec<PixelContributionMaps>,
+)>
+where
+    R: Renderer,
+{
+    let maps = compute_channel_maps::<R>(
+        scene,
+        stats,
+        options,
+        &map_configs(camera_configs, &options.camera_distances)?,
+        render_stats,
+        ContributionChannels::AssemblyAndObjects,
+        None,
+    )?;
+
+    Ok(split_object_maps(maps))
+}
+
+/// Resumes the computation of the maps of [`compute_contribution_maps_with_objects`] from the
+/// checkpoint file like [`resume_contribution_maps`].
+///
+/// # Arguments
+/// * `scene` - The scene for which the pixel contribution maps should be computed.
+/// * `stats` - The stats node to log the timing for the computation.
+/// * `options` - The options for the pixel contribution calculation. The camera config of the
+///   options is ignored in favor of `camera_configs`.
+/// * `camera_configs` - The camera configs for which a map should be computed each.
+/// * `render_stats` - The stats node to log the rendering stats.
+pub fn resume_contribution_maps_with_objects<R>(
+    scene: &Scene,
+    stats: StatsNode,
+    options: &PixelContributionOptions,
+    camera_configs: &[CameraConfig],
+    render_stats: &mut RenderStats,
+) -> Result<(
+    PixelContributionMaps,
+    PixelContributionMaps,
+    Vec<PixelContributionMaps>,
+)>
+where
+    R: Renderer,
+{
+    let maps = resume_channel_maps::<R>(
+        scene,
+        stats,
+        options,
+        camera_configs,
+        render_stats,
+        ContributionChannels::AssemblyAndObjects,
+    )?;
+
+    Ok(split_object_maps(maps))
+}
+
+/// Computes the pixel contribution maps of each instance of the given scene from the same renders
+/// and returns them in the order of the instances. The camera is fitted to the bounding sphere
+/// of the whole scene for all instances and the values are normalized like the values of the
+/// scene, s.t. the maps of all instances sum up to the maps returned by
+/// [`compute_contribution_maps`]. The index of the instance is stored in the metadata of its
+/// maps.
+///
+/// # Arguments
+/// * `scene` - The scene for whose instances the pixel contribution maps should be computed.
+/// * `stats` - The stats node to log the timing for the computation.
+/// * `options` - The options for the pixel contribution calculation. The camera config of the
+///   options is ignored in favor of `camera_configs`.
+/// * `camera_configs` - The camera configs for which a map should be computed each.
+/// * `render_stats` - The stats node to log the rendering stats.
+pub fn compute_object_contribution_maps<R>(
+    scene: &Scene,
+    stats: StatsNode,
+    options: &PixelContributionOptions,
+    camera_configs: &[CameraConfig],
+    render_stats: &mut RenderStats,
+) -> Result<Vec<PixelContributionMaps>>
+where
+    R: Renderer,
+{
+    let (_, _, object_maps) = compute_contribution_maps_with_objects::<R>(
+        scene,
+        stats,
+        options,
+        camera_configs,
+        render_stats,
+    )?;
+
+    Ok(object_maps)
+}
+
+/// Resumes the computation of the maps of all channels from the checkpoint file configured in
+/// [`PixelContributionOptions::checkpoint`], see [`compute_channel_maps`].
+///
+/// # Arguments
+/// * `scene` - The scene for which the pixel contribution maps should be computed.
+/// * `stats` - The stats node to log the timing for the computation.
+/// * `options` - The options for the pixel contribution calculation.
+/// * `camera_configs` - The camera configs for which a map should be computed each.
+/// * `render_stats` - The stats node to log the rendering stats.
+/// * `channels` - Determines how the rendered pixels are split into channels.
+fn resume_channel_maps<R>(
+    scene: &Scene,
+    stats: StatsNode,
+    options: &PixelContributionOptions,
+    camera_configs: &[CameraConfig],
+    render_stats: &mut RenderStats,
+    channels: ContributionChannels,
+) -> Result<Vec<(PixelContributionMaps, PixelContributionMaps)>>
+where
+    R: Renderer,
+{
+    let checkpoint_options = options.checkpoint.as_ref().ok_or_else(|| {
+        Error::InvalidArgument("No checkpoint file has been configured".to_string())
+    })?;
+
+    let map_configs = map_configs(camera_configs, &options.camera_distances)?;
+    let hash = checkpoint::compute_hash(scene, options, &map_configs, channels);
+    let num_texels = options.contrib_map_size.pow(2) * map_configs.len();
+    let checkpoint = checkpoint::Checkpoint::from_file(
+        &checkpoint_options.path,
+        hash,
+        num_texels,
+        channels.num_channels(scene),
+    )?;
+
+    info!(
+        "Resume from checkpoint '{}' with {} of {} texels",
+        checkpoint_options.path.to_string_lossy(),
+        checkpoint.num_completed(),
+        num_texels
+    );
+
+    compute_channel_maps::<R>(
+        scene,
+        stats,
+        options,
+        &map_configs,
+        render_stats,
+        channels,
+        Some(checkpoint),
+    )
+}
+
+/// Splits the maps computed for [`ContributionChannels::AssemblyAndObjects`] into the maps and
+/// the variance maps of the whole scene and the maps of each instance.
+///
+/// # Arguments
+/// * `maps` - The maps and the variance maps of each channel.
+fn split_object_maps(
+    maps: Vec<(PixelContributionMaps, PixelContributionMaps)>,
+) -> (
+    PixelContributionMaps,
+    PixelContributionMaps,
+    Vec<PixelContributionMaps>,
+) {
+    let mut maps = maps.into_iter();
+    let (assembly_maps, variance_maps) = maps.next().unwrap();
+    let object_maps = maps.map(|(maps, _)| maps).collect();
+
+    (assembly_maps, variance_maps, object_maps)
+}
+
+/// Determines how the rendered pixels are split into the channels of the computed maps.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ContributionChannels {
+    /// A single channel with the pixels of the whole scene.
+    Assembly,
+
+    /// A channel with the pixels of the whole scene followed by one channel per instance of the
+    /// scene with the pixels of the instance.
+    AssemblyAndObjects,
+}
+
+impl ContributionChannels {
+    /// Returns the number of channels for the given scene.
+    ///
+    /// # Arguments
+    /// * `scene` - The scene for which the maps are computed.
+    fn num_channels(self, scene: &Scene) -> usize {
+        match self {
+            ContributionChannels::Assembly => 1,
+            ContributionChannels::AssemblyAndObjects => 1 + scene.get_instances().len(),
+        }
+    }
+}
+
+/// Computes the pixel contribution maps and their variance maps for all given camera configs
+/// and camera distances and all channels in a single pass. The geometry, the bounding sphere and
+/// the renderers are shared by all maps and all pairs of map and camera direction are scheduled
+/// as one parallel job.
+///
+/// Returns the maps and the variance maps of each channel.
+///
+/// # Arguments
+/// * `scene` - The scene for which the pixel contribution maps should be computed.
+/// * `stats` - The stats node to log the timing for the computation.
+/// * `options` - The options for the pixel contribution calculation.
+/// * `map_configs` - The camera configs and camera distances for which a map should be computed
+///   each, see [`map_configs`].
+/// * `render_stats` - The stats node to log the rendering stats.
+/// * `channels` - Determines how the rendered pixels are split into channels.
+/// * `checkpoint` - The texels computed by a previous, interrupted computation, if any.
+fn compute_channel_maps<R>(
+    scene: &Scene,
+    stats: StatsNode,
+    options: &PixelContributionOptions,
+    map_configs: &[(CameraConfig, f32)],
+    render_stats: &mut RenderStats,
+    channels: ContributionChannels,
+    checkpoint: Option<checkpoint::Checkpoint>,
+) -> Result<Vec<(PixelContributionMaps, PixelContributionMaps)>>
 where
     R: Renderer,
 {
@@ -101,106 +679,358 @@ where
     // initialize render stats to 0
     *render_stats = Default::default();
 
-    // Determine the maximum number of pixels that can be filled. This can only be the bounding
-    // sphere fit tightly into the screen, i.e., the largest possible sphere on the screen.
-    // Therefore, the maximal number of possible pixels is the area of the 2D sphere filling
-    // the quadratic frame.
-    let max_num_pixels_filled = {
-        let r = options.render_options.frame_size as f32 / 2f32;
-        std::f32::consts::PI * r * r
-    };
-
-    let angle = match options.camera_config {
-        CameraConfig::Orthographic => 0f32,
-        CameraConfig::Perspective { fovy } => fovy,
-    };
-
     let contrib_map_size = options.contrib_map_size;
     let render_options = options.render_options.clone();
-    let descriptor = PixelContribColorMapDescriptor::new(contrib_map_size, angle);
+    let frame_size = render_options.frame_size as f32;
+
+    // Determine the maximum number of pixels that can be filled for each camera config. This can
+    // only be the bounding sphere fit tightly into the screen, i.e., the largest possible sphere
+    // on the screen. Therefore, the maximal number of possible pixels is the area of the
+    // projected sphere filling the frame. Non-square frusta are stretched onto the quadratic
+    // frame buffer, which preserves the covered fraction of the frame. Closer to the sphere, only
+    // the part of the projected sphere inside of the frame can be filled.
+    let max_num_pixels_filled: Vec<f32> = map_configs
+        .iter()
+        .map(|(c, distance)| c.sphere_coverage_at_distance(*distance) * frame_size * frame_size)
+        .collect();
+
+    let descriptors: Vec<PixelContribColorMapDescriptor> = map_configs
+        .iter()
+        .map(|(camera_config, distance)| {
+            match *camera_config {
+                CameraConfig::Frustum(frustum) => PixelContribColorMapDescriptor::new_with_frustum(
+                    contrib_map_size,
+                    frustum,
+                    options.parameterization,
+                ),
+                camera_config => PixelContribColorMapDescriptor::new_with_parameterization(
+                    contrib_map_size,
+                    camera_config.angle(),
+                    options.parameterization,
+                ),
+            }
+            .with_distance(*distance)
+        })
+        .collect();
+
+    let num_channels = channels.num_channels(scene);
 
     info!(
-        "Computing pixel contribution map for {}x{} pixels",
-        contrib_map_size, contrib_map_size
+        "Computing {} pixel contribution maps for {}x{} pixels and {} channels",
+        map_configs.len(),
+        contrib_map_size,
+        contrib_map_size,
+        num_channels
     );
 
     let geo = R::G::new(scene, stats.get_child("render_geo"));
-    let bounding_sphere = scene.compute_bounding_sphere();
+    let bounding_sphere = options.sphere_fitting.bounding_sphere(scene);
     info!(
         "Bounding sphere: Center={}, Radius={}",
         bounding_sphere.center, bounding_sphere.radius
     );
 
+    let num_values_per_map = contrib_map_size * contrib_map_size;
+    let num_values = num_values_per_map * map_configs.len();
+
     let mtx_render_stats = Arc::new(Mutex::new(RenderStats::default()));
     let renderer: ThreadLocal<Arc<Mutex<R>>> = ThreadLocal::new();
-    let progress = Arc::new(Mutex::new(progress::Progress::new(descriptor.num_values())));
+    let progress = progress::ProgressTracker::new(options.progress.as_ref(), num_values);
+    let cancellation = &options.cancellation;
 
-    let pixel_contrib = thread_pool.install(|| {
-        let mut pixel_contrib = PixelContributionMap::new(descriptor);
+    let samples_per_texel = options.samples_per_texel.max(1);
 
-        pixel_contrib
-            .pixel_contrib
-            .par_iter_mut()
-            .enumerate()
-            .for_each(|(index, p)| {
-                progress.lock().unwrap().update();
+    // the computed texels are recorded, if configured, s.t. an interrupted computation can be
+    // resumed
+    let checkpointer = options.checkpoint.as_ref().map(|checkpoint_options| {
+        let checkpoint = checkpoint.clone().unwrap_or_else(|| {
+            let hash = checkpoint::compute_hash(scene, options, map_configs, channels);
+            checkpoint::Checkpoint::new(hash, num_values, num_channels)
+        });
 
-                // create renderer if not already done
-                let mut renderer = renderer
-                    .get_or(|| {
-                        let mut r = R::new(stats.clone());
-                        r.initialize(render_options.clone()).unwrap();
+        checkpoint::Checkpointer::new(checkpoint, checkpoint_options.clone())
+    });
 
-                        Arc::new(Mutex::new(r))
-                    })
-                    .lock()
-                    .unwrap();
+    // the finished maps are passed to the sink, if any. The texels of adaptively sampled maps
+    // are only final after the interpolation, i.e., these maps are passed at the end
+    let mut collector = options
+        .map_sink
+        .as_ref()
+        .map(|sink| sink::MapCollector::new(sink.as_ref(), &descriptors, num_channels));
+    let stream_texels = options.sampling == DirectionSampling::Full;
+    let is_aborted =
+        || cancellation.is_cancelled() || collector.as_ref().is_some_and(|c| c.has_failed());
 
-                let camera_dir = descriptor.camera_dir_from_index(index);
+    // renders the values and the variances of all channels of the texel with the given index of
+    // the map with the given index
+    let compute_value = |map_index: usize, index: usize| {
+        // skip texels, which have already been computed before an interruption
+        let texel = map_index * num_values_per_map + index;
+        if let Some(values) = checkpoint.as_ref().and_then(|c| c.get(texel)) {
+            return Some(values.to_vec());
+        }
+
+        // the remaining texels are skipped without being recorded after a cancellation
+        if is_aborted() {
+            return None;
+        }
+
+        // create renderer if not already done
+        let mut renderer = renderer
+            .get_or(|| {
+                let mut r = R::new(stats.clone());
+                r.initialize(render_options.clone()).unwrap();
+
+                Arc::new(Mutex::new(r))
+            })
+            .lock()
+            .unwrap();
+
+        let renderer: &mut R = &mut renderer;
+        let seed = texel as u64;
+        let samples: Vec<Vec<f32>> = jitter::stratified_offsets(samples_per_texel, seed)
+            .into_iter()
+            .map(|offset| {
+                let camera_dir =
+                    descriptors[map_index].camera_dir_from_index_with_offset(index, offset);
 
                 // create view based on the view direction
-                let view =
-                    View::new_from_sphere(&bounding_sphere, options.camera_config, camera_dir);
+                let (camera_config, distance) = map_configs[map_index];
+                let view = View::new_from_sphere_at_distance(
+                    &bounding_sphere,
+                    camera_config,
+                    camera_dir,
+                    distance,
+                );
 
                 // render the scene
-                let renderer: &mut R = &mut renderer;
-                let num_pixels_filled =
-                    count_number_of_filled_pixel(renderer, &view, &geo, mtx_render_stats.clone());
+                let histogram = render_histogram(renderer, &view, &geo, mtx_render_stats.clone());
+
+                let num_pixels_filled: Vec<u32> = match channels {
+                    ContributionChannels::Assembly => vec![histogram.iter().sum()],
+                    ContributionChannels::AssemblyAndObjects => {
+                        std::iter::once(histogram.iter().sum())
+                            .chain(
+                                (0..num_channels - 1)
+                                    .map(|id| histogram.get(id).copied().unwrap_or_default()),
+                            )
+                            .collect()
+                    }
+                };
+
+                num_pixels_filled
+                    .into_iter()
+                    .map(|n| n as f32 / max_num_pixels_filled[map_index])
+                    .collect()
+            })
+            .collect();
+
+        let n = samples.len() as f32;
+        let values: Vec<(f32, f32)> = (0..num_channels)
+            .map(|channel| {
+                let mean = samples.iter().map(|s| s[channel]).sum::<f32>() / n;
+                let variance = samples
+                    .iter()
+                    .map(|s| (s[channel] - mean) * (s[channel] - mean))
+                    .sum::<f32>()
+                    / n;
+
+                (mean, variance)
+            })
+            .collect();
+
+        if let Some(checkpointer) = &checkpointer {
+            checkpointer.record(texel, &values);
+        }
+
+        Some(values)
+    };
+
+    let render_value = |map_index: usize, index: usize| match compute_value(map_index, index) {
+        Some(values) => {
+            if let Some(collector) = collector.as_ref().filter(|_| stream_texels) {
+                collector.record(map_index, index, &values);
+            }
+
+            values
+        }
+        None => vec![(0f32, 0f32); num_channels],
+    };
+
+    // the values of all maps are computed as one job, s.t. the load is balanced across all maps
+    let (values, variances, num_rendered) = thread_pool.install(|| match options.sampling {
+        DirectionSampling::Full => {
+            let texels: Vec<Vec<(f32, f32)>> = (0..num_values)
+                .into_par_iter()
+                .map(|i| {
+                    progress.advance();
+                    render_value(i / num_values_per_map, i % num_values_per_map)
+                })
+                .collect();
 
-                *p = num_pixels_filled as f32 / max_num_pixels_filled;
-            });
+            let (values, variances) = (0..num_channels)
+                .map(|channel| texels.iter().map(|t| t[channel]).unzip())
+                .unzip();
 
-        pixel_contrib
+            (values, variances, num_values)
+        }
+        DirectionSampling::Adaptive { tolerance } => adaptive::sample_adaptive(
+            map_configs.len(),
+            contrib_map_size,
+            num_channels,
+            tolerance,
+            render_value,
+            |num_texels| progress.advance_by(num_texels),
+        ),
     });
 
+    if let Some(checkpointer) = &checkpointer {
+        checkpointer.write();
+    }
+    progress.finish();
+
     *render_stats = mtx_render_stats.lock().unwrap().clone();
+    if let Some(err) = collector
+        .take_if(|c| c.has_failed())
+        .and_then(|c| c.into_error())
+    {
+        return Err(err);
+    }
+    if cancellation.is_cancelled() {
+        info!("The computation has been cancelled");
+        return Err(Error::Cancelled);
+    }
 
-    println!();
+    render_stats.num_frames_saved = (num_values - num_rendered) * samples_per_texel;
 
-    info!(
-        "Max contribution: {} ",
-        pixel_contrib
-            .pixel_contrib
+    if num_rendered < num_values {
+        info!(
+            "Rendered {} of {} directions, saved {} renders",
+            num_rendered,
+            num_values,
+            num_values - num_rendered
+        );
+    }
+
+    if let Some(collector) = collector.filter(|_| !stream_texels) {
+        for (channel, (values, variances)) in values.iter().zip(variances.iter()).enumerate() {
+            let chunks = values.chunks_exact(num_values_per_map.max(1));
+            let variance_chunks = variances.chunks_exact(num_values_per_map.max(1));
+            for (map_index, (values, variances)) in chunks.zip(variance_chunks).enumerate() {
+                collector.finish_map(map_index, channel, values, variances);
+            }
+        }
+
+        if let Some(err) = collector.into_error() {
+            return Err(err);
+        }
+    }
+
+    let create_maps = |values: &[f32]| -> Vec<PixelContributionMap> {
+        descriptors
             .iter()
-            .fold(0f32, |a, b| a.max(*b))
-    );
+            .zip(values.chunks_exact(num_values_per_map.max(1)))
+            .map(|(descriptor, values)| {
+                let mut map = PixelContributionMap::new(*descriptor);
+                map.pixel_contrib.copy_from_slice(values);
+
+                map
+            })
+            .collect()
+    };
+
+    let maps = values
+        .iter()
+        .zip(variances.iter())
+        .enumerate()
+        .map(|(channel, (values, variances))| {
+            let channel_maps = create_maps(values);
+            if channel == 0 {
+                for (map, (camera_config, distance)) in channel_maps.iter().zip(map_configs) {
+                    let stats = map.statistics();
+                    if *distance > 0f32 {
+                        info!("{} at distance {}:", camera_config.to_string(), distance);
+                    } else {
+                        info!("{}:", camera_config.to_string());
+                    }
+                    info!(
+                        "  Max contribution: {} at {:?}",
+                        stats.max,
+                        stats.argmax.as_slice()
+                    );
+                    info!("  Mean contribution: {} ", stats.mean);
+                }
+            }
+
+            let mut maps = PixelContributionMaps::from_maps(channel_maps);
+            maps.metadata_mut().set_bounding_sphere(&bounding_sphere);
+            maps.metadata_mut()
+                .set_frame_size(render_options.frame_size);
+            if channel > 0 {
+                maps.metadata_mut().set_instance_index(channel - 1);
+            }
+
+            let mut variance_maps = PixelContributionMaps::from_maps(create_maps(variances));
+            variance_maps.metadata = maps.metadata.clone();
+
+            (maps, variance_maps)
+        })
+        .collect();
 
-    pixel_contrib
+    Ok(maps)
 }
 
-/// Counts the number of filled pixels for the given view and geometry using the given renderer.
+/// Returns the camera config and the camera distance of each map to compute, where a distance of
+/// 0 denotes the distance fitting the frustum. Each camera config is combined with the fitted
+/// distance and with all given distances, except for orthographic cameras, which do not depend on
+/// the distance.
+///
+/// # Arguments
+/// * `camera_configs` - The camera configs for which the maps should be computed.
+/// * `camera_distances` - The additional camera distances as multiples of the sphere radius.
+fn map_configs(
+    camera_configs: &[CameraConfig],
+    camera_distances: &[f32],
+) -> Result<Vec<(CameraConfig, f32)>> {
+    if let Some(distance) = camera_distances
+        .iter()
+        .find(|d| !d.is_finite() || **d <= 1f32)
+    {
+        return Err(Error::InvalidArgument(format!(
+            "The camera distance {} must be larger than 1",
+            distance
+        )));
+    }
+
+    Ok(camera_configs
+        .iter()
+        .flat_map(|camera_config| {
+            let distances = if camera_config.is_orthographic() {
+                &[]
+            } else {
+                camera_distances
+            };
+
+            std::iter::once((*camera_config, 0f32))
+                .chain(distances.iter().map(|distance| (*camera_config, *distance)))
+        })
+        .collect())
+}
+
+/// Renders the given view and geometry using the given renderer and returns the number of
+/// filled pixels of each object.
 ///
 /// # Arguments
 /// * `renderer` - The renderer to use for the computation.
 /// * `view` - The view for which the number of filled pixels should be computed.
 /// * `geo` - The geometry to render.
 /// * `mtx_render_stats` - The stats node to log the rendering stats.
-fn count_number_of_filled_pixel<R: Renderer>(
+fn render_histogram<R: Renderer>(
     renderer: &mut R,
     view: &View,
     geo: &R::G,
     mtx_render_stats: Arc<Mutex<RenderStats>>,
-) -> u32 {
+) -> Histogram {
     let mut histogram = Histogram::new();
     let r = renderer.render_frame(
         geo,
@@ -212,7 +1042,7 @@ fn count_number_of_filled_pixel<R: Renderer>(
 
     *mtx_render_stats.lock().unwrap() += r;
 
-    histogram.iter().sum()
+    histogram
 }
 
 /// The color map for encoding the pixel contribution.
@@ -279,3 +1109,762 @@ impl ColorMap for GrayScaleColorMap {
         (value, value, value)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use cad_import::loader::{loader_gltf::LoaderGLTF, Loader, MemoryResource};
+    use nalgebra_glm::Vec3;
+    use pixel_contrib_types::METADATA_BOUNDING_SPHERE;
+    use rasterizer::{simple_rasterizer::SimpleRasterizer, Stats};
+
+    use super::*;
+
+    fn load_two_cubes() -> Scene {
+        let data = include_bytes!("../../test_data/models/2Boxes.glb");
+        let memory_resource = MemoryResource::new(data, "model/gltf-binary".to_owned());
+        let cad_data = LoaderGLTF::new().read(&memory_resource).unwrap();
+
+        Scene::new_from_cad(&cad_data).unwrap()
+    }
+
+    #[test]
+    fn test_compute_contribution_maps() {
+        let scene = load_two_cubes();
+        let camera_configs = [
+            CameraConfig::Perspective {
+                fovy: 60f32.to_radians(),
+            },
+            CameraConfig::Orthographic,
+            CameraConfig::Frustum(CameraFrustum::perspective(40f32.to_radians(), 2f32)),
+        ];
+
+        let mut options = PixelContributionOptions {
+            render_options: RenderOptions {
+                num_threads: 1,
+                frame_size: 64,
+            },
+            num_threads: 2,
+            contrib_map_size: 6,
+            camera_config: camera_configs[0],
+            camera_distances: Vec::new(),
+            parameterization: SphereParameterization::Octahedral,
+            sphere_fitting: SphereFitting::default(),
+            sampling: DirectionSampling::Full,
+            samples_per_texel: 1,
+            checkpoint: None,
+            progress: Arc::new(NoProgress::default()),
+            map_sink: None,
+            cancellation: CancellationToken::new(),
+        };
+
+        let mut render_stats = RenderStats::default();
+        let maps = compute_contribution_maps::<SimpleRasterizer>(
+            &scene,
+            Stats::root(),
+            &options,
+            &camera_configs,
+            &mut render_stats,
+        )
+        .unwrap();
+        assert_eq!(maps.get_maps().len(), camera_configs.len());
+        assert_eq!(
+            maps.metadata().frame_size(),
+            Some(options.render_options.frame_size)
+        );
+
+        // the maps are the same as when computing them one by one
+        for camera_config in camera_configs {
+            options.camera_config = camera_config;
+            let map = compute_contribution_map::<SimpleRasterizer>(
+                &scene,
+                Stats::root(),
+                &options,
+                &mut render_stats,
+            )
+            .unwrap();
+
+            assert!(map.pixel_contrib.iter().any(|v| *v > 0f32));
+            assert!(maps.get_maps().contains(&map));
+        }
+        assert_eq!(render_stats.num_frames, options.contrib_map_size.pow(2));
+        assert_eq!(render_stats.num_frames_saved, 0);
+    }
+
+    #[test]
+    fn test_compute_contribution_maps_at_distances() {
+        let scene = load_two_cubes();
+        let fovy = 60f32.to_radians();
+        let camera_configs = [
+            CameraConfig::Perspective { fovy },
+            CameraConfig::Orthographic,
+        ];
+
+        let mut options = PixelContributionOptions {
+            render_options: RenderOptions {
+                num_threads: 1,
+                frame_size: 64,
+            },
+            num_threads: 2,
+            contrib_map_size: 6,
+            camera_config: camera_configs[0],
+            camera_distances: vec![1.2f32, 3f32],
+            parameterization: SphereParameterization::Octahedral,
+            sphere_fitting: SphereFitting::default(),
+            sampling: DirectionSampling::Full,
+            samples_per_texel: 1,
+            checkpoint: None,
+            progress: Arc::new(NoProgress::default()),
+            map_sink: None,
+            cancellation: CancellationToken::new(),
+        };
+
+        let mut render_stats = RenderStats::default();
+        let maps = compute_contribution_maps::<SimpleRasterizer>(
+            &scene,
+            Stats::root(),
+            &options,
+            &camera_configs,
+            &mut render_stats,
+        )
+        .unwrap();
+
+        // the orthographic camera does not depend on the distance
+        let mut descriptors: Vec<(f32, f32)> = maps
+            .get_maps()
+            .iter()
+            .map(|m| (m.descriptor.camera_angle(), m.descriptor.distance()))
+            .collect();
+        descriptors.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(
+            descriptors,
+            vec![(0f32, 0f32), (fovy, 0f32), (fovy, 1.2f32), (fovy, 3f32)]
+        );
+
+        // the values are normalized by the part of the sphere inside of the frame
+        for map in maps.get_maps() {
+            assert!(map
+                .pixel_contrib
+                .iter()
+                .all(|v| (0f32..=1.05f32).contains(v)));
+            assert!(map.pixel_contrib.iter().any(|v| *v > 0f32));
+        }
+
+        // the fitted map is the same as when computing it without distances
+        let map = compute_contribution_map::<SimpleRasterizer>(
+            &scene,
+            Stats::root(),
+            &options,
+            &mut render_stats,
+        )
+        .unwrap();
+        assert!(maps.get_maps().contains(&map));
+
+        // the estimator uses the map computed at the distance of the camera
+        let sphere = maps.metadata().bounding_sphere().unwrap();
+        let dir = Vec3::new(0.3f32, -0.8f32, 0.5f32).normalize();
+        let camera_pos = sphere.center - dir * sphere.radius * 1.2f32;
+        let model_view = nalgebra_glm::look_at(&camera_pos, &sphere.center, &Vec3::z());
+        let perspective = nalgebra_glm::perspective(1f32, fovy, 0.01f32, 100f32);
+
+        let mut estimator = PixelContribution::new(maps.clone());
+        estimator
+            .update_camera(model_view, perspective, 64f32)
+            .unwrap();
+
+        let mut sphere_estimator = screen_space::ScreenSpaceEstimator::new();
+        sphere_estimator.update_camera(model_view, perspective, 64f32);
+        let (sphere_pixels, _) = sphere_estimator.estimate_screen_space_for_bounding_sphere(sphere);
+
+        let near_map = maps
+            .get_maps()
+            .iter()
+            .find(|m| m.descriptor.distance() == 1.2f32)
+            .unwrap();
+        let expected = sphere_pixels * near_map.get_pixel_contrib_for_camera_dir(dir);
+        let estimate = estimator.estimate_pixel_contribution(&sphere);
+        assert!(
+            (estimate - expected).abs() <= expected * 1e-3,
+            "{} != {}",
+            estimate,
+            expected
+        );
+
+        options.camera_distances = vec![0.5f32];
+        assert!(compute_contribution_maps::<SimpleRasterizer>(
+            &scene,
+            Stats::root(),
+            &options,
+            &camera_configs,
+            &mut render_stats,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_compute_contribution_maps_with_variance() {
+        let scene = load_two_cubes();
+        let camera_configs = [CameraConfig::Perspective {
+            fovy: 60f32.to_radians(),
+        }];
+
+        let mut options = PixelContributionOptions {
+            render_options: RenderOptions {
+                num_threads: 1,
+                frame_size: 64,
+            },
+            num_threads: 2,
+            contrib_map_size: 8,
+            camera_config: camera_configs[0],
+            camera_distances: Vec::new(),
+            parameterization: SphereParameterization::Octahedral,
+            sphere_fitting: SphereFitting::default(),
+            sampling: DirectionSampling::Full,
+            samples_per_texel: 1,
+            checkpoint: None,
+            progress: Arc::new(NoProgress::default()),
+            map_sink: None,
+            cancellation: CancellationToken::new(),
+        };
+
+        // a single sample per texel has no variance
+        let mut render_stats = RenderStats::default();
+        let (maps, variance_maps) = compute_contribution_maps_with_variance::<SimpleRasterizer>(
+            &scene,
+            Stats::root(),
+            &options,
+            &camera_configs,
+            &mut render_stats,
+        )
+        .unwrap();
+        assert!(variance_maps.maps[0]
+            .pixel_contrib
+            .iter()
+            .all(|v| *v == 0f32));
+
+        options.samples_per_texel = 4;
+        let (maps2, variance_maps) = compute_contribution_maps_with_variance::<SimpleRasterizer>(
+            &scene,
+            Stats::root(),
+            &options,
+            &camera_configs,
+            &mut render_stats,
+        )
+        .unwrap();
+        assert_eq!(render_stats.num_frames, 4 * 64);
+        assert_eq!(variance_maps.maps[0].descriptor, maps2.maps[0].descriptor);
+
+        // the jittered samples vary, but their average stays close to the texel centers
+        let variances = &variance_maps.maps[0].pixel_contrib;
+        assert!(variances.iter().all(|v| *v >= 0f32));
+        assert!(variances.iter().any(|v| *v > 0f32));
+
+        let mean_error = maps.maps[0]
+            .pixel_contrib
+            .iter()
+            .zip(maps2.maps[0].pixel_contrib.iter())
+            .map(|(a, b)| (a - b).abs())
+            .sum::<f32>()
+            / 64f32;
+        assert!(mean_error < 0.1, "mean error {}", mean_error);
+    }
+
+    /// Records the maps passed to the sink.
+    #[derive(Default)]
+    struct RecordingSink {
+        maps: Mutex<Vec<(usize, PixelContributionMap, PixelContributionMap)>>,
+    }
+
+    impl MapSink for RecordingSink {
+        fn map_finished(
+            &self,
+            channel: usize,
+            map: &PixelContributionMap,
+            variance_map: &PixelContributionMap,
+        ) -> Result<()> {
+            self.maps
+                .lock()
+                .unwrap()
+                .push((channel, map.clone(), variance_map.clone()));
+
+            Ok(())
+        }
+    }
+
+    /// Fails for every map passed to the sink.
+    struct FailingSink {}
+
+    impl MapSink for FailingSink {
+        fn map_finished(
+            &self,
+            _channel: usize,
+            _map: &PixelContributionMap,
+            _variance_map: &PixelContributionMap,
+        ) -> Result<()> {
+            Err(Error::IO("disk full".to_string()))
+        }
+    }
+
+    #[test]
+    fn test_map_sink() {
+        let scene = load_two_cubes();
+        let camera_configs = [
+            CameraConfig::Perspective {
+                fovy: 60f32.to_radians(),
+            },
+            CameraConfig::Orthographic,
+        ];
+
+        let mut options = PixelContributionOptions {
+            render_options: RenderOptions {
+                num_threads: 1,
+                frame_size: 64,
+            },
+            num_threads: 2,
+            contrib_map_size: 8,
+            camera_config: camera_configs[0],
+            camera_distances: vec![2f32],
+            parameterization: SphereParameterization::Octahedral,
+            sphere_fitting: SphereFitting::default(),
+            sampling: DirectionSampling::Full,
+            samples_per_texel: 2,
+            checkpoint: None,
+            progress: Arc::new(NoProgress::default()),
+            map_sink: None,
+            cancellation: CancellationToken::new(),
+        };
+
+        // each map is passed to the sink exactly once, also if the texels are interpolated
+        for sampling in [
+            DirectionSampling::Full,
+            DirectionSampling::Adaptive { tolerance: 0.05 },
+        ] {
+            let sink = Arc::new(RecordingSink::default());
+            options.sampling = sampling;
+            options.map_sink = Some(sink.clone());
+
+            let mut render_stats = RenderStats::default();
+            let (maps, variance_maps) =
+                compute_contribution_maps_with_variance::<SimpleRasterizer>(
+                    &scene,
+                    Stats::root(),
+                    &options,
+                    &camera_configs,
+                    &mut render_stats,
+                )
+                .unwrap();
+
+            let finished = sink.maps.lock().unwrap();
+            assert_eq!(finished.len(), 3);
+            for (channel, map, variance_map) in finished.iter() {
+                assert_eq!(*channel, 0);
+                assert!(maps.get_maps().contains(map));
+                assert!(variance_maps.get_maps().contains(variance_map));
+                assert_eq!(map.descriptor, variance_map.descriptor);
+            }
+        }
+
+        // an error of the sink aborts the computation
+        options.sampling = DirectionSampling::Full;
+        options.map_sink = Some(Arc::new(FailingSink {}));
+        let mut render_stats = RenderStats::default();
+        let result = compute_contribution_maps::<SimpleRasterizer>(
+            &scene,
+            Stats::root(),
+            &options,
+            &camera_configs,
+            &mut render_stats,
+        );
+        assert!(matches!(result, Err(Error::IO(_))));
+    }
+
+    #[test]
+    fn test_compute_contribution_maps_adaptive() {
+        let scene = load_two_cubes();
+        let camera_config = CameraConfig::Perspective {
+            fovy: 60f32.to_radians(),
+        };
+
+        let mut options = PixelContributionOptions {
+            render_options: RenderOptions {
+                num_threads: 1,
+                frame_size: 64,
+            },
+            num_threads: 2,
+            contrib_map_size: 32,
+            camera_config,
+            camera_distances: Vec::new(),
+            parameterization: SphereParameterization::Octahedral,
+            sphere_fitting: SphereFitting::default(),
+            sampling: DirectionSampling::Full,
+            samples_per_texel: 1,
+            checkpoint: None,
+            progress: Arc::new(NoProgress::default()),
+            map_sink: None,
+            cancellation: CancellationToken::new(),
+        };
+
+        let mut render_stats = RenderStats::default();
+        let full = compute_contribution_map::<SimpleRasterizer>(
+            &scene,
+            Stats::root(),
+            &options,
+            &mut render_stats,
+        )
+        .unwrap();
+
+        options.sampling = DirectionSampling::Adaptive { tolerance: 0.05 };
+        let adaptive = compute_contribution_map::<SimpleRasterizer>(
+            &scene,
+            Stats::root(),
+            &options,
+            &mut render_stats,
+        )
+        .unwrap();
+
+        let num_values = full.pixel_contrib.len();
+        assert!(render_stats.num_frames_saved > 0);
+        assert_eq!(
+            render_stats.num_frames + render_stats.num_frames_saved,
+            num_values
+        );
+
+        let mean_error = full
+            .pixel_contrib
+            .iter()
+            .zip(adaptive.pixel_contrib.iter())
+            .map(|(a, b)| (a - b).abs())
+            .sum::<f32>()
+            / num_values as f32;
+        assert!(mean_error < 0.05, "mean error {}", mean_error);
+    }
+
+    #[test]
+    fn test_compute_object_contribution_maps() {
+        let scene = load_two_cubes();
+        let camera_configs = [
+            CameraConfig::Perspective {
+                fovy: 60f32.to_radians(),
+            },
+            CameraConfig::Orthographic,
+        ];
+
+        let options = PixelContributionOptions {
+            render_options: RenderOptions {
+                num_threads: 1,
+                frame_size: 64,
+            },
+            num_threads: 2,
+            contrib_map_size: 8,
+            camera_config: camera_configs[0],
+            camera_distances: Vec::new(),
+            parameterization: SphereParameterization::Octahedral,
+            sphere_fitting: SphereFitting::default(),
+            sampling: DirectionSampling::Full,
+            samples_per_texel: 1,
+            checkpoint: None,
+            progress: Arc::new(NoProgress::default()),
+            map_sink: None,
+            cancellation: CancellationToken::new(),
+        };
+
+        let mut render_stats = RenderStats::default();
+        let maps = compute_contribution_maps::<SimpleRasterizer>(
+            &scene,
+            Stats::root(),
+            &options,
+            &camera_configs,
+            &mut render_stats,
+        )
+        .unwrap();
+
+        let object_maps = compute_object_contribution_maps::<SimpleRasterizer>(
+            &scene,
+            Stats::root(),
+            &options,
+            &camera_configs,
+            &mut render_stats,
+        )
+        .unwrap();
+        assert_eq!(object_maps.len(), scene.get_instances().len());
+
+        for (index, object_maps) in object_maps.iter().enumerate() {
+            assert_eq!(object_maps.metadata().instance_index(), Some(index));
+            assert_eq!(
+                object_maps.metadata().get(METADATA_BOUNDING_SPHERE),
+                maps.metadata().get(METADATA_BOUNDING_SPHERE)
+            );
+            assert!(object_maps.maps[0].pixel_contrib.iter().any(|v| *v > 0f32));
+        }
+
+        // the maps of all objects sum up to the maps of the whole scene
+        for (map_index, map) in maps.get_maps().iter().enumerate() {
+            for (i, v) in map.pixel_contrib.iter().enumerate() {
+                let sum: f32 = object_maps
+                    .iter()
+                    .map(|m| m.maps[map_index].pixel_contrib[i])
+                    .sum();
+                assert!((sum - v).abs() < 1e-5, "{} != {}", sum, v);
+            }
+        }
+
+        // the maps of the whole scene and of the objects are computed from the same renders
+        let num_frames = render_stats.num_frames;
+        let (maps2, _, object_maps2) = compute_contribution_maps_with_objects::<SimpleRasterizer>(
+            &scene,
+            Stats::root(),
+            &options,
+            &camera_configs,
+            &mut render_stats,
+        )
+        .unwrap();
+        assert_eq!(render_stats.num_frames, num_frames);
+        assert!(maps2 == maps);
+        assert_eq!(object_maps2.len(), object_maps.len());
+        for (object_maps, object_maps2) in object_maps.iter().zip(object_maps2.iter()) {
+            assert!(object_maps == object_maps2);
+        }
+    }
+
+    #[test]
+    fn test_resume_contribution_maps() {
+        let scene = load_two_cubes();
+        let camera_configs = [CameraConfig::Perspective {
+            fovy: 60f32.to_radians(),
+        }];
+
+        let path = std::env::temp_dir().join(format!(
+            "pixel_contrib_checkpoint_{}.bin",
+            std::process::id()
+        ));
+
+        let mut options = PixelContributionOptions {
+            render_options: RenderOptions {
+                num_threads: 1,
+                frame_size: 64,
+            },
+            num_threads: 2,
+            contrib_map_size: 6,
+            camera_config: camera_configs[0],
+            camera_distances: Vec::new(),
+            parameterization: SphereParameterization::Octahedral,
+            sphere_fitting: SphereFitting::default(),
+            sampling: DirectionSampling::Full,
+            samples_per_texel: 1,
+            checkpoint: Some(CheckpointOptions {
+                path: path.clone(),
+                interval: std::time::Duration::from_secs(3600),
+            }),
+            progress: Arc::new(NoProgress::default()),
+            map_sink: None,
+            cancellation: CancellationToken::new(),
+        };
+
+        let mut render_stats = RenderStats::default();
+        let maps = compute_contribution_maps::<SimpleRasterizer>(
+            &scene,
+            Stats::root(),
+            &options,
+            &camera_configs,
+            &mut render_stats,
+        )
+        .unwrap();
+
+        // simulate an interrupted computation, which has computed every other texel
+        let num_texels = options.contrib_map_size.pow(2);
+        let hash = checkpoint::compute_hash(
+            &scene,
+            &options,
+            &map_configs(&camera_configs, &options.camera_distances).unwrap(),
+            ContributionChannels::Assembly,
+        );
+        let mut partial = checkpoint::Checkpoint::new(hash, num_texels, 1);
+        for (i, v) in maps.maps[0].pixel_contrib.iter().enumerate().step_by(2) {
+            partial.set(i, &[(*v, 0f32)]);
+        }
+        partial.write_file(&path).unwrap();
+
+        let resumed = resume_contribution_maps::<SimpleRasterizer>(
+            &scene,
+            Stats::root(),
+            &options,
+            &camera_configs,
+            &mut render_stats,
+        )
+        .unwrap();
+        assert!(resumed == maps);
+        assert_eq!(render_stats.num_frames, num_texels / 2);
+
+        // the finished computation is stored in the checkpoint, s.t. nothing is rendered again
+        resume_contribution_maps::<SimpleRasterizer>(
+            &scene,
+            Stats::root(),
+            &options,
+            &camera_configs,
+            &mut render_stats,
+        )
+        .unwrap();
+        assert_eq!(render_stats.num_frames, 0);
+
+        // a checkpoint of different options is refused
+        options.samples_per_texel = 2;
+        let result = resume_contribution_maps::<SimpleRasterizer>(
+            &scene,
+            Stats::root(),
+            &options,
+            &camera_configs,
+            &mut render_stats,
+        );
+        assert!(matches!(result, Err(Error::CheckpointMismatch)));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    /// Cancels the computation after the given number of texels.
+    struct CancellingProgress {
+        cancellation: CancellationToken,
+        num_texels: usize,
+    }
+
+    impl ProgressSink for CancellingProgress {
+        fn report(&self, report: &ProgressReport) {
+            if report.completed >= self.num_texels {
+                self.cancellation.cancel();
+            }
+        }
+    }
+
+    #[test]
+    fn test_cancel_and_resume_contribution_maps() {
+        let scene = load_two_cubes();
+        let camera_configs = [CameraConfig::Perspective {
+            fovy: 60f32.to_radians(),
+        }];
+
+        let path = std::env::temp_dir().join(format!(
+            "pixel_contrib_cancel_checkpoint_{}.bin",
+            std::process::id()
+        ));
+
+        let cancellation = CancellationToken::new();
+        let mut options = PixelContributionOptions {
+            render_options: RenderOptions {
+                num_threads: 1,
+                frame_size: 64,
+            },
+            num_threads: 1,
+            contrib_map_size: 8,
+            camera_config: camera_configs[0],
+            camera_distances: Vec::new(),
+            parameterization: SphereParameterization::Octahedral,
+            sphere_fitting: SphereFitting::default(),
+            sampling: DirectionSampling::Full,
+            samples_per_texel: 1,
+            checkpoint: Some(CheckpointOptions {
+                path: path.clone(),
+                interval: std::time::Duration::from_secs(3600),
+            }),
+            progress: Arc::new(CancellingProgress {
+                cancellation: cancellation.clone(),
+                num_texels: 20,
+            }),
+            map_sink: None,
+            cancellation,
+        };
+
+        let mut render_stats = RenderStats::default();
+        let result = compute_contribution_maps::<SimpleRasterizer>(
+            &scene,
+            Stats::root(),
+            &options,
+            &camera_configs,
+            &mut render_stats,
+        );
+        assert!(matches!(result, Err(Error::Cancelled)));
+        assert!(render_stats.num_frames < 64);
+
+        // the cancelled computation is resumed from its checkpoint
+        options.progress = Arc::new(NoProgress::default());
+        options.cancellation = CancellationToken::new();
+        let resumed = resume_contribution_maps::<SimpleRasterizer>(
+            &scene,
+            Stats::root(),
+            &options,
+            &camera_configs,
+            &mut render_stats,
+        )
+        .unwrap();
+        assert!(render_stats.num_frames < 64);
+
+        options.checkpoint = None;
+        let maps = compute_contribution_maps::<SimpleRasterizer>(
+            &scene,
+            Stats::root(),
+            &options,
+            &camera_configs,
+            &mut render_stats,
+        )
+        .unwrap();
+        assert!(resumed == maps);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_sphere_fitting() {
+        let scene = load_two_cubes();
+        let camera_configs = [CameraConfig::Perspective {
+            fovy: 60f32.to_radians(),
+        }];
+
+        let mut options = PixelContributionOptions {
+            render_options: RenderOptions {
+                num_threads: 1,
+                frame_size: 64,
+            },
+            num_threads: 2,
+            contrib_map_size: 4,
+            camera_config: camera_configs[0],
+            camera_distances: Vec::new(),
+            parameterization: SphereParameterization::Octahedral,
+            sphere_fitting: SphereFitting::default(),
+            sampling: DirectionSampling::Full,
+            samples_per_texel: 1,
+            checkpoint: None,
+            progress: Arc::new(NoProgress::default()),
+            map_sink: None,
+            cancellation: CancellationToken::new(),
+        };
+
+        let mut render_stats = RenderStats::default();
+        let mut compute_maps = |options: &PixelContributionOptions| {
+            compute_contribution_maps::<SimpleRasterizer>(
+                &scene,
+                Stats::root(),
+                options,
+                &camera_configs,
+                &mut render_stats,
+            )
+            .unwrap()
+        };
+
+        let aabb_center = compute_maps(&options);
+
+        // the minimal sphere is at most as large as the default sphere and thus the object
+        // covers at least as many pixels
+        options.sphere_fitting = SphereFitting::Algorithm(BoundingSphereAlgorithm::Welzl);
+        let minimal = compute_maps(&options);
+        let aabb_sphere = aabb_center.metadata().bounding_sphere().unwrap();
+        let minimal_sphere = minimal.metadata().bounding_sphere().unwrap();
+        assert!(minimal_sphere.radius <= aabb_sphere.radius * 1.0001);
+
+        let mean = |maps: &PixelContributionMaps| maps.maps[0].statistics().mean;
+        assert!(mean(&minimal) >= mean(&aabb_center) * 0.99);
+
+        // a given sphere is used as is and a larger sphere reduces the contribution
+        let sphere = BoundingSphere::from((aabb_sphere.center, aabb_sphere.radius * 2f32));
+        options.sphere_fitting = SphereFitting::Sphere(sphere);
+        let given = compute_maps(&options);
+        assert_eq!(given.metadata().bounding_sphere(), Some(sphere));
+        assert!(mean(&given) < mean(&aabb_center));
+    }
+}
@@ -5,7 +5,7 @@ use std::{
 
 use math::BoundingSphere;
 use nalgebra_glm::{Mat4, Vec3};
-use pixel_contrib_types::PixelContributionMaps;
+use pixel_contrib_types::{AngleInterpolator, CameraFrustum, PixelContributionMaps, SamplingMode};
 
 use crate::{
     screen_space::{ScreenSpaceEstimator, ScreenSpaceResult},
@@ -19,30 +19,50 @@ pub struct PixelContribution {
     /// The precomputed pixel contribution maps.
     maps: PixelContributionMaps,
 
+    /// The maps grouped by the shape of their frusta. Empty if all maps share the same shape.
+    frustum_groups: Vec<PixelContributionMaps>,
+
+    /// The index of the frustum group matching the frustum of the current camera.
+    active_group: usize,
+
+    /// The frustum of the current camera, if it could be determined.
+    frustum: Option<CameraFrustum>,
+
     /// The position of the camera.
     cam_pos: Vec3,
 
     /// The screen space estimator used to estimate the screen space of the bounding volume.
     sphere_estimator: ScreenSpaceEstimator,
+
+    /// The filter used for the lookup in the pixel contribution maps.
+    sampling_mode: SamplingMode,
+
+    /// Whether the level of detail of the lookup is chosen based on the projected sphere size.
+    use_mip_chain: bool,
 }
 
 impl PixelContribution {
     /// Creates a new pixel contribution estimator with the given maps.
     ///
     /// # Arguments
-    /// `maps` - The maps to use for encoding the pixel contribution.
+    /// * `maps` - The maps to use for encoding the pixel contribution.
     pub fn new(maps: PixelContributionMaps) -> Self {
         Self {
+            frustum_groups: Self::split_frustum_groups(&maps),
+            active_group: 0,
+            frustum: None,
             maps,
             cam_pos: Vec3::zeros(),
             sphere_estimator: Default::default(),
+            sampling_mode: SamplingMode::default(),
+            use_mip_chain: false,
         }
     }
 
     /// Creates a new pixel contribution estimator from the given reader.
     ///
     /// # Arguments
-    /// `reader` - The reader to read the pixel contribution maps from.
+    /// * `reader` - The reader to read the pixel contribution maps from.
     pub fn from_reader<R: Read>(reader: &mut R) -> Result<Self> {
         let maps = PixelContributionMaps::from_reader(reader)?;
 
@@ -61,9 +81,9 @@ impl PixelContribution {
     /// Updates the internal camera configuration.
     ///
     /// # Arguments
-    /// `model_view` - The model-view matrix of the camera.
-    /// `perspective` - The perspective matrix of the camera.
-    /// `height` - The height of the frame buffer in pixels.
+    /// * `model_view` - The model-view matrix of the camera.
+    /// * `perspective` - The perspective matrix of the camera.
+    /// * `height` - The height of the frame buffer in pixels.
     pub fn update_camera(
         &mut self,
         model_view: Mat4,
@@ -77,13 +97,34 @@ impl PixelContribution {
         self.cam_pos = math::extract_camera_position(&model_view)
             .ok_or_else(|| Error::InvalidArgument("Model-View matrix is invalid".to_owned()))?;
 
+        // select the maps computed for the frustum most similar to the one of the camera
+        self.frustum = CameraFrustum::from_projection_matrix(&perspective);
+        if let Some(frustum) = self.frustum {
+            self.active_group = self
+                .frustum_groups
+                .iter()
+                .enumerate()
+                .map(|(i, g)| (i, g.maps[0].descriptor.frustum().shape_distance(&frustum)))
+                .min_by(|(_, d1), (_, d2)| d1.total_cmp(d2))
+                .map_or(0, |(i, _)| i);
+        }
+
         Ok(())
     }
 
     /// Estimates the pixel contribution of the given bounding sphere.
     ///
+    /// The maps are looked up for the frustum of the current camera and the angle under which
+    /// the sphere is seen, see [`PixelContributionMaps::sample_pixel_contrib_for_camera`]. That
+    /// is, the values of maps computed at the same camera distance are interpolated w.r.t the
+    /// angle of the camera frustum, and the values of the different distances w.r.t the angle
+    /// under which the sphere is seen. Maps computed at the distance fitting their frustum form
+    /// groups of their own, s.t. these are interpolated w.r.t the angle of the sphere alone. If
+    /// the frustum of the camera cannot be determined, the quadratic frustum fitting the sphere
+    /// is assumed.
+    ///
     /// # Arguments
-    /// `sphere` - The bounding sphere to estimate the pixel contribution for.
+    /// * `sphere` - The bounding sphere to estimate the pixel contribution for.
     pub fn estimate_pixel_contribution(&self, sphere: &BoundingSphere) -> f32 {
         // First make a prediction of the pixels that the bounding sphere will cover.
         let (predicted_sphere_pixels, classification) = self
@@ -100,19 +141,102 @@ impl PixelContribution {
         // the pixel contribution maps.
         let cam_dir = nalgebra_glm::normalize(&(sphere.center - self.cam_pos));
         let sphere_angle = Self::estimate_camera_angle(&self.cam_pos, sphere);
-        let pixel_contrib_value = self
-            .maps
-            .get_pixel_contrib_for_camera_dir(cam_dir, sphere_angle);
+        let frustum = self
+            .frustum
+            .unwrap_or_else(|| CameraFrustum::square(sphere_angle));
+        let maps = self.active_maps();
+        let pixel_contrib_value = if self.use_mip_chain {
+            let lod = self.estimate_lod(predicted_sphere_pixels);
+            maps.sample_pixel_contrib_for_camera_lod(
+                cam_dir,
+                &frustum,
+                sphere_angle,
+                lod,
+                self.sampling_mode,
+            )
+        } else {
+            maps.sample_pixel_contrib_for_camera(
+                cam_dir,
+                &frustum,
+                sphere_angle,
+                self.sampling_mode,
+            )
+        };
 
         predicted_sphere_pixels * pixel_contrib_value
     }
 
+    /// Sets the filter used for the lookup in the pixel contribution maps.
+    ///
+    /// # Arguments
+    /// * `mode` - The sampling mode to use.
+    pub fn set_sampling_mode(&mut self, mode: SamplingMode) {
+        self.sampling_mode = mode;
+    }
+
+    /// Enables or disables the selection of the mip level based on the projected size of the
+    /// bounding sphere. Has no effect on maps without mip chains, see
+    /// [`PixelContributionMaps::generate_mip_chains`].
+    ///
+    /// # Arguments
+    /// * `enabled` - Whether the mip level should be selected based on the projected size.
+    pub fn set_use_mip_chain(&mut self, enabled: bool) {
+        self.use_mip_chain = enabled;
+    }
+
+    /// Sets the interpolator used for camera angles between the angles of the maps.
+    ///
+    /// # Arguments
+    /// * `interpolator` - The angle interpolator to use.
+    pub fn set_angle_interpolator<I: AngleInterpolator + 'static>(&mut self, interpolator: I) {
+        self.maps.set_angle_interpolator(interpolator);
+        self.frustum_groups = Self::split_frustum_groups(&self.maps);
+    }
+
     /// Returns the pixel contribution maps used by this estimator.
     #[inline]
     pub fn get_maps(&self) -> &PixelContributionMaps {
         &self.maps
     }
 
+    /// Returns the pixel contribution maps matching the frustum of the current camera.
+    #[inline]
+    pub fn active_maps(&self) -> &PixelContributionMaps {
+        self.frustum_groups
+            .get(self.active_group)
+            .unwrap_or(&self.maps)
+    }
+
+    /// Splits the given maps into groups of maps computed for frusta of the same shape. Returns
+    /// no groups if all maps share the same shape.
+    ///
+    /// # Arguments
+    /// * `maps` - The maps to split.
+    fn split_frustum_groups(maps: &PixelContributionMaps) -> Vec<PixelContributionMaps> {
+        let groups = maps.frustum_groups();
+        if groups.len() > 1 {
+            groups
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Estimates the level of detail for the lookup in the pixel contribution maps based on the
+    /// number of pixels covered by the bounding sphere.
+    ///
+    /// # Arguments
+    /// * `sphere_pixels` - The predicted number of pixels covered by the bounding sphere.
+    fn estimate_lod(&self, sphere_pixels: f32) -> f32 {
+        // Rotating the view by the inverse of the projected sphere radius in pixels moves the
+        // surface by about one pixel. Thus, views within this angle are indistinguishable.
+        let footprint = std::f32::consts::PI / sphere_pixels.max(1.0);
+
+        self.active_maps()
+            .get_maps()
+            .first()
+            .map_or(0.0, |m| m.lod_from_footprint(footprint))
+    }
+
     /// Estimates the angle of the camera based on the bounding sphere. The further away the sphere
     /// is, the smaller the angle will be. That is, the angle is the angle of the camera frustum
     /// that is covered by the bounding sphere.
@@ -123,6 +247,104 @@ impl PixelContribution {
     #[inline]
     fn estimate_camera_angle(cam_pos: &Vec3, sphere: &BoundingSphere) -> f32 {
         let d = nalgebra_glm::distance(cam_pos, &sphere.center);
-        (sphere.radius / d).asin() * 2f32
+        (sphere.radius / d).min(1f32).asin() * 2f32
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use pixel_contrib_types::{PixelContribColorMapDescriptor, PixelContributionMap};
+
+    use super::*;
+
+    /// Creates maps with the given camera angles in degrees, distances and constant values.
+    fn create_maps(maps: &[(f32, f32, f32)]) -> PixelContributionMaps {
+        PixelContributionMaps::from_maps(
+            maps.iter()
+                .map(|(angle, distance, value)| {
+                    let descriptor = PixelContribColorMapDescriptor::new(8, angle.to_radians())
+                        .with_distance(*distance);
+                    let mut map = PixelContributionMap::new(descriptor);
+                    map.pixel_contrib.iter_mut().for_each(|p| *p = *value);
+
+                    map
+                })
+                .collect(),
+        )
+    }
+
+    /// Returns the value looked up by the estimator for the unit sphere seen from the given
+    /// distance with the given field of view in degrees, i.e., the estimated pixel contribution
+    /// divided by the predicted number of pixels of the sphere.
+    fn estimate(estimator: &mut PixelContribution, distance: f32, fovy: f32) -> f32 {
+        let sphere = BoundingSphere {
+            center: Vec3::zeros(),
+            radius: 1.0,
+        };
+
+        let camera_pos = Vec3::new(0.3, -0.8, 0.5).normalize() * distance;
+        let model_view = nalgebra_glm::look_at(&camera_pos, &sphere.center, &Vec3::z());
+        let perspective = nalgebra_glm::perspective(1.0, fovy.to_radians(), 0.01, 100.0);
+        estimator
+            .update_camera(model_view, perspective, 256.0)
+            .unwrap();
+
+        let mut sphere_estimator = ScreenSpaceEstimator::new();
+        sphere_estimator.update_camera(model_view, perspective, 256.0);
+        let (sphere_pixels, classification) =
+            sphere_estimator.estimate_screen_space_for_bounding_sphere(sphere);
+        assert_eq!(classification, ScreenSpaceResult::PartiallyVisible);
+
+        estimator.estimate_pixel_contribution(&sphere) / sphere_pixels
+    }
+
+    #[test]
+    fn test_estimate_fitted_maps() {
+        let maps = create_maps(&[(40.0, 0.0, 0.1), (60.0, 0.0, 0.2), (80.0, 0.0, 0.3)]);
+        let mut estimator = PixelContribution::new(maps.clone());
+
+        // the fitted maps are interpolated w.r.t the sphere angle regardless of the camera angle
+        let dir = Vec3::new(0.3, -0.8, 0.5).normalize();
+        for distance in [1.2f32, 1.8, 2.0, 2.5, 10.0] {
+            let sphere_angle = (1.0 / distance).asin() * 2.0;
+            let expected = maps.get_pixel_contrib_for_camera_dir(-dir, sphere_angle);
+
+            for fovy in [45.0, 60.0, 90.0] {
+                let value = estimate(&mut estimator, distance, fovy);
+                assert!((value - expected).abs() < 1e-4, "{} != {}", value, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_estimate_mixed_distances() {
+        // the sphere angles are 38.9° for the distance 3 and 83.6° for the distance 1.5
+        let maps = create_maps(&[
+            (40.0, 0.0, 0.1),
+            (60.0, 0.0, 0.2),
+            (80.0, 0.0, 0.3),
+            (40.0, 3.0, 0.4),
+            (60.0, 3.0, 0.45),
+            (80.0, 3.0, 0.5),
+            (40.0, 1.5, 0.5),
+            (60.0, 1.5, 0.6),
+            (80.0, 1.5, 0.7),
+        ]);
+        let mut estimator = PixelContribution::new(maps);
+
+        // the camera fitting the sphere uses the fitted map
+        assert!((estimate(&mut estimator, 2.0, 60.0) - 0.2).abs() < 1e-4);
+
+        // beyond the smallest sphere angle, the maps at the largest distance are used
+        assert!((estimate(&mut estimator, 10.0, 60.0) - 0.45).abs() < 1e-4);
+        assert!((estimate(&mut estimator, 10.0, 80.0) - 0.5).abs() < 1e-4);
+
+        // beyond the largest sphere angle, the maps at the smallest distance are used
+        assert!((estimate(&mut estimator, 1.1, 60.0) - 0.6).abs() < 1e-4);
+        assert!((estimate(&mut estimator, 1.1, 40.0) - 0.5).abs() < 1e-4);
+
+        // between the fitted map of 80° and the distance 1.5, both are interpolated
+        let value = estimate(&mut estimator, 1.52, 60.0);
+        assert!(value > 0.3 && value < 0.6, "{}", value);
     }
 }
@@ -4,7 +4,7 @@ use std::{
 };
 
 use image::RgbImage;
-use pixel_contrib_types::{PixelContributionMap, PixelContributionMaps};
+use pixel_contrib_types::{MapEncoding, PixelContributionMap, PixelContributionMaps};
 
 use crate::{ColorMap, Result};
 
@@ -16,6 +16,18 @@ pub trait PixelContributionMapFile: Sized {
     /// * `path` - The path to which the pixel contribution should be written.
     fn write_file<P: AsRef<Path>>(&self, path: P) -> Result<()>;
 
+    /// Writes the pixel contribution map to the given path as binary file using the given
+    /// encoding for the values.
+    ///
+    /// # Arguments
+    /// * `path` - The path to which the pixel contribution should be written.
+    /// * `encoding` - The encoding and compression used for storing the values.
+    fn write_file_with_encoding<P: AsRef<Path>>(
+        &self,
+        path: P,
+        encoding: MapEncoding,
+    ) -> Result<()>;
+
     /// Reads the pixel contribution map from the given path.
     ///
     /// # Arguments
@@ -25,9 +37,17 @@ pub trait PixelContributionMapFile: Sized {
 
 impl PixelContributionMapFile for PixelContributionMaps {
     fn write_file<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        self.write_file_with_encoding(path, MapEncoding::default())
+    }
+
+    fn write_file_with_encoding<P: AsRef<Path>>(
+        &self,
+        path: P,
+        encoding: MapEncoding,
+    ) -> Result<()> {
         let file = std::fs::File::create(path)?;
 
-        self.write_writer(&mut BufWriter::new(file))?;
+        self.write_writer_with_encoding(&mut BufWriter::new(file), encoding)?;
 
         Ok(())
     }
@@ -1,35 +1,301 @@
-/// A progress bar for the console.
-pub struct Progress {
-    cur: usize,
-    total: usize,
+use std::{
+    io::Write,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+/// The number of reports sent to the progress sink over the whole computation.
+const NUM_REPORTS: usize = 1000;
+
+/// The progress of a running computation.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ProgressReport {
+    /// The number of completed texels.
+    pub completed: usize,
+
+    /// The total number of texels.
+    pub total: usize,
+
+    /// The time passed since the start of the computation.
+    pub elapsed: Duration,
 }
 
-impl Progress {
-    /// Creates a new progress bar with the given total number of steps.
-    pub fn new(total: usize) -> Self {
-        Self { cur: 0, total }
+impl ProgressReport {
+    /// Returns the completed fraction of the computation in the range [0, 1].
+    pub fn fraction(&self) -> f32 {
+        if self.total == 0 {
+            1f32
+        } else {
+            self.completed as f32 / self.total as f32
+        }
     }
 
-    /// Updates the progress of the current task and prints it to the console.
-    pub fn update(&mut self) {
-        self.cur += 1;
-        self.print();
+    /// Returns the number of completed texels per second.
+    pub fn throughput(&self) -> f64 {
+        let secs = self.elapsed.as_secs_f64();
+        if secs > 0f64 {
+            self.completed as f64 / secs
+        } else {
+            0f64
+        }
     }
 
-    /// Prints the progress of the current task to the console.
-    pub fn print(&self) {
+    /// Returns the estimated remaining time of the computation based on the throughput so far.
+    /// Returns None, if no texel has been completed yet.
+    pub fn eta(&self) -> Option<Duration> {
+        if self.completed == 0 {
+            return None;
+        }
+
+        let remaining = self.total.saturating_sub(self.completed) as f64;
+        Some(self.elapsed.mul_f64(remaining / self.completed as f64))
+    }
+}
+
+/// Receives the progress of a running computation. The methods are called from the worker
+/// threads of the computation and must therefore be cheap.
+pub trait ProgressSink: Send + Sync {
+    /// Called whenever a noticeable amount of texels has been completed.
+    ///
+    /// # Arguments
+    /// * `report` - The current progress.
+    fn report(&self, report: &ProgressReport);
+
+    /// Called once after the computation has finished or has been cancelled.
+    ///
+    /// # Arguments
+    /// * `report` - The final progress.
+    fn finish(&self, _report: &ProgressReport) {}
+}
+
+/// Prints the progress as a bar with the throughput and the remaining time to stdout.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TerminalProgress {}
+
+impl TerminalProgress {
+    /// Creates a new terminal progress bar.
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl ProgressSink for TerminalProgress {
+    fn report(&self, report: &ProgressReport) {
         let bar_length = 50;
-        let progress = self.cur as f32 / self.total as f32;
-        let num_bars = (progress * bar_length as f32) as usize;
-        let num_spaces = bar_length - num_bars;
+        let num_bars = ((report.fraction() * bar_length as f32) as usize).min(bar_length);
+
+        let eta = match report.eta() {
+            Some(eta) => {
+                let secs = eta.as_secs();
+                format!(
+                    "{:02}:{:02}:{:02}",
+                    secs / 3600,
+                    (secs / 60) % 60,
+                    secs % 60
+                )
+            }
+            None => "--:--:--".to_string(),
+        };
+
+        // the line is written at once, s.t. reports of different threads do not interleave
+        let line = format!(
+            "\r[{}{}] {:.2}% {:.1} texels/s ETA {}",
+            "=".repeat(num_bars),
+            " ".repeat(bar_length - num_bars),
+            report.fraction() * 100f32,
+            report.throughput(),
+            eta
+        );
+
+        let mut stdout = std::io::stdout().lock();
+        stdout.write_all(line.as_bytes()).ok();
+        stdout.flush().ok();
+    }
+
+    fn finish(&self, report: &ProgressReport) {
+        self.report(report);
+        println!();
+    }
+}
+
+/// Ignores the progress.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoProgress {}
+
+impl ProgressSink for NoProgress {
+    fn report(&self, _report: &ProgressReport) {}
+}
+
+/// A handle for cooperatively cancelling a running computation. All clones share the same
+/// state, s.t. a caller can keep a clone and cancel the computation from another thread.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+    /// Whether the cancellation has been requested.
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    /// Creates a new token, which has not been cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requests the cancellation of the computations using this token.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns true if the cancellation has been requested.
+    #[inline]
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
 
-        print!("\r[");
-        for _ in 0..num_bars {
-            print!("=");
+/// Counts the completed texels without locking and forwards the progress to a sink.
+pub(crate) struct ProgressTracker<'a> {
+    /// The sink receiving the progress.
+    sink: &'a dyn ProgressSink,
+
+    /// The number of completed texels.
+    completed: AtomicUsize,
+
+    /// The total number of texels.
+    total: usize,
+
+    /// The start time of the computation.
+    start: Instant,
+
+    /// The number of completed texels between two reports.
+    step: usize,
+}
+
+impl<'a> ProgressTracker<'a> {
+    /// Creates a new tracker for the given total number of texels.
+    ///
+    /// # Arguments
+    /// * `sink` - The sink receiving the progress.
+    /// * `total` - The total number of texels.
+    pub fn new(sink: &'a dyn ProgressSink, total: usize) -> Self {
+        Self {
+            sink,
+            completed: AtomicUsize::new(0),
+            total,
+            start: Instant::now(),
+            step: (total / NUM_REPORTS).max(1),
+        }
+    }
+
+    /// Marks one more texel as completed and reports the progress, if due.
+    #[inline]
+    pub fn advance(&self) {
+        self.advance_by(1);
+    }
+
+    /// Marks the given number of texels as completed and reports the progress, if due.
+    ///
+    /// # Arguments
+    /// * `num_texels` - The number of completed texels.
+    pub fn advance_by(&self, num_texels: usize) {
+        if num_texels == 0 {
+            return;
+        }
+
+        let previous = self.completed.fetch_add(num_texels, Ordering::Relaxed);
+        let completed = previous + num_texels;
+        if previous / self.step != completed / self.step || completed == self.total {
+            self.sink.report(&self.report(completed));
+        }
+    }
+
+    /// Reports the final progress to the sink.
+    pub fn finish(&self) {
+        let completed = self.completed.load(Ordering::Relaxed);
+        self.sink.finish(&self.report(completed));
+    }
+
+    /// Returns the report for the given number of completed texels.
+    ///
+    /// # Arguments
+    /// * `completed` - The number of completed texels.
+    fn report(&self, completed: usize) -> ProgressReport {
+        ProgressReport {
+            completed,
+            total: self.total,
+            elapsed: self.start.elapsed(),
         }
-        for _ in 0..num_spaces {
-            print!(" ");
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Mutex;
+
+    use super::*;
+
+    #[derive(Default)]
+    struct RecordingProgress {
+        reports: Mutex<Vec<ProgressReport>>,
+        finished: AtomicBool,
+    }
+
+    impl ProgressSink for RecordingProgress {
+        fn report(&self, report: &ProgressReport) {
+            self.reports.lock().unwrap().push(*report);
         }
-        print!("] {:.2}%", progress * 100.0);
+
+        fn finish(&self, _report: &ProgressReport) {
+            self.finished.store(true, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn test_progress_tracker() {
+        let sink = RecordingProgress::default();
+        let tracker = ProgressTracker::new(&sink, 3000);
+        (0..3000).for_each(|_| tracker.advance());
+        tracker.finish();
+
+        // the reports are throttled
+        let reports = sink.reports.lock().unwrap();
+        assert_eq!(reports.len(), NUM_REPORTS);
+        assert_eq!(reports.last().unwrap().completed, 3000);
+        assert_eq!(reports.last().unwrap().fraction(), 1f32);
+        assert!(sink.finished.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn test_progress_tracker_advance_by() {
+        let sink = RecordingProgress::default();
+        let tracker = ProgressTracker::new(&sink, 3000);
+        tracker.advance_by(0);
+        (0..1000).for_each(|_| tracker.advance_by(3));
+
+        // the reports are throttled, although the steps are skipped
+        let reports = sink.reports.lock().unwrap();
+        assert_eq!(reports.len(), NUM_REPORTS);
+        assert_eq!(reports.last().unwrap().completed, 3000);
+    }
+
+    #[test]
+    fn test_progress_report() {
+        let report = ProgressReport {
+            completed: 25,
+            total: 100,
+            elapsed: Duration::from_secs(10),
+        };
+
+        assert_eq!(report.fraction(), 0.25);
+        assert_eq!(report.throughput(), 2.5);
+        assert_eq!(report.eta(), Some(Duration::from_secs(30)));
+
+        let report = ProgressReport {
+            completed: 0,
+            ..report
+        };
+        assert_eq!(report.eta(), None);
     }
 }
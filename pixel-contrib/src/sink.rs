@@ -0,0 +1,160 @@
+use std::sync::{
+    atomic::{AtomicBool, AtomicUsize, Ordering},
+    Mutex,
+};
+
+use pixel_contrib_types::{PixelContribColorMapDescriptor, PixelContributionMap};
+
+use crate::{Error, Result};
+
+/// Receives each pixel contribution map as soon as all of its texels have been computed, e.g.,
+/// for writing the finished maps to a file, s.t. a crash does not lose all results.
+///
+/// The maps are finished in no particular order and the sink is called from the worker threads
+/// of the computation. With [`crate::DirectionSampling::Adaptive`], the texels are interpolated
+/// after all maps have been rendered, i.e., all maps are finished at the end of the computation.
+pub trait MapSink: Send + Sync {
+    /// Called once for each channel of each finished map. An error aborts the computation, which
+    /// then returns the error.
+    ///
+    /// # Arguments
+    /// * `channel` - The channel of the map, i.e., 0 for the maps of the whole scene and the
+    ///   index of the instance plus one for the maps of the individual objects.
+    /// * `map` - The finished pixel contribution map.
+    /// * `variance_map` - The variances of the jittered samples of each texel of the map.
+    fn map_finished(
+        &self,
+        channel: usize,
+        map: &PixelContributionMap,
+        variance_map: &PixelContributionMap,
+    ) -> Result<()>;
+}
+
+/// Collects the computed texels of each map and forwards each map to a sink once all of its
+/// texels have been computed.
+pub(crate) struct MapCollector<'a> {
+    /// The sink receiving the finished maps.
+    sink: &'a dyn MapSink,
+
+    /// The descriptors of the maps.
+    descriptors: &'a [PixelContribColorMapDescriptor],
+
+    /// The number of values of each texel.
+    num_channels: usize,
+
+    /// The values and the variances of all channels of each texel of each map, one texel after
+    /// the other.
+    texels: Vec<Mutex<Vec<(f32, f32)>>>,
+
+    /// The number of texels of each map, which have not been computed yet.
+    remaining: Vec<AtomicUsize>,
+
+    /// Whether the sink has returned an error.
+    failed: AtomicBool,
+
+    /// The first error returned by the sink.
+    error: Mutex<Option<Error>>,
+}
+
+impl<'a> MapCollector<'a> {
+    /// Creates a new collector for maps with the given descriptors.
+    ///
+    /// # Arguments
+    /// * `sink` - The sink receiving the finished maps.
+    /// * `descriptors` - The descriptors of the maps.
+    /// * `num_channels` - The number of values of each texel.
+    pub fn new(
+        sink: &'a dyn MapSink,
+        descriptors: &'a [PixelContribColorMapDescriptor],
+        num_channels: usize,
+    ) -> Self {
+        let texels = descriptors
+            .iter()
+            .map(|d| Mutex::new(vec![(0f32, 0f32); d.num_values() * num_channels]))
+            .collect();
+        let remaining = descriptors
+            .iter()
+            .map(|d| AtomicUsize::new(d.num_values()))
+            .collect();
+
+        Self {
+            sink,
+            descriptors,
+            num_channels,
+            texels,
+            remaining,
+            failed: AtomicBool::new(false),
+            error: Mutex::new(None),
+        }
+    }
+
+    /// Records the values of the given texel and passes its map to the sink, if the texel was the
+    /// last one of the map. Each texel must be recorded exactly once.
+    ///
+    /// # Arguments
+    /// * `map_index` - The index of the map.
+    /// * `index` - The index of the texel within the map.
+    /// * `values` - The values and the variances of all channels of the texel.
+    pub fn record(&self, map_index: usize, index: usize, values: &[(f32, f32)]) {
+        debug_assert_eq!(values.len(), self.num_channels);
+        let offset = index * self.num_channels;
+        self.texels[map_index].lock().unwrap()[offset..offset + self.num_channels]
+            .copy_from_slice(values);
+
+        if self.remaining[map_index].fetch_sub(1, Ordering::AcqRel) == 1 {
+            let texels = std::mem::take(&mut *self.texels[map_index].lock().unwrap());
+            let channel_values = |channel: usize| {
+                texels
+                    .iter()
+                    .skip(channel)
+                    .step_by(self.num_channels)
+                    .copied()
+                    .unzip()
+            };
+
+            for channel in 0..self.num_channels {
+                let (values, variances): (Vec<f32>, Vec<f32>) = channel_values(channel);
+                self.finish_map(map_index, channel, &values, &variances);
+            }
+        }
+    }
+
+    /// Passes the map with the given values to the sink, unless the sink has failed before.
+    ///
+    /// # Arguments
+    /// * `map_index` - The index of the map.
+    /// * `channel` - The channel of the map.
+    /// * `values` - The values of the map.
+    /// * `variances` - The variances of the values of the map.
+    pub fn finish_map(&self, map_index: usize, channel: usize, values: &[f32], variances: &[f32]) {
+        if self.has_failed() {
+            return;
+        }
+
+        let create_map = |values: &[f32]| {
+            let mut map = PixelContributionMap::new(self.descriptors[map_index]);
+            map.pixel_contrib.copy_from_slice(values);
+
+            map
+        };
+
+        if let Err(err) =
+            self.sink
+                .map_finished(channel, &create_map(values), &create_map(variances))
+        {
+            self.failed.store(true, Ordering::Relaxed);
+            self.error.lock().unwrap().get_or_insert(err);
+        }
+    }
+
+    /// Returns true if the sink has returned an error.
+    #[inline]
+    pub fn has_failed(&self) -> bool {
+        self.failed.load(Ordering::Relaxed)
+    }
+
+    /// Returns the first error returned by the sink, if any.
+    pub fn into_error(self) -> Option<Error> {
+        self.error.into_inner().unwrap()
+    }
+}
@@ -1,4 +1,5 @@
 use nalgebra_glm::{ortho, Mat4, Vec3};
+use pixel_contrib_types::CameraFrustum;
 use rasterizer::BoundingSphere;
 
 use crate::CameraConfig;
@@ -34,12 +35,41 @@ impl View {
         camera_config: CameraConfig,
         dir: Vec3,
     ) -> Self {
+        Self::new_from_sphere_at_distance(sphere, camera_config, dir, 0f32)
+    }
+
+    /// Creates a new view onto the given sphere with the camera placed at the given distance from
+    /// the center of the sphere. Unlike the fitted view, the sphere may be clipped by the frame.
+    /// Orthographic cameras ignore the distance.
+    ///
+    /// # Arguments
+    /// * `sphere` - The sphere that the view should look at.
+    /// * `camera_config` - The camera configuration for which the view should be created.
+    /// * `dir` - The direction of the camera, i.e, the directional vector which points toward the
+    ///   object.
+    /// * `distance` - The distance as multiple of the sphere radius. Must be larger than 1 or 0
+    ///   for the distance at which the sphere fits the frustum.
+    pub fn new_from_sphere_at_distance(
+        sphere: &BoundingSphere,
+        camera_config: CameraConfig,
+        dir: Vec3,
+        distance: f32,
+    ) -> Self {
+        assert!(
+            distance == 0f32 || distance > 1f32,
+            "distance must be 0 or larger than 1, but is {}",
+            distance
+        );
+
         let (projection_matrix, distance) = match camera_config {
             CameraConfig::Orthographic => {
                 Self::create_projection_matrix_for_orthographic_camera(sphere)
             }
             CameraConfig::Perspective { fovy } => {
-                Self::create_projection_matrix_for_perspective_camera(sphere, fovy)
+                Self::create_projection_matrix_for_perspective_camera(sphere, fovy, distance)
+            }
+            CameraConfig::Frustum(frustum) => {
+                Self::create_projection_matrix_for_frustum(sphere, &frustum, distance)
             }
         };
 
@@ -59,7 +89,16 @@ impl View {
             Vec3::new(0f32, 1f32, 0f32)
         };
 
-        let view_matrix = nalgebra_glm::look_at(&camera_center, &sphere.center, &up);
+        let mut view_matrix = nalgebra_glm::look_at(&camera_center, &sphere.center, &up);
+
+        // off-axis frusta see the sphere along the direction to the center of their frame
+        if let CameraConfig::Frustum(frustum) = camera_config {
+            let rotation = nalgebra_glm::quat_rotation(
+                &Vec3::new(0f32, 0f32, -1f32),
+                &frustum.sphere_direction(),
+            );
+            view_matrix = nalgebra_glm::quat_to_mat4(&rotation) * view_matrix;
+        }
 
         Self {
             view_matrix,
@@ -74,9 +113,11 @@ impl View {
     /// # Arguments
     /// * `sphere` - The sphere that the view should fit.
     /// * `fovy` - The field of view in y-direction in radians.
+    /// * `distance` - The distance as multiple of the sphere radius or 0 for fitting the sphere.
     fn create_projection_matrix_for_perspective_camera(
         sphere: &BoundingSphere,
         fovy: f32,
+        distance: f32,
     ) -> (Mat4, f32) {
         assert!(
             fovy > 0f32 && fovy < std::f32::consts::PI,
@@ -84,19 +125,95 @@ impl View {
         );
 
         // determine the distance between the camera and the center of the sphere s.t. it fits
-        // perfectly into it
-        let distance = sphere.radius / (fovy / 2f32).sin();
+        // perfectly into it, unless the distance is given
+        let distance = if distance > 0f32 {
+            sphere.radius * distance
+        } else {
+            sphere.radius / (fovy / 2f32).sin()
+        };
 
         // far plane is at the back of the sphere
         let far = distance + sphere.radius;
 
         // near plane is at the front of the sphere
-        // NOTE: near is positive as long as fovy < 180°
+        // NOTE: near is positive as long as the camera is outside of the sphere
         let near = distance - sphere.radius;
 
         (nalgebra_glm::perspective(1f32, fovy, near, far), distance)
     }
 
+    /// Creates a projection matrix for the given frustum s.t. the sphere fits into it when being
+    /// placed along [`CameraFrustum::sphere_direction`]. Returns the projection matrix and the
+    /// distance between the camera and the center of the bounding sphere.
+    ///
+    /// # Arguments
+    /// * `sphere` - The sphere that the view should fit.
+    /// * `frustum` - The frustum of the camera.
+    /// * `distance` - The distance as multiple of the sphere radius or 0 for fitting the sphere.
+    fn create_projection_matrix_for_frustum(
+        sphere: &BoundingSphere,
+        frustum: &CameraFrustum,
+        distance: f32,
+    ) -> (Mat4, f32) {
+        assert!(frustum.is_valid(), "frustum must be valid");
+
+        let radius = sphere.radius;
+        if frustum.is_orthographic() {
+            // the sphere touches the shorter sides of the frame
+            let aspect_ratio = frustum.aspect_ratio();
+            let half_width = radius * aspect_ratio.max(1f32);
+            let half_height = half_width / aspect_ratio;
+
+            return (
+                ortho(
+                    -half_width,
+                    half_width,
+                    -half_height,
+                    half_height,
+                    radius,
+                    radius * 4f32,
+                ),
+                radius * 2f32,
+            );
+        }
+
+        let distance = if distance > 0f32 {
+            radius * distance
+        } else {
+            radius / (frustum.sphere_angle() / 2f32).sin()
+        };
+
+        // the near and far plane enclose the sphere along the view axis
+        let (left, right) = frustum.horizontal_half_angles();
+        let (bottom, top) = frustum.vertical_half_angles();
+        let depth = distance * -frustum.sphere_direction().z;
+        let far = depth + radius;
+
+        // Close to the sphere, its front may lie behind the camera. However, no point of the
+        // sphere inside of the frustum is closer to the camera plane than its distance to the
+        // camera times the cosine of the angle between the view axis and the corners of the frame.
+        let tan_x = left.tan().abs().max(right.tan().abs());
+        let tan_y = bottom.tan().abs().max(top.tan().abs());
+        let min_near = (distance - radius) / (1f32 + tan_x * tan_x + tan_y * tan_y).sqrt();
+        let near = (depth - radius).max(min_near);
+        assert!(near > 0f32, "the camera must lie outside of the sphere");
+
+        // see the definition of glFrustum
+        let (l, r) = (-near * left.tan(), near * right.tan());
+        let (b, t) = (-near * bottom.tan(), near * top.tan());
+
+        let mut projection = Mat4::zeros();
+        projection[(0, 0)] = 2f32 * near / (r - l);
+        projection[(0, 2)] = (r + l) / (r - l);
+        projection[(1, 1)] = 2f32 * near / (t - b);
+        projection[(1, 2)] = (t + b) / (t - b);
+        projection[(2, 2)] = -(far + near) / (far - near);
+        projection[(2, 3)] = -2f32 * far * near / (far - near);
+        projection[(3, 2)] = -1f32;
+
+        (projection, distance)
+    }
+
     /// Creates a projection matrix for an orthographic camera that fits the given sphere.
     ///
     /// # Arguments
@@ -114,3 +231,132 @@ impl View {
         )
     }
 }
+
+#[cfg(test)]
+mod test {
+    use nalgebra_glm::{vec4_to_vec3, Vec4};
+
+    use super::*;
+
+    /// Returns the fraction of the frame covered by the sphere by casting a grid of rays.
+    fn estimate_coverage(view: &View, sphere: &BoundingSphere) -> f32 {
+        let inv = nalgebra_glm::inverse(&(view.projection_matrix * view.view_matrix));
+        let unproject = |x: f32, y: f32, z: f32| {
+            let p = inv * Vec4::new(x, y, z, 1f32);
+            vec4_to_vec3(&p) / p.w
+        };
+
+        let n = 200;
+        let mut num_hits = 0;
+        for i in 0..n {
+            for j in 0..n {
+                let x = (i as f32 + 0.5) / n as f32 * 2f32 - 1f32;
+                let y = (j as f32 + 0.5) / n as f32 * 2f32 - 1f32;
+
+                let origin = unproject(x, y, -1f32);
+                let dir = (unproject(x, y, 1f32) - origin).normalize();
+                let to_center = sphere.center - origin;
+                let dist = (to_center - dir * to_center.dot(&dir)).norm();
+                if dist <= sphere.radius {
+                    num_hits += 1;
+                }
+            }
+        }
+
+        num_hits as f32 / (n * n) as f32
+    }
+
+    #[test]
+    fn test_view_for_off_axis_frustum() {
+        let sphere = BoundingSphere {
+            center: Vec3::new(1f32, 2f32, 3f32),
+            radius: 0.5f32,
+        };
+        let dir = Vec3::new(0.3f32, -0.5f32, 0.8f32);
+
+        let frustums = [
+            CameraFrustum::perspective(60f32.to_radians(), 1f32),
+            CameraFrustum::perspective(60f32.to_radians(), 16f32 / 9f32),
+            CameraFrustum::off_axis(
+                10f32.to_radians(),
+                50f32.to_radians(),
+                20f32.to_radians(),
+                35f32.to_radians(),
+            ),
+            CameraFrustum::orthographic(0.5f32),
+        ];
+
+        for frustum in frustums {
+            let camera_config = CameraConfig::Frustum(frustum);
+            let view = View::new_from_sphere(&sphere, camera_config, dir);
+
+            // the center of the sphere is projected onto the center of the frame
+            let center = view.projection_matrix
+                * view.view_matrix
+                * Vec4::new(sphere.center.x, sphere.center.y, sphere.center.z, 1f32);
+            assert!(center.x.abs() / center.w < 1e-4);
+            assert!(center.y.abs() / center.w < 1e-4);
+            assert!((center.z / center.w).abs() < 1f32);
+
+            let coverage = estimate_coverage(&view, &sphere);
+            assert!(
+                (coverage - camera_config.sphere_coverage()).abs() < 0.01,
+                "{}: {} != {}",
+                camera_config.to_string(),
+                coverage,
+                camera_config.sphere_coverage()
+            );
+        }
+
+        let coverage = CameraConfig::Frustum(frustums[0]).sphere_coverage();
+        assert!((coverage - std::f32::consts::PI / 4f32).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_view_at_distance() {
+        let sphere = BoundingSphere {
+            center: Vec3::new(-1f32, 0.5f32, 2f32),
+            radius: 2f32,
+        };
+        let dir = Vec3::new(-0.4f32, 0.7f32, 0.2f32);
+
+        let camera_configs = [
+            CameraConfig::Perspective {
+                fovy: 60f32.to_radians(),
+            },
+            CameraConfig::Frustum(CameraFrustum::perspective(50f32.to_radians(), 1.5f32)),
+            CameraConfig::Frustum(CameraFrustum::off_axis(
+                10f32.to_radians(),
+                50f32.to_radians(),
+                20f32.to_radians(),
+                35f32.to_radians(),
+            )),
+        ];
+
+        for camera_config in camera_configs {
+            for distance in [1.05f32, 1.5f32, 3f32, 10f32] {
+                let view = View::new_from_sphere_at_distance(&sphere, camera_config, dir, distance);
+
+                // the camera is placed at the given distance
+                let camera_pos = math::extract_camera_position(&view.view_matrix).unwrap();
+                let d = nalgebra_glm::distance(&camera_pos, &sphere.center) / sphere.radius;
+                assert!((d - distance).abs() < 1e-3, "{} != {}", d, distance);
+
+                let coverage = estimate_coverage(&view, &sphere);
+                let expected = camera_config.sphere_coverage_at_distance(distance);
+                assert!(
+                    (coverage - expected).abs() < 0.01,
+                    "{} at distance {}: {} != {}",
+                    camera_config.to_string(),
+                    distance,
+                    coverage,
+                    expected
+                );
+            }
+        }
+
+        // close to the sphere, the frame is filled completely
+        let camera_config = camera_configs[0];
+        assert!(camera_config.sphere_coverage_at_distance(1.05f32) > 0.999f32);
+    }
+}
@@ -30,6 +30,13 @@ pub struct RenderStats {
     /// The number of triangles processed, i.e., that could not be avoided through acceleration
     /// structures or other means.
     pub num_triangles: usize,
+
+    /// The number of rendered frames.
+    pub num_frames: usize,
+
+    /// The number of frames whose rendering has been avoided, e.g., by interpolating between the
+    /// results of rendered frames.
+    pub num_frames_saved: usize,
 }
 
 impl std::ops::Add<Self> for RenderStats {
@@ -38,6 +45,8 @@ impl std::ops::Add<Self> for RenderStats {
     fn add(self, rhs: Self) -> Self::Output {
         Self {
             num_triangles: self.num_triangles + rhs.num_triangles,
+            num_frames: self.num_frames + rhs.num_frames,
+            num_frames_saved: self.num_frames_saved + rhs.num_frames_saved,
         }
     }
 }
@@ -45,6 +54,8 @@ impl std::ops::Add<Self> for RenderStats {
 impl std::ops::AddAssign<Self> for RenderStats {
     fn add_assign(&mut self, rhs: Self) {
         self.num_triangles += rhs.num_triangles;
+        self.num_frames += rhs.num_frames;
+        self.num_frames_saved += rhs.num_frames_saved;
     }
 }
 
@@ -73,6 +84,9 @@ pub trait Renderer: Send {
     /// The optimized geometry used for rendering.
     type G: RendererGeometry;
 
+    /// The name of the renderer.
+    const NAME: &'static str;
+
     /// Creates and returns a new renderer instance.
     ///
     /// # Arguments
@@ -80,7 +94,9 @@ pub trait Renderer: Send {
     fn new(stats: StatsNode) -> Self;
 
     /// Returns the name of the renderer
-    fn get_name(&self) -> &str;
+    fn get_name(&self) -> &str {
+        Self::NAME
+    }
 
     /// Initializes the renderer with the given frame size.
     ///
@@ -23,6 +23,8 @@ pub struct SimpleRasterizer {
 impl Renderer for SimpleRasterizer {
     type G = SimpleRasterizerGeometry;
 
+    const NAME: &'static str = "Simple Rasterizer";
+
     fn new(stats: StatsNode) -> Self {
         Self {
             stats,
@@ -30,10 +32,6 @@ impl Renderer for SimpleRasterizer {
         }
     }
 
-    fn get_name(&self) -> &str {
-        "Simple Rasterizer"
-    }
-
     fn initialize(&mut self, options: RenderOptions) -> Result<()> {
         info!(
             "Initialize simple rasterizer with size {}x{}",
@@ -54,7 +52,10 @@ impl Renderer for SimpleRasterizer {
         projection_matrix: nalgebra_glm::Mat4,
     ) -> RenderStats {
         let _t = self.stats.register_timing();
-        let mut stats: RenderStats = Default::default();
+        let mut stats = RenderStats {
+            num_frames: 1,
+            ..Default::default()
+        };
 
         let pages = geo.pages.as_slice();
 
@@ -3,7 +3,7 @@ mod geometry;
 
 pub use compressed::*;
 pub use geometry::*;
-use math::{transform_vec3, Aabb, BoundingSphere};
+use math::{fit_bounding_sphere, transform_vec3, Aabb, BoundingSphere, BoundingSphereAlgorithm};
 
 use std::{collections::HashMap, path::Path};
 
@@ -123,6 +123,33 @@ impl Scene {
         BoundingSphere::from((center, radius.sqrt()))
     }
 
+    /// Computes and returns the bounding sphere for the given scene using the given algorithm.
+    ///
+    /// # Arguments
+    /// * `algorithm` - The algorithm for fitting the sphere to the vertices of all instances.
+    pub fn compute_bounding_sphere_with(
+        &self,
+        algorithm: BoundingSphereAlgorithm,
+    ) -> BoundingSphere {
+        if algorithm == BoundingSphereAlgorithm::AabbCenter {
+            return self.compute_bounding_sphere();
+        }
+
+        let geometries = &self.geometries;
+        let positions: Vec<Vec3> = self
+            .instances
+            .iter()
+            .flat_map(|instance| {
+                geometries[instance.geometry_index]
+                    .positions
+                    .iter()
+                    .map(|p| transform_vec3(&instance.transform, p))
+            })
+            .collect();
+
+        fit_bounding_sphere(&positions, algorithm)
+    }
+
     /// Prints statistics about the loaded scene.
     pub fn print_scene_stats(&self) {
         let mut num_vertices = 0usize;
@@ -146,7 +146,10 @@ impl<'a> PageReferences<'a> {
                     );
 
                     // append the local object id for each triangle
-                    local_object_ids.resize(chunk.geo_slice.triangle_range.len(), local_id);
+                    local_object_ids.resize(
+                        local_object_ids.len() + chunk.geo_slice.triangle_range.len(),
+                        local_id,
+                    );
 
                     // append triangles to the page
                     let chunk_vertex_offset = chunk.geo_slice.vertex_range.start as u32;
@@ -1,4 +1,7 @@
-use nalgebra_glm::{Mat2, Vec2};
+use pixel_contrib_types::{
+    AngleInterpolator, Extrapolation, LinearAngleInterpolator, PchipAngleInterpolator,
+    QuadraticAngleInterpolator, TangentAngleInterpolator,
+};
 
 use crate::{PixelContributionMap, PixelContributionMaps};
 
@@ -8,15 +11,17 @@ use wasm_bindgen::prelude::*;
 /// last pixel contribution map using the angle.
 #[wasm_bindgen]
 pub struct LinearAngle {
-    first_map: PixelContributionMap,
-    last_map: PixelContributionMap,
+    inner: SubsetInterpolator,
 }
 
 #[wasm_bindgen]
 impl LinearAngle {
     /// Creates a new linear pixel contribution interpolator from the given pixel contribution maps.
+    /// Only the maps at the camera distance fitting the frustum are used.
     #[wasm_bindgen(constructor)]
     pub fn new(contrib_maps: &PixelContributionMaps) -> LinearAngle {
+        // the maps at other camera distances share the angles of the fitted maps
+        let contrib_maps = &contrib_maps.filter_distance(0f32);
         let n = contrib_maps.size();
 
         assert!(
@@ -24,29 +29,18 @@ impl LinearAngle {
             "At least 2 pixel contribution maps are required for the linear interpolator."
         );
 
-        let first_map = contrib_maps.get_map(0);
-        let last_map = contrib_maps.get_map(n - 1);
-
         LinearAngle {
-            first_map,
-            last_map,
+            inner: SubsetInterpolator::new(
+                contrib_maps,
+                &[0, n - 1],
+                LinearAngleInterpolator,
+                Extrapolation::Extend,
+            ),
         }
     }
 
     pub fn interpolate(&self, angle: f32, pos_x: usize, pos_y: usize) -> f32 {
-        let first_map = &self.first_map;
-        let last_map = &self.last_map;
-
-        let first_angle = first_map.get_description().camera_angle;
-        let last_angle = last_map.get_description().camera_angle;
-
-        let index = pos_y * first_map.get_description().map_size + pos_x;
-
-        let first_value = first_map.get_value_at_index(index);
-        let last_value = last_map.get_value_at_index(index);
-
-        let f = (angle - first_angle) / (last_angle - first_angle);
-        first_value * (1f32 - f) + last_value * f
+        self.inner.interpolate(angle, pos_x, pos_y)
     }
 }
 
@@ -56,23 +50,26 @@ impl LinearAngle {
 /// to interpolate the pixel contributions.
 #[wasm_bindgen]
 pub struct TangentAngle {
-    first_map: PixelContributionMap,
-    last_map: PixelContributionMap,
+    inner: SubsetInterpolator,
 }
 
 #[wasm_bindgen]
 impl TangentAngle {
     /// Creates a new linear pixel contribution interpolator from the given pixel contribution maps.
+    /// Only the maps at the camera distance fitting the frustum are used.
     #[wasm_bindgen(constructor)]
     pub fn new(contrib_maps: &PixelContributionMaps) -> TangentAngle {
+        // the maps at other camera distances share the angles of the fitted maps
+        let contrib_maps = &contrib_maps.filter_distance(0f32);
         let n = contrib_maps.size();
 
-        let first_map = contrib_maps.get_map(0);
-        let last_map = contrib_maps.get_map(n - 1);
-
         TangentAngle {
-            first_map,
-            last_map,
+            inner: SubsetInterpolator::new(
+                contrib_maps,
+                &[0, n - 1],
+                TangentAngleInterpolator,
+                Extrapolation::Extend,
+            ),
         }
     }
 
@@ -81,24 +78,7 @@ impl TangentAngle {
     }
 
     pub fn interpolate(&self, angle: f32, pos_x: usize, pos_y: usize) -> f32 {
-        let first_map = &self.first_map;
-        let last_map = &self.last_map;
-
-        let first_angle = first_map.get_description().camera_angle;
-        let last_angle = last_map.get_description().camera_angle;
-
-        let index = pos_y * first_map.get_description().map_size + pos_x;
-
-        let first_value = first_map.get_value_at_index(index);
-        let last_value = last_map.get_value_at_index(index);
-
-        let a_start = (first_angle / 2.0).tan();
-        let a_last = (last_angle / 2.0).tan();
-
-        let a = (angle / 2.0).tan();
-        let t = (a - a_start) / (a_last - a_start);
-
-        first_value * (1f32 - t) + last_value * t
+        self.inner.interpolate(angle, pos_x, pos_y)
     }
 }
 
@@ -106,18 +86,17 @@ impl TangentAngle {
 /// using the first, middle and last pixel contribution map based on the angle as input.
 #[wasm_bindgen]
 pub struct QuadraticAngle {
-    first_map: PixelContributionMap,
-    middle_map: PixelContributionMap,
-    last_map: PixelContributionMap,
-
-    mat: Mat2,
+    inner: SubsetInterpolator,
 }
 
 #[wasm_bindgen]
 impl QuadraticAngle {
     /// Creates a new quadratic pixel contribution interpolator from the given pixel contribution maps.
+    /// Only the maps at the camera distance fitting the frustum are used.
     #[wasm_bindgen(constructor)]
     pub fn new(contrib_maps: &PixelContributionMaps) -> QuadraticAngle {
+        // the maps at other camera distances share the angles of the fitted maps
+        let contrib_maps = &contrib_maps.filter_distance(0f32);
         let n = contrib_maps.size();
 
         assert!(
@@ -125,24 +104,13 @@ impl QuadraticAngle {
             "At least 3 pixel contribution maps are required for the quadratic interpolator."
         );
 
-        let first_map = contrib_maps.get_map(0);
-        let middle_map = contrib_maps.get_map(n / 2);
-        let last_map = contrib_maps.get_map(n - 1);
-
-        let x0 = first_map.get_description().camera_angle;
-        let x1 = middle_map.get_description().camera_angle;
-        let x2 = last_map.get_description().camera_angle;
-
-        assert_eq!(x0, 0f32, "The first angle must be 0");
-        assert!(x0 < x1 && x1 < x2, "The angles are not in ascending order");
-
-        let mat = Mat2::new(x1 * x1, x1, x2 * x2, x2).try_inverse().unwrap();
-
         QuadraticAngle {
-            first_map,
-            middle_map,
-            last_map,
-            mat,
+            inner: SubsetInterpolator::new(
+                contrib_maps,
+                &[0, n / 2, n - 1],
+                QuadraticAngleInterpolator,
+                Extrapolation::Extend,
+            ),
         }
     }
 
@@ -151,23 +119,95 @@ impl QuadraticAngle {
     }
 
     pub fn interpolate(&self, angle: f32, pos_x: usize, pos_y: usize) -> f32 {
-        let first_map = &self.first_map;
-        let middle_map = &self.middle_map;
-        let last_map = &self.last_map;
+        self.inner.interpolate(angle, pos_x, pos_y)
+    }
+}
 
-        let index = pos_y * first_map.get_description().map_size + pos_x;
+/// Interpolates the pixel contribution using a subset of the pixel contribution maps, such that
+/// the interpolation error can be measured at the angles of the remaining maps.
+struct SubsetInterpolator {
+    maps: Vec<PixelContributionMap>,
+    interpolator: Box<dyn AngleInterpolator>,
+    extrapolation: Extrapolation,
+}
 
-        let y0 = first_map.get_value_at_index(index);
-        let y1 = middle_map.get_value_at_index(index);
-        let y2 = last_map.get_value_at_index(index);
+impl SubsetInterpolator {
+    /// Creates a new interpolator using the maps at the given indices.
+    ///
+    /// # Arguments
+    /// * `contrib_maps` - The pixel contribution maps to select the subset from.
+    /// * `indices` - The indices of the selected maps in ascending order.
+    /// * `interpolator` - The interpolator used for the selected maps.
+    /// * `extrapolation` - Determines how angles outside of the range of the selected maps are
+    ///   handled.
+    fn new<I: AngleInterpolator + 'static>(
+        contrib_maps: &PixelContributionMaps,
+        indices: &[usize],
+        interpolator: I,
+        extrapolation: Extrapolation,
+    ) -> Self {
+        let maps = indices.iter().map(|i| contrib_maps.get_map(*i)).collect();
+
+        Self {
+            maps,
+            interpolator: Box::new(interpolator),
+            extrapolation,
+        }
+    }
 
-        // determine the polynomial coefficients a,b,c
-        let c = y0; // as x0 = 0
-        let rhs = Vec2::new(y1 - c, y2 - c);
-        let coefficients = self.mat * rhs;
-        let a = coefficients[0];
-        let b = coefficients[1];
+    fn interpolate(&self, angle: f32, pos_x: usize, pos_y: usize) -> f32 {
+        let index = pos_y * self.maps[0].get_description().map_size + pos_x;
 
-        a * angle * angle + b * angle + c
+        self.interpolator.interpolate_with_extrapolation(
+            angle,
+            self.maps.len(),
+            &|i| self.maps[i].inner.descriptor.camera_angle(),
+            &|i| self.maps[i].get_value_at_index(index),
+            self.extrapolation,
+        )
+    }
+}
+
+/// A monotone cubic interpolator (PCHIP) for pixel contributions that interpolates using the
+/// first, middle and last pixel contribution map based on the angle as input.
+/// In contrast to the quadratic interpolator, the result never overshoots the values of the maps
+/// and angles outside of the range of the maps are clamped.
+#[wasm_bindgen]
+pub struct PchipAngle {
+    inner: SubsetInterpolator,
+}
+
+#[wasm_bindgen]
+impl PchipAngle {
+    /// Creates a new monotone cubic pixel contribution interpolator from the given pixel
+    /// contribution maps.
+    /// Only the maps at the camera distance fitting the frustum are used.
+    #[wasm_bindgen(constructor)]
+    pub fn new(contrib_maps: &PixelContributionMaps) -> PchipAngle {
+        // the maps at other camera distances share the angles of the fitted maps
+        let contrib_maps = &contrib_maps.filter_distance(0f32);
+        let n = contrib_maps.size();
+
+        assert!(
+            n >= 3,
+            "At least 3 pixel contribution maps are required for the PCHIP interpolator."
+        );
+
+        PchipAngle {
+            inner: SubsetInterpolator::new(
+                contrib_maps,
+                &[0, n / 2, n - 1],
+                PchipAngleInterpolator,
+                Extrapolation::Clamp,
+            ),
+        }
+    }
+
+    pub fn get_name(&self) -> String {
+        "PCHIP".to_string()
+    }
+
+    pub fn interpolate(&self, angle: f32, pos_x: usize, pos_y: usize) -> f32 {
+        self.inner.interpolate(angle, pos_x, pos_y)
     }
 }
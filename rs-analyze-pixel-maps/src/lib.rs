@@ -36,7 +36,8 @@ impl PixelContributionMap {
         let descriptor = pixel_contrib_types::PixelContribColorMapDescriptor::new(
             descriptor.map_size,
             descriptor.camera_angle,
-        );
+        )
+        .with_distance(descriptor.distance);
 
         let values = values.to_vec();
 
@@ -44,6 +45,7 @@ impl PixelContributionMap {
             inner: Rc::new(pixel_contrib_types::PixelContributionMap {
                 descriptor,
                 pixel_contrib: values,
+                mip_levels: Vec::new(),
             }),
         }
     }
@@ -61,6 +63,17 @@ impl PixelContributionMap {
         self.inner.pixel_contrib[index]
     }
 
+    /// Returns the element-wise absolute difference to the given map. The given map is
+    /// resampled, if its size differs.
+    ///
+    /// # Arguments
+    /// * `other` - The map to compare with.
+    pub fn abs_difference(&self, other: &PixelContributionMap) -> PixelContributionMap {
+        Self {
+            inner: Rc::new(self.inner.abs_difference(&other.inner)),
+        }
+    }
+
     /// Returns the pixel contribution values as image data with the given scale.
     ///
     /// # Arguments
@@ -212,6 +225,42 @@ impl PixelContributionMaps {
         result
     }
 
+    /// Returns the distinct camera distances of the pixel contribution maps in ascending order.
+    /// A distance of 0 denotes the maps computed at the distance fitting the camera frustum.
+    pub fn get_distances(&self) -> Float32Array {
+        let mut distances: Vec<f32> = self
+            .inner
+            .borrow()
+            .iter()
+            .map(|map| map.inner.descriptor.distance())
+            .collect();
+        distances.sort_by(f32::total_cmp);
+        distances.dedup();
+
+        Float32Array::from(distances.as_slice())
+    }
+
+    /// Returns a shallow copy of this PixelContributionMaps object, which only contains the pixel
+    /// contribution maps computed at the given camera distance. The maps of a single distance
+    /// have distinct camera angles, s.t. they can be interpolated w.r.t the angle.
+    ///
+    /// # Arguments
+    /// * `distance` - The camera distance as multiple of the sphere radius or 0 for the distance
+    ///   fitting the camera frustum.
+    pub fn filter_distance(&self, distance: f32) -> PixelContributionMaps {
+        let inner = self
+            .inner
+            .borrow()
+            .iter()
+            .filter(|map| map.inner.descriptor.distance() == distance)
+            .cloned()
+            .collect();
+
+        Self {
+            inner: RefCell::new(inner),
+        }
+    }
+
     /// Adds a PixelContributionMap object to this PixelContributionMaps object.
     ///
     /// # Arguments
@@ -231,6 +280,11 @@ pub struct PixelContribColorMapDescriptor {
     /// The camera angle for the pixel contribution map. The angle is in radians.
     /// A value of 0 means that the camera is orthographic.
     pub camera_angle: f32,
+
+    /// The distance between the camera and the center of the bounding sphere as multiple of the
+    /// sphere radius. A value of 0 means that the camera is placed where the sphere fits the
+    /// frustum.
+    pub distance: f32,
 }
 
 impl From<pixel_contrib_types::PixelContribColorMapDescriptor> for PixelContribColorMapDescriptor {
@@ -238,6 +292,7 @@ impl From<pixel_contrib_types::PixelContribColorMapDescriptor> for PixelContribC
         Self {
             map_size: descriptor.size(),
             camera_angle: descriptor.camera_angle(),
+            distance: descriptor.distance(),
         }
     }
 }
@@ -82,17 +82,42 @@ impl ViewerImpl {
             .unwrap_or_default();
         info!("Map Size: {}", size);
 
-        let mut supported_angles: String = String::new();
-        p.get_maps().iter().for_each(|p| {
-            let angle = p.descriptor.camera_angle().to_degrees();
-            supported_angles = if supported_angles.is_empty() {
-                format!("{}", angle)
+        let mut distances: Vec<f32> = p
+            .get_maps()
+            .iter()
+            .map(|p| p.descriptor.distance())
+            .collect();
+        distances.sort_by(f32::total_cmp);
+        distances.dedup();
+
+        // the maps at other camera distances share the angles of the fitted maps
+        for distance in distances {
+            let mut supported_angles: String = String::new();
+            p.get_maps()
+                .iter()
+                .filter(|p| p.descriptor.distance() == distance)
+                .for_each(|p| {
+                    let angle = p.descriptor.camera_angle().to_degrees();
+                    supported_angles = if supported_angles.is_empty() {
+                        format!("{}", angle)
+                    } else {
+                        format!("{}, {}", supported_angles, angle)
+                    };
+                });
+
+            if distance > 0f32 {
+                info!(
+                    "Supported Angles (in degree) at distance {}: {}",
+                    distance, supported_angles
+                );
             } else {
-                format!("{}, {}", supported_angles, angle)
-            };
-        });
+                info!("Supported Angles (in degree): {}", supported_angles);
+            }
+        }
 
-        info!("Supported Angles (in degree): {}", supported_angles);
+        for (key, value) in p.metadata().iter() {
+            info!("Metadata {}: {}", key, value);
+        }
     }
 }
 
@@ -253,9 +278,10 @@ impl EventHandler for ViewerImpl {
                         let contrib_map = &self.pixel_contrib.get_maps().get_maps()
                             [self.current_contrib_map_index];
                         info!(
-                            "Switched to pixel contribution map (i={}, angle={:?})",
+                            "Switched to pixel contribution map (i={}, angle={:?}, distance={})",
                             self.current_contrib_map_index,
-                            contrib_map.descriptor.camera_angle().to_degrees()
+                            contrib_map.descriptor.camera_angle().to_degrees(),
+                            contrib_map.descriptor.distance()
                         );
                     }
                 }
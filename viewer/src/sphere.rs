@@ -3,7 +3,9 @@ use std::mem::size_of;
 use anyhow::Result;
 use log::info;
 use nalgebra_glm::Mat4;
-use pixel_contrib_types::PixelContributionMaps;
+use pixel_contrib_types::{
+    PixelContributionMap, PixelContributionMaps, SamplingMode, SphereParameterization,
+};
 use render_lib::{
     Attribute, AttributeBlock, Bind, DataType, DrawCall, Filtering, GPUBuffer, GPUBufferType,
     IndexData, PrimitiveType, Shader, Texture, TextureData, TextureDescriptor, Uniform,
@@ -72,6 +74,8 @@ impl Sphere {
 
         // initialize texture
         for pixel_contrib in pixel_contrib_maps.get_maps() {
+            // the shader expects the octahedral parameterization
+            let pixel_contrib = &Self::to_octahedral(pixel_contrib);
             let pixel_contrib_data: &[u8] = unsafe {
                 std::slice::from_raw_parts(
                     pixel_contrib.pixel_contrib.as_ptr() as *const u8,
@@ -118,6 +122,33 @@ impl Sphere {
         Ok(())
     }
 
+    /// Converts the given map into the octahedral parameterization used by the shader.
+    ///
+    /// # Arguments
+    /// * `pixel_contrib` - The pixel contribution map to convert.
+    fn to_octahedral(pixel_contrib: &PixelContributionMap) -> PixelContributionMap {
+        let descriptor = &pixel_contrib.descriptor;
+        if descriptor.parameterization() == SphereParameterization::Octahedral {
+            return pixel_contrib.clone();
+        }
+
+        let mut result = PixelContributionMap::new(
+            descriptor.with_parameterization(SphereParameterization::Octahedral),
+        );
+
+        let octahedral = result.descriptor;
+        result
+            .pixel_contrib
+            .iter_mut()
+            .enumerate()
+            .for_each(|(i, p)| {
+                let dir = octahedral.camera_dir_from_index(i);
+                *p = pixel_contrib.sample_pixel_contrib_for_camera_dir(dir, SamplingMode::Bilinear);
+            });
+
+        result
+    }
+
     /// Renders the sphere
     ///
     /// # Arguments